@@ -1,64 +1,310 @@
 // Input : https://github.com/EloiStree/2024_08_29_ScratchToWarcraft
 
+use std::env;
 use std::io;
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
-use rand::seq::SliceRandom;
-use wowint::utility::{EnumWowKey, WowWindowKeyInt};
+use wowint::utility::WowWindowKeyInt;
+use wowint::utility::panic_stop::{emergency_release_all, release_all_on_ctrl_c};
+use wowint::utility::describe::describe;
+use wowint::utility::error::WowIntError;
+use wowint::utility::http::WowIntegerHttpServer;
+use wowint::utility::metrics::{IntegerMetrics, WowIntegerMetricsServer};
+use wowint::utility::midi::{play_midi_file, play_midi_stream, MidiFile, MidiMapping, MidiTranslator};
+use wowint::utility::mqtt::{forward_mqtt_integers, MqttClient, MqttPublishingSender, DEFAULT_TOPIC_PREFIX};
+use wowint::utility::osc::{OscMapping, WowIntegerOscListener};
+use wowint::utility::pcap::{PcapReader, PcapRecorder, PcapRecordingSender};
+use wowint::utility::pipe::run_pipe;
+use wowint::utility::record::{replay_stream, RecordingSender, ReplayOptions, SessionRecorder};
+use wowint::utility::relay::WowIntegerRelay;
+use wowint::utility::session::{convert_session_file, SessionReader};
+use wowint::utility::websocket::WowIntegerWebSocketBridge;
 use wowint::utility::{
     WowIntegerTarget,
-    IntegerUdpSender,
+    IntegerSender,
     get_random_integer_between,
     get_random_integer_from_list
 };
 
+const USAGE: &str = "Usage:
+  wowint                                      Run the random key demo on 192.168.1.37:7073 index 2
+  wowint demo <ip> <port> <index>             Run the random key demo on the given target
+  wowint listen <listen>                      Print the integers received, e.g. \"player 2: press Space (VK 0x20)\"
+  wowint ping <ip> <port> [count]             Measure the round trip and clock offset to a listener or relay
+  wowint relay <listen> <rules|-> <ip:port>...  Forward integers through a rules file to targets
+  wowint relay-metrics <listen> <metrics-listen> <rules|-> <ip:port>...  Relay and serve Prometheus metrics
+  wowint panic-stop <ip> <port> <index>...    Release every key and gamepad input of the players
+  wowint websocket <listen> <ip> <port> <index> [origin...]  Forward integers received from browsers
+  wowint http <listen> <ip> <port>            Serve the HTTP endpoint sending to the target
+  wowint osc <listen> <ip> <port> <index> [mapping]  Translate OSC messages to integers
+  wowint midi <file.mid|-> <mapping> <ip> <port> <index>  Play a MIDI file, or raw MIDI bytes from stdin
+  wowint mqtt <broker> <ip> <port> [republish-prefix]  Forward wowint/<index>/integer messages to the target
+  wowint pipe <ip> <port> <index>             Forward commands read from stdin (42, 2 1032, tap Space 200ms)
+  wowint record <listen> <file> <ip:port>     Forward received integers to the target and record them (.jsonl or binary)
+  wowint replay <file> <ip> <port> [index|-] [speed] [max-pause-ms]  Replay a recorded session
+  wowint convert-session <input> <output>     Convert a session, .jsonl is JSON Lines, other extensions binary
+  wowint pcap-record <listen> <file.pcap> <ip:port>  Forward received integers to the target and capture them
+  wowint pcap-read <file.pcap> [port]         Print the integers of a capture, described";
 
 
 fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        None => run_demo("192.168.1.37", 7073, 2),
+        Some("demo") if args.len() == 4 => {
+            let port = parse_arg(&args[2], "port")?;
+            let index = parse_arg(&args[3], "index")?;
+            run_demo(&args[1], port, index)
+        }
+        Some("listen") if args.len() == 2 => {
+            let mut relay = WowIntegerRelay::bind(&args[1])?;
+            relay.set_error_handler(print_error);
+            relay.set_packet_log(|source, index, value| println!("{} {}", source, describe(index, value)));
+            println!("Listening on {}", relay.local_addr()?);
+            relay.run()
+        }
+        Some("ping") if args.len() == 3 || args.len() == 4 => {
+            let port = parse_arg(&args[2], "port")?;
+            let count = match args.get(3) {
+                Some(count) => parse_arg(count, "count")?,
+                None => 5,
+            };
+            let target = WowIntegerTarget::try_new(&args[1], port, 0)?;
+            let estimate = target.measure_clock(count, Duration::from_secs(1))?;
+            println!(
+                "{} pongs from {}: round trip min {:?} mean {:?}, clock offset {} us",
+                estimate.samples,
+                target.address(),
+                estimate.min_round_trip,
+                estimate.mean_round_trip,
+                estimate.offset_micros
+            );
+            Ok(())
+        }
+        Some("relay") if args.len() >= 4 => run_relay(&args[1], &args[2], &args[3..], None),
+        Some("relay-metrics") if args.len() >= 5 => run_relay(&args[1], &args[3], &args[4..], Some(&args[2])),
+        Some("panic-stop") if args.len() >= 4 => {
+            let port = parse_arg(&args[2], "port")?;
+            let indices = args[3..]
+                .iter()
+                .map(|index| parse_arg(index, "index"))
+                .collect::<io::Result<Vec<i32>>>()?;
+            Ok(emergency_release_all(&WowIntegerTarget::new(&args[1], port, 0), &indices)?)
+        }
+        Some("websocket") if args.len() >= 5 => {
+            let port = parse_arg(&args[3], "port")?;
+            let index = parse_arg(&args[4], "index")?;
+            run_websocket(&args[1], WowIntegerTarget::new(&args[2], port, index), &args[5..])
+        }
+        Some("http") if args.len() == 4 => {
+            let port = parse_arg(&args[3], "port")?;
+            let mut server = WowIntegerHttpServer::bind(&args[1], WowIntegerTarget::new(&args[2], port, 0))?;
+            server.set_error_handler(print_error);
+            println!("HTTP endpoint listening on http://{}", server.local_addr()?);
+            server.run()
+        }
+        Some("osc") if args.len() == 5 || args.len() == 6 => {
+            let port = parse_arg(&args[3], "port")?;
+            let index = parse_arg(&args[4], "index")?;
+            let mut listener = WowIntegerOscListener::bind(&args[1], WowIntegerTarget::new(&args[2], port, index))?;
+            listener.set_error_handler(print_error);
+            if let Some(mapping_file) = args.get(5) {
+                listener.set_mapping(OscMapping::load_from_file(mapping_file)?);
+            }
+            println!("OSC listening on {}", listener.local_addr()?);
+            listener.run()
+        }
+        Some("midi") if args.len() == 6 => {
+            let port = parse_arg(&args[4], "port")?;
+            let index = parse_arg(&args[5], "index")?;
+            let target = WowIntegerTarget::new(&args[3], port, index);
+            let mut translator = MidiTranslator::new(MidiMapping::load_from_file(&args[2])?);
+            if args[1] == "-" {
+                Ok(play_midi_stream(io::stdin().lock(), &mut translator, &target)?)
+            } else {
+                Ok(play_midi_file(&MidiFile::load_from_file(&args[1])?, &mut translator, &target)?)
+            }
+        }
+        Some("mqtt") if args.len() == 4 || args.len() == 5 => {
+            let port = parse_arg(&args[3], "port")?;
+            let target = WowIntegerTarget::new(&args[2], port, 0);
+            let mut client = MqttClient::connect(&args[1], "wowint", Duration::from_secs(30))?;
+            println!("MQTT subscribed on {} to {}/+/integer", args[1], DEFAULT_TOPIC_PREFIX);
+            match args.get(4) {
+                Some(prefix) => {
+                    let target = MqttPublishingSender::new(target, client.publisher(), prefix, 0);
+                    Ok(forward_mqtt_integers(&mut client, DEFAULT_TOPIC_PREFIX, &target, print_error)?)
+                }
+                None => Ok(forward_mqtt_integers(&mut client, DEFAULT_TOPIC_PREFIX, &target, print_error)?),
+            }
+        }
+        Some("pipe") if args.len() == 4 => {
+            let port = parse_arg(&args[2], "port")?;
+            let index = parse_arg(&args[3], "index")?;
+            let target = WowIntegerTarget::new(&args[1], port, index);
+            Ok(run_pipe(io::stdin().lock(), &target, |line, e| eprintln!("Line {}: {}", line, e))?)
+        }
+        Some("record") if args.len() == 4 => {
+            let recorder = Arc::new(SessionRecorder::create(&args[2])?);
+            let mut relay = WowIntegerRelay::bind(&args[1])?;
+            relay.set_error_handler(print_error);
+            relay.add_target(RecordingSender::new(parse_target(&args[3])?, recorder, 0));
+            println!("Recording integers received on {} into {}", relay.local_addr()?, args[2]);
+            relay.run()
+        }
+        Some("replay") if (4..=7).contains(&args.len()) => {
+            let port = parse_arg(&args[3], "port")?;
+            let mut options = ReplayOptions::new();
+            if let Some(index) = args.get(4).filter(|index| *index != "-") {
+                options = options.with_index(parse_arg(index, "index")?);
+            }
+            if let Some(speed) = args.get(5) {
+                options = options.with_speed(parse_arg(speed, "speed")?);
+            }
+            if let Some(max_pause) = args.get(6) {
+                options = options.with_max_pause(Duration::from_millis(parse_arg(max_pause, "max pause")?));
+            }
+            Ok(replay_stream(SessionReader::open(&args[1])?, &WowIntegerTarget::new(&args[2], port, 0), &options)?)
+        }
+        Some("convert-session") if args.len() == 3 => {
+            let count = convert_session_file(&args[1], &args[2])?;
+            println!("{} integers written to {}", count, args[2]);
+            Ok(())
+        }
+        Some("pcap-record") if args.len() == 4 => {
+            let recorder = Arc::new(PcapRecorder::create(&args[2])?);
+            let target = parse_target(&args[3])?;
+            let destination = target.socket_addr()?;
+            let mut relay = WowIntegerRelay::bind(&args[1])?;
+            relay.set_error_handler(print_error);
+            let source = relay.local_addr()?;
+            relay.add_target(PcapRecordingSender::new(target, recorder, source, destination, 0));
+            println!("Capturing integers received on {} into {}", source, args[2]);
+            relay.run()
+        }
+        Some("pcap-read") if args.len() == 2 || args.len() == 3 => {
+            let mut reader = PcapReader::open(&args[1])?;
+            if let Some(port) = args.get(2) {
+                reader = reader.with_port(parse_arg(port, "port")?);
+            }
+            for integer in reader {
+                let integer = integer?;
+                println!(
+                    "{}.{:06} {} -> {} {}",
+                    integer.time.as_secs(),
+                    integer.time.subsec_micros(),
+                    integer.source,
+                    integer.destination,
+                    describe(integer.index, integer.value)
+                );
+            }
+            Ok(())
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid arguments"))
+        }
+    }
+}
+
+/// Error handler of the servers, they keep running after printing it.
+fn print_error(error: &WowIntError) {
+    eprintln!("Error: {}", error);
+}
+
+fn parse_arg<T: std::str::FromStr>(text: &str, name: &str) -> io::Result<T> {
+    text.parse().map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid {}: {}", name, text))
+    })
+}
+
+/// Reads "ip:port" or "[ipv6]:port" into a target, the index is chosen by the relay rules.
+fn parse_target(text: &str) -> io::Result<WowIntegerTarget> {
+    Ok(WowIntegerTarget::try_from_address(text, 0)?)
+}
+
+fn run_relay(listen: &str, rules_file: &str, targets: &[String], metrics_listen: Option<&str>) -> io::Result<()> {
+    let mut relay = WowIntegerRelay::bind(listen)?;
+    relay.set_error_handler(print_error);
+    if rules_file != "-" {
+        relay.load_rules_from_file(rules_file)?;
+    }
+    let metrics = Arc::new(IntegerMetrics::new());
+    for target in targets {
+        let target = parse_target(target)?;
+        match metrics_listen {
+            Some(_) => relay.add_target(target.with_metrics(Arc::clone(&metrics))),
+            None => relay.add_target(target),
+        };
+    }
+    if let Some(metrics_listen) = metrics_listen {
+        relay.set_metrics(Arc::clone(&metrics))?;
+        let mut server = WowIntegerMetricsServer::bind(metrics_listen, metrics)?;
+        server.set_error_handler(print_error);
+        println!("Metrics on http://{}/metrics", server.local_addr()?);
+        server.spawn();
+    }
+    println!("Relay listening on {}", relay.local_addr()?);
+    relay.run()
+}
+
+fn run_websocket(listen: &str, target: WowIntegerTarget, origins: &[String]) -> io::Result<()> {
+    let mut bridge = WowIntegerWebSocketBridge::bind(listen, target)?;
+    bridge.set_error_handler(print_error);
+    for origin in origins {
+        bridge.allow_origin(origin);
+    }
+    println!("WebSocket bridge listening on ws://{}", bridge.local_addr()?);
+    bridge.run()
+}
+
+fn run_demo(ip: &str, port: u16, index: i32) -> io::Result<()> {
     // Create a new WowIntegerPlayer instance with a dummy IP, port, and index
-    let player = WowIntegerTarget::new("192.168.1.37", 7073, 2);
+    let player = WowIntegerTarget::new(ip, port, index);
+
+    // Release everything if the demo is stopped with Ctrl+C while a key is pressed
+    release_all_on_ctrl_c(WowIntegerTarget::new(ip, port, index), vec![index], print_error)?;
 
     // Send a fixed integer to the target player (using default index)
     println!("Sending a fixed integer to target player...");
     player.send_integer_to_target(42)?;
 
-    let exit_condition: bool =false;
-
     // Create a list of integers
-    let rotateLeft: i32 = (WowWindowKeyInt::Numpad4 as i32) + 1000;
-    let rotateRight: i32 = (WowWindowKeyInt::Numpad6 as i32) + 1000;
-    let moveFroward: i32 = (WowWindowKeyInt::Numpad8 as i32) + 1000;
-    let moveBackwardd: i32 = (WowWindowKeyInt::Numpad2 as i32) + 1000;
+    let rotate_left: i32 = (WowWindowKeyInt::Numpad4 as i32) + 1000;
+    let rotate_right: i32 = (WowWindowKeyInt::Numpad6 as i32) + 1000;
+    let move_forward: i32 = (WowWindowKeyInt::Numpad8 as i32) + 1000;
+    let move_backward: i32 = (WowWindowKeyInt::Numpad2 as i32) + 1000;
     let enter: i32 =(WowWindowKeyInt::Enter as i32) + 1000;
-    let moveUp: i32 = (WowWindowKeyInt::Numpad2 as i32) + 1000;
-    let moveDown: i32 = (WowWindowKeyInt::Numpad0 as i32) + 1000;
-    let moveLeft: i32 = (WowWindowKeyInt::Numpad1 as i32) + 1000;
-    let moveRight: i32 = (WowWindowKeyInt::Numpad3 as i32) + 1000;
-    let arrow_list_key: Vec<i32> = vec![ 
-        rotateLeft,
-        rotateRight,
-        moveFroward,
-        moveBackwardd,
+    let move_up: i32 = (WowWindowKeyInt::Numpad2 as i32) + 1000;
+    let move_down: i32 = (WowWindowKeyInt::Numpad0 as i32) + 1000;
+    let move_left: i32 = (WowWindowKeyInt::Numpad1 as i32) + 1000;
+    let move_right: i32 = (WowWindowKeyInt::Numpad3 as i32) + 1000;
+    let arrow_list_key: Vec<i32> = vec![
+        rotate_left,
+        rotate_right,
+        move_forward,
+        move_backward,
         enter,
-        moveUp,
-        moveDown,
-        moveLeft,
-        moveRight
+        move_up,
+        move_down,
+        move_left,
+        move_right
     ];
 
 
 
 
-    while !exit_condition {
+    loop {
         let range_int_press = get_random_integer_between(1048, 1090);
         let range_int_release   = range_int_press+1000;
 
         println!("Sending integer: {}", range_int_press);
         player.send_integer_to_target(range_int_press)?;
-        thread::sleep(Duration::from_secs(1)); 
+        thread::sleep(Duration::from_secs(1));
         println!("Sending integer: {}", range_int_release);
         player.send_integer_to_target(range_int_release)?;
-        thread::sleep(Duration::from_secs(1)); 
+        thread::sleep(Duration::from_secs(1));
 
         let random_arrow_input_press = get_random_integer_from_list(&arrow_list_key);
         let random_arrow_input_release = random_arrow_input_press+1000;
@@ -70,6 +316,4 @@ fn main() -> io::Result<()> {
         thread::sleep(Duration::from_secs(1));
 
     }
-
-    Ok(())
 }
@@ -0,0 +1,130 @@
+//! # Batched integers
+//! Sends many integers in one call, for macros firing several keys at once.
+//!
+//! On Linux the datagrams are written with one `sendmmsg` call, other systems send them in a loop.
+//!
+//! A batch datagram packs several integers in one packet, little endian:
+//! - magic `WOWB` (4 bytes)
+//! - index (i32) + value (i32), repeated up to 128 times
+//!
+//! Receivers that only know the 4 and 8 bytes formats ignore batch datagrams,
+//! so enable them only if the player reads them (`decode_index_integers`).
+
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+
+pub const BATCH_MAGIC: [u8; 4] = *b"WOWB";
+/// Most integers packed in one batch datagram, 1028 bytes fit in any network MTU.
+pub const MAX_BATCH_INTEGERS: usize = 128;
+pub const MAX_BATCH_DATAGRAM_SIZE: usize = BATCH_MAGIC.len() + MAX_BATCH_INTEGERS * 8;
+
+/// Encodes up to `MAX_BATCH_INTEGERS` pairs of index and integer as a batch datagram.
+pub fn encode_index_integers(integers: &[(i32, i32)]) -> Vec<u8> {
+    debug_assert!(integers.len() <= MAX_BATCH_INTEGERS);
+    let mut buf = Vec::with_capacity(BATCH_MAGIC.len() + integers.len() * 8);
+    buf.extend_from_slice(&BATCH_MAGIC);
+    for &(index, value) in integers {
+        buf.extend_from_slice(&super::encode_index_integer(index, value));
+    }
+    buf
+}
+
+/// Decodes a batch datagram, or a single 4 or 8 bytes datagram.
+/// Returns `None` if the datagram is neither.
+pub fn decode_index_integers(buf: &[u8]) -> Option<Vec<(i32, i32)>> {
+    match buf.strip_prefix(&BATCH_MAGIC) {
+        Some(pairs) if !pairs.is_empty() && pairs.len() % 8 == 0 && pairs.len() <= MAX_BATCH_INTEGERS * 8 => {
+            pairs.chunks_exact(8).map(super::decode_index_integer).collect()
+        }
+        _ => super::decode_index_integer(buf).map(|pair| vec![pair]),
+    }
+}
+
+/// Sends every datagram to the address, with as few system calls as possible.
+/// On error, returns how many datagrams were sent before the one that failed.
+#[cfg(target_os = "linux")]
+pub fn send_datagrams(socket: &UdpSocket, datagrams: &[Vec<u8>], address: SocketAddr) -> Result<(), (usize, io::Error)> {
+    use std::os::unix::io::AsRawFd;
+
+    // sendmmsg sends at most UIO_MAXIOV (1024) messages per call
+    const MAX_MESSAGES_PER_CALL: usize = 1024;
+    let (raw_address, raw_address_length) = raw_socket_address(address);
+    let mut total_sent = 0;
+    for chunk in datagrams.chunks(MAX_MESSAGES_PER_CALL) {
+        let mut iovecs: Vec<libc::iovec> = chunk
+            .iter()
+            .map(|datagram| libc::iovec {
+                iov_base: datagram.as_ptr() as *mut libc::c_void,
+                iov_len: datagram.len(),
+            })
+            .collect();
+        let mut messages: Vec<libc::mmsghdr> = iovecs
+            .iter_mut()
+            .map(|iovec| {
+                // SAFETY: msghdr is a plain C struct, all zeroes is a valid empty header
+                let mut header: libc::msghdr = unsafe { std::mem::zeroed() };
+                header.msg_name = &raw_address as *const libc::sockaddr_storage as *mut libc::c_void;
+                header.msg_namelen = raw_address_length;
+                header.msg_iov = iovec;
+                header.msg_iovlen = 1;
+                libc::mmsghdr { msg_hdr: header, msg_len: 0 }
+            })
+            .collect();
+        let mut sent = 0;
+        while sent < messages.len() {
+            let remaining = &mut messages[sent..];
+            // SAFETY: the headers point to the address and buffers above, alive during the call
+            let result = unsafe { libc::sendmmsg(socket.as_raw_fd(), remaining.as_mut_ptr(), remaining.len() as libc::c_uint, 0) };
+            if result < 0 {
+                let error = io::Error::last_os_error();
+                if error.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err((total_sent + sent, error));
+            }
+            if result == 0 {
+                return Err((total_sent + sent, io::Error::new(io::ErrorKind::WriteZero, "sendmmsg sent no datagram")));
+            }
+            sent += result as usize;
+        }
+        total_sent += sent;
+    }
+    Ok(())
+}
+
+/// Sends every datagram to the address, one system call each.
+/// On error, returns how many datagrams were sent before the one that failed.
+#[cfg(not(target_os = "linux"))]
+pub fn send_datagrams(socket: &UdpSocket, datagrams: &[Vec<u8>], address: SocketAddr) -> Result<(), (usize, io::Error)> {
+    for (sent, datagram) in datagrams.iter().enumerate() {
+        socket.send_to(datagram, address).map_err(|error| (sent, error))?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn raw_socket_address(address: SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    // SAFETY: sockaddr_storage is a plain C struct, all zeroes is valid
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let length = match address {
+        SocketAddr::V4(address) => {
+            // SAFETY: sockaddr_storage is big and aligned enough for any socket address
+            let raw = unsafe { &mut *(&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in) };
+            raw.sin_family = libc::AF_INET as libc::sa_family_t;
+            raw.sin_port = address.port().to_be();
+            raw.sin_addr.s_addr = u32::from_ne_bytes(address.ip().octets());
+            std::mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(address) => {
+            // SAFETY: sockaddr_storage is big and aligned enough for any socket address
+            let raw = unsafe { &mut *(&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in6) };
+            raw.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            raw.sin6_port = address.port().to_be();
+            raw.sin6_flowinfo = address.flowinfo();
+            raw.sin6_addr.s6_addr = address.ip().octets();
+            raw.sin6_scope_id = address.scope_id();
+            std::mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    (storage, length as libc::socklen_t)
+}
@@ -0,0 +1,229 @@
+//! # Clock
+//! Measures the round trip time and the clock offset to a player, to schedule synchronized presses.
+//!
+//! Ping and pong use the port of the integers, `WowIntegerRelay` answers the pings
+//! when it reads plain packets. A relay expecting signed or encrypted packets does not answer,
+//! an unauthenticated pong twice the size of the ping would let anyone use it as a reflector.
+//! Times are microseconds since the Unix epoch (u64), little endian:
+//! - ping: magic `WOWP`, id (u32), t1 sender time when sent (16 bytes)
+//! - pong: magic `WOWQ`, id, t1, t2 receiver time when received, t3 receiver time when answered (32 bytes)
+//!
+//! With t4 the sender time when the pong is received, as in NTP:
+//! - round trip = (t4 - t1) - (t3 - t2)
+//! - offset = ((t2 - t1) + (t3 - t4)) / 2, the receiver clock minus the sender clock
+//!
+//! The estimate keeps the offset of the sample with the shortest round trip,
+//! the one least disturbed by the network.
+
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::bind_udp_socket;
+use super::error::WowIntResult;
+
+pub const PING_MAGIC: [u8; 4] = *b"WOWP";
+pub const PONG_MAGIC: [u8; 4] = *b"WOWQ";
+const PING_SIZE: usize = 16;
+const PONG_SIZE: usize = 32;
+/// Samples kept by a `ClockTracker`.
+const TRACKED_SAMPLES: usize = 8;
+/// Shortest interval of a `ClockTracker`, a zero interval would ping in a busy loop.
+pub const MIN_TRACK_INTERVAL: Duration = Duration::from_millis(10);
+
+fn micros_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_micros() as u64).unwrap_or(0)
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(buf[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(buf[at..at + 8].try_into().unwrap())
+}
+
+fn connect(address: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = bind_udp_socket(address)?;
+    socket.connect(address)?;
+    Ok(socket)
+}
+
+/// Returns the id and send time of a ping datagram.
+pub fn decode_ping(buf: &[u8]) -> Option<(u32, u64)> {
+    if buf.len() != PING_SIZE || buf[..4] != PING_MAGIC {
+        return None;
+    }
+    Some((read_u32(buf, 4), read_u64(buf, 8)))
+}
+
+/// Answers a ping datagram received at `received`, returns false if it is not a ping.
+pub fn answer_ping(socket: &UdpSocket, source: SocketAddr, buf: &[u8], received: SystemTime) -> io::Result<bool> {
+    let (id, sent) = match decode_ping(buf) {
+        Some(ping) => ping,
+        None => return Ok(false),
+    };
+    let mut pong = [0u8; PONG_SIZE];
+    pong[..4].copy_from_slice(&PONG_MAGIC);
+    pong[4..8].copy_from_slice(&id.to_le_bytes());
+    pong[8..16].copy_from_slice(&sent.to_le_bytes());
+    pong[16..24].copy_from_slice(&micros_since_epoch(received).to_le_bytes());
+    pong[24..].copy_from_slice(&micros_since_epoch(SystemTime::now()).to_le_bytes());
+    socket.send_to(&pong, source)?;
+    Ok(true)
+}
+
+/// One ping and its pong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSample {
+    pub round_trip: Duration,
+    /// Receiver clock minus sender clock, in microseconds.
+    pub offset_micros: i64,
+}
+
+impl ClockSample {
+    fn from_times(t1: u64, t2: u64, t3: u64, t4: u64) -> ClockSample {
+        let (t1, t2, t3, t4) = (t1 as i64, t2 as i64, t3 as i64, t4 as i64);
+        let round_trip = ((t4 - t1) - (t3 - t2)).max(0);
+        ClockSample {
+            round_trip: Duration::from_micros(round_trip as u64),
+            offset_micros: ((t2 - t1) + (t3 - t4)) / 2,
+        }
+    }
+}
+
+/// Summary of several samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockEstimate {
+    pub min_round_trip: Duration,
+    pub mean_round_trip: Duration,
+    /// Offset of the sample with the shortest round trip, receiver minus sender, in microseconds.
+    pub offset_micros: i64,
+    pub samples: usize,
+}
+
+impl ClockEstimate {
+    /// Returns `None` without samples.
+    pub fn from_samples<'a, I: IntoIterator<Item = &'a ClockSample>>(samples: I) -> Option<ClockEstimate> {
+        let samples: Vec<&ClockSample> = samples.into_iter().collect();
+        let best = samples.iter().min_by_key(|sample| sample.round_trip)?;
+        let total: Duration = samples.iter().map(|sample| sample.round_trip).sum();
+        Some(ClockEstimate {
+            min_round_trip: best.round_trip,
+            mean_round_trip: total / samples.len() as u32,
+            offset_micros: best.offset_micros,
+            samples: samples.len(),
+        })
+    }
+
+    /// Converts a time of the receiver clock to the sender clock.
+    pub fn to_sender_time(&self, receiver_time: SystemTime) -> SystemTime {
+        let offset = Duration::from_micros(self.offset_micros.unsigned_abs());
+        if self.offset_micros >= 0 {
+            receiver_time - offset
+        } else {
+            receiver_time + offset
+        }
+    }
+}
+
+/// Sends a ping to a player and waits for its pong.
+pub fn ping(address: SocketAddr, timeout: Duration) -> WowIntResult<ClockSample> {
+    let socket = connect(address)?;
+    ping_on(&socket, timeout)
+}
+
+fn ping_on(socket: &UdpSocket, timeout: Duration) -> WowIntResult<ClockSample> {
+    let id: u32 = rand::random();
+    let mut ping = [0u8; PING_SIZE];
+    ping[..4].copy_from_slice(&PING_MAGIC);
+    ping[4..8].copy_from_slice(&id.to_le_bytes());
+    ping[8..].copy_from_slice(&micros_since_epoch(SystemTime::now()).to_le_bytes());
+    socket.send(&ping)?;
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 64];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "No pong received").into());
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        let size = match socket.recv(&mut buf) {
+            Ok(size) => size,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let received = micros_since_epoch(SystemTime::now());
+        let pong = &buf[..size];
+        if size == PONG_SIZE && pong[..4] == PONG_MAGIC && read_u32(pong, 4) == id {
+            return Ok(ClockSample::from_times(read_u64(pong, 8), read_u64(pong, 16), read_u64(pong, 24), received));
+        }
+    }
+}
+
+/// Pings `count` times and summarizes the pongs received, failing only if none came back.
+pub fn measure_clock(address: SocketAddr, count: usize, timeout: Duration) -> WowIntResult<ClockEstimate> {
+    let socket = connect(address)?;
+    let mut samples = Vec::with_capacity(count);
+    let mut last_error = None;
+    for _ in 0..count.max(1) {
+        match ping_on(&socket, timeout) {
+            Ok(sample) => samples.push(sample),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match ClockEstimate::from_samples(&samples) {
+        Some(estimate) => Ok(estimate),
+        None => Err(last_error.expect("at least one ping was sent")),
+    }
+}
+
+/// Pings a player in the background to keep its estimate fresh.
+/// The thread stops when the tracker is dropped.
+pub struct ClockTracker {
+    samples: Arc<Mutex<VecDeque<ClockSample>>>,
+}
+
+impl ClockTracker {
+    /// Pings every `interval` (at least `MIN_TRACK_INTERVAL`), a pong later than `interval` is counted as lost.
+    pub fn start(address: SocketAddr, interval: Duration) -> WowIntResult<ClockTracker> {
+        let interval = interval.max(MIN_TRACK_INTERVAL);
+        let socket = connect(address)?;
+        let samples = Arc::new(Mutex::new(VecDeque::with_capacity(TRACKED_SAMPLES)));
+        let weak = Arc::downgrade(&samples);
+        thread::spawn(move || track(socket, weak, interval));
+        Ok(ClockTracker { samples })
+    }
+
+    /// Estimate of the last 8 samples, `None` until a pong is received.
+    pub fn estimate(&self) -> Option<ClockEstimate> {
+        ClockEstimate::from_samples(self.samples.lock().unwrap().iter())
+    }
+
+    pub fn last_sample(&self) -> Option<ClockSample> {
+        self.samples.lock().unwrap().back().copied()
+    }
+}
+
+fn track(socket: UdpSocket, samples: Weak<Mutex<VecDeque<ClockSample>>>, interval: Duration) {
+    loop {
+        let start = Instant::now();
+        let sample = ping_on(&socket, interval);
+        let samples = match samples.upgrade() {
+            Some(samples) => samples,
+            None => return,
+        };
+        if let Ok(sample) = sample {
+            let mut samples = samples.lock().unwrap();
+            if samples.len() == TRACKED_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(sample);
+        }
+        drop(samples);
+        thread::sleep(interval.saturating_sub(start.elapsed()));
+    }
+}
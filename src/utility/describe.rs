@@ -0,0 +1,60 @@
+//! # Describe
+//! Turns raw integers into readable text for logs.
+//!
+//! ```text
+//! describe(2, 1104)  player 2: press Numpad8 (VK 0x68)
+//! describe(2, 2032)  player 2: release Space (VK 0x20)
+//! describe(0, 1300)  player 0: xbox PressA
+//! describe(0, 2370)  player 0: xbox release SetLeftStickHorizontal050
+//! describe(0, 777)   player 0: unknown 777
+//! ```
+
+use std::sync::OnceLock;
+
+use super::{EnumWowKey, XboxIntegerActionEnum};
+
+fn keys() -> &'static EnumWowKey {
+    static KEYS: OnceLock<EnumWowKey> = OnceLock::new();
+    KEYS.get_or_init(EnumWowKey::new)
+}
+
+fn key_description(action: &str, virtual_key: u8) -> String {
+    match keys().get_key_info_by_decimal(virtual_key) {
+        Some(key) => format!("{} {} (VK 0x{:02X})", action, key.key_name(), virtual_key),
+        None => format!("{} VK 0x{:02X}", action, virtual_key),
+    }
+}
+
+/// Describes an integer without its player.
+pub fn describe_value(value: i32) -> String {
+    if let Some(action) = XboxIntegerActionEnum::from_integer(value) {
+        return format!("xbox {:?}", action);
+    }
+    if let Some(action) = value.checked_sub(1000).and_then(XboxIntegerActionEnum::from_integer) {
+        return format!("xbox release {:?}", action);
+    }
+    match value {
+        1000..=1255 => key_description("press", (value - 1000) as u8),
+        2000..=2255 => key_description("release", (value - 2000) as u8),
+        _ => format!("unknown {}", value),
+    }
+}
+
+/// Name of the key or gamepad input of a press integer, e.g. "Space" or "PressA".
+pub fn press_name(value: i32) -> String {
+    if let Some(action) = XboxIntegerActionEnum::from_integer(value) {
+        return format!("{:?}", action);
+    }
+    match value {
+        1000..=1255 => match keys().get_key_info_by_decimal((value - 1000) as u8) {
+            Some(key) => key.key_name().to_string(),
+            None => format!("VK 0x{:02X}", value - 1000),
+        },
+        _ => value.to_string(),
+    }
+}
+
+/// Describes an integer sent to a player, e.g. "player 2: press Numpad8 (VK 0x68)".
+pub fn describe(index: i32, value: i32) -> String {
+    format!("player {}: {}", index, describe_value(value))
+}
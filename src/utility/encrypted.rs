@@ -0,0 +1,162 @@
+//! # Encrypted packets
+//! Optional framing that hides the integers sent, using ChaCha20-Poly1305 with a pre-shared key.
+//! The cipher key is the SHA-256 of the pre-shared key, so any passphrase can be used.
+//!
+//! An encrypted packet is 36 bytes:
+//! - nonce (12 bytes): sender id (u32) and counter (u64), little endian
+//! - index and value encrypted (8 bytes), same layout as a plain packet
+//! - Poly1305 tag (16 bytes)
+//!
+//! As with signed packets, the decoder rejects a counter seen twice or older than the replay window of its sender id.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use sha2::{Digest, Sha256};
+
+use super::error::{WowIntError, WowIntResult};
+use super::signed::ReplayWindow;
+use super::{decode_index_integer, encode_index_integer};
+
+pub const ENCRYPTED_PACKET_SIZE: usize = 36;
+const NONCE_SIZE: usize = 12;
+
+fn new_cipher(pre_shared_key: &[u8]) -> ChaCha20Poly1305 {
+    let key = Sha256::digest(pre_shared_key);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+/// Encrypts the integers sent with a pre-shared key.
+pub struct PacketEncrypter {
+    cipher: ChaCha20Poly1305,
+    sender_id: u32,
+    counter: AtomicU64,
+}
+
+impl PacketEncrypter {
+    /// Creates an encrypter with a random sender id.
+    pub fn new(pre_shared_key: &[u8]) -> PacketEncrypter {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_micros() as u64)
+            .unwrap_or(0);
+        PacketEncrypter {
+            cipher: new_cipher(pre_shared_key),
+            sender_id: rand::random(),
+            counter: AtomicU64::new(now),
+        }
+    }
+
+    pub fn sender_id(&self) -> u32 {
+        self.sender_id
+    }
+
+    /// Builds the encrypted packet of an index and an integer.
+    pub fn encode(&self, index: i32, value: i32) -> [u8; ENCRYPTED_PACKET_SIZE] {
+        let counter = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut buf = [0u8; ENCRYPTED_PACKET_SIZE];
+        buf[0..4].copy_from_slice(&self.sender_id.to_le_bytes());
+        buf[4..12].copy_from_slice(&counter.to_le_bytes());
+        let sealed = self
+            .cipher
+            .encrypt(Nonce::from_slice(&buf[..NONCE_SIZE]), &encode_index_integer(index, value)[..])
+            .expect("encrypting 8 bytes can not fail");
+        buf[NONCE_SIZE..].copy_from_slice(&sealed);
+        buf
+    }
+}
+
+/// Decrypts encrypted packets and rejects replayed ones.
+pub struct EncryptedPacketDecoder {
+    cipher: ChaCha20Poly1305,
+    windows: HashMap<u32, ReplayWindow>,
+}
+
+impl EncryptedPacketDecoder {
+    pub fn new(pre_shared_key: &[u8]) -> EncryptedPacketDecoder {
+        EncryptedPacketDecoder {
+            cipher: new_cipher(pre_shared_key),
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Returns the index and integer of a valid packet.
+    /// Fails with `WowIntError::Parse` if the size is wrong or the packet can not be decrypted,
+    /// and with `WowIntError::Replayed` if the packet was replayed.
+    pub fn decode(&mut self, buf: &[u8]) -> WowIntResult<(i32, i32)> {
+        if buf.len() != ENCRYPTED_PACKET_SIZE {
+            return Err(WowIntError::parse(
+                "encrypted packet",
+                &format!("must be {} bytes, received {}", ENCRYPTED_PACKET_SIZE, buf.len()),
+            ));
+        }
+        let plain = self
+            .cipher
+            .decrypt(Nonce::from_slice(&buf[..NONCE_SIZE]), &buf[NONCE_SIZE..])
+            .map_err(|_| WowIntError::parse("encrypted packet", "can not be decrypted"))?;
+        let packet = decode_index_integer(&plain)
+            .ok_or_else(|| WowIntError::parse("encrypted packet", "wrong decrypted size"))?;
+        let sender_id = u32::from_le_bytes(buf[0..4].try_into().unwrap());
+        let counter = u64::from_le_bytes(buf[4..12].try_into().unwrap());
+        if !self.windows.entry(sender_id).or_default().accept(counter) {
+            return Err(WowIntError::Replayed { sender_id, counter });
+        }
+        Ok(packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let encrypter = PacketEncrypter::new(b"key");
+        let mut decoder = EncryptedPacketDecoder::new(b"key");
+        let packet = encrypter.encode(3, 1032);
+        assert_ne!(&packet[NONCE_SIZE..NONCE_SIZE + 8], &encode_index_integer(3, 1032)[..]);
+        assert_eq!(decoder.decode(&packet).unwrap(), (3, 1032));
+        assert_eq!(decoder.decode(&encrypter.encode(-1, 2032)).unwrap(), (-1, 2032));
+    }
+
+    #[test]
+    fn rejects_tampered_packets_and_wrong_keys() {
+        let encrypter = PacketEncrypter::new(b"key");
+        let mut decoder = EncryptedPacketDecoder::new(b"key");
+        for position in [0, NONCE_SIZE, ENCRYPTED_PACKET_SIZE - 1] {
+            let mut tampered = encrypter.encode(3, 1032);
+            tampered[position] ^= 1;
+            assert!(matches!(decoder.decode(&tampered), Err(WowIntError::Parse { .. })));
+        }
+        let other = PacketEncrypter::new(b"other key");
+        assert!(matches!(decoder.decode(&other.encode(3, 1032)), Err(WowIntError::Parse { .. })));
+        assert!(decoder.decode(&encrypter.encode(3, 1032)[..20]).is_err());
+    }
+
+    #[test]
+    fn rejects_replayed_packets() {
+        let encrypter = PacketEncrypter::new(b"key");
+        let mut decoder = EncryptedPacketDecoder::new(b"key");
+        let packet = encrypter.encode(3, 1032);
+        decoder.decode(&packet).unwrap();
+        assert!(matches!(
+            decoder.decode(&packet),
+            Err(WowIntError::Replayed { sender_id, .. }) if sender_id == encrypter.sender_id()
+        ));
+    }
+
+    #[test]
+    fn accepts_reordered_packets_within_the_window() {
+        let encrypter = PacketEncrypter::new(b"key");
+        let mut decoder = EncryptedPacketDecoder::new(b"key");
+        let packets: Vec<_> = (0..66).map(|value| encrypter.encode(0, value)).collect();
+        assert_eq!(decoder.decode(&packets[2]).unwrap(), (0, 2));
+        assert_eq!(decoder.decode(&packets[1]).unwrap(), (0, 1));
+        assert!(decoder.decode(&packets[1]).is_err());
+        decoder.decode(&packets[65]).unwrap();
+        assert!(matches!(decoder.decode(&packets[0]), Err(WowIntError::Replayed { .. })));
+    }
+}
@@ -0,0 +1,124 @@
+//! # Errors
+//! `WowIntError` tells what failed, with the target, index and value when there is one.
+//! It converts from and into `io::Error`, so `?` works in code still using `io::Result`.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+pub type WowIntResult<T> = Result<T, WowIntError>;
+
+/// Receives the errors a server skips to keep running, e.g. to print them.
+pub type ErrorHandler = Arc<dyn Fn(&WowIntError) + Send + Sync>;
+
+/// Error handler of a server until one is set, the errors are dropped.
+pub fn ignore_errors() -> ErrorHandler {
+    Arc::new(|_| {})
+}
+
+/// Why a policy dropped an integer, see `policy::IntegerPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropReason {
+    /// The integer is outside of the allowed ranges.
+    NotAllowed,
+    /// The integer is explicitly rejected.
+    Rejected,
+    SourceRateLimited,
+    IndexRateLimited,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WowIntError {
+    /// The address of a target can not be read.
+    InvalidAddress { address: String, message: String },
+    /// The host name of a target does not resolve.
+    Resolve { host: String, source: io::Error },
+    /// The transport failed to send an integer.
+    Send { target: String, index: i32, value: i32, source: io::Error },
+    /// A policy refused the integer.
+    Rejected { index: i32, value: i32, reason: DropReason },
+    /// No key of the registry has this name.
+    UnknownKey { name: String },
+    /// A frame or packet is bigger than allowed.
+    PayloadTooLarge { size: usize, max: usize },
+    /// A line of a text format is invalid, `what` names the format (e.g. "relay rule").
+    Parse { what: &'static str, line: Option<usize>, text: String },
+    /// A signed or encrypted packet was already received.
+    Replayed { sender_id: u32, counter: u64 },
+    Io(io::Error),
+}
+
+impl WowIntError {
+    /// Parse error of a line of a file, `line` starting at 1.
+    pub fn parse_at_line(what: &'static str, line: usize, text: &str) -> WowIntError {
+        WowIntError::Parse { what, line: Some(line), text: text.to_string() }
+    }
+
+    /// Parse error of a single value or command.
+    pub fn parse(what: &'static str, text: &str) -> WowIntError {
+        WowIntError::Parse { what, line: None, text: text.to_string() }
+    }
+
+    pub fn unknown_key(name: &str) -> WowIntError {
+        WowIntError::UnknownKey { name: name.to_string() }
+    }
+
+    /// Closest `io::ErrorKind`, used by the conversion to `io::Error`.
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            WowIntError::InvalidAddress { .. } | WowIntError::UnknownKey { .. } => io::ErrorKind::InvalidInput,
+            WowIntError::Resolve { source, .. } | WowIntError::Send { source, .. } | WowIntError::Io(source) => source.kind(),
+            WowIntError::Rejected { .. } | WowIntError::Replayed { .. } => io::ErrorKind::PermissionDenied,
+            WowIntError::PayloadTooLarge { .. } | WowIntError::Parse { .. } => io::ErrorKind::InvalidData,
+        }
+    }
+}
+
+impl fmt::Display for WowIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WowIntError::InvalidAddress { address, message } => write!(f, "Invalid address {}: {}", address, message),
+            WowIntError::Resolve { host, source } => write!(f, "Can not resolve {}: {}", host, source),
+            WowIntError::Send { target, index, value, source } => {
+                write!(f, "Can not send {} to {} index {}: {}", value, target, index, source)
+            }
+            WowIntError::Rejected { index, value, reason } => {
+                write!(f, "Integer {} for index {} rejected: {:?}", value, index, reason)
+            }
+            WowIntError::UnknownKey { name } => write!(f, "Unknown key: {}", name),
+            WowIntError::PayloadTooLarge { size, max } => write!(f, "Payload of {} bytes is bigger than {}", size, max),
+            WowIntError::Parse { what, line: Some(line), text } => write!(f, "Invalid {} at line {}: {}", what, line, text),
+            WowIntError::Parse { what, line: None, text } => write!(f, "Invalid {}: {}", what, text),
+            WowIntError::Replayed { sender_id, counter } => {
+                write!(f, "Replayed packet from sender {} (counter {})", sender_id, counter)
+            }
+            WowIntError::Io(source) => source.fmt(f),
+        }
+    }
+}
+
+impl Error for WowIntError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WowIntError::Resolve { source, .. } | WowIntError::Send { source, .. } | WowIntError::Io(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WowIntError {
+    fn from(error: io::Error) -> Self {
+        WowIntError::Io(error)
+    }
+}
+
+impl From<WowIntError> for io::Error {
+    fn from(error: WowIntError) -> Self {
+        match error {
+            WowIntError::Io(error) => error,
+            error => io::Error::new(error.kind(), error),
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! # HTTP endpoint
+//! Small embedded HTTP server so other tools (curl, dashboards, Node-RED) can drive the game.
+//!
+//! - `POST /players/{index}/integer` with the integer as body (e.g. `1032`)
+//! - `POST /players/{index}/tap/{key}` presses then releases a key by its name,
+//!   `?duration_ms=200` changes how long the key is held (100 ms by default)
+//! - `GET /keys` lists the keys of the registry as JSON
+//!
+//! ```text
+//! curl -X POST -d 1032 http://127.0.0.1:7076/players/2/integer
+//! curl -X POST http://127.0.0.1:7076/players/2/tap/Space?duration_ms=300
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::error::{ignore_errors, DropReason, ErrorHandler, WowIntError, WowIntResult};
+use super::{tap, EnumWowKey, IntegerSender, DEFAULT_TAP_DURATION, MAX_TAP_DURATION};
+
+/// Biggest request body accepted, an integer does not need more.
+const MAX_BODY_SIZE: usize = 1024;
+/// A client silent this long is disconnected, so it can not hold its thread forever.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl HttpResponse {
+    fn json(status: u16, body: String) -> HttpResponse {
+        HttpResponse { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> HttpResponse {
+        HttpResponse::json(status, format!("{{\"error\":\"{}\"}}", json_escape(message)))
+    }
+
+    fn ok() -> HttpResponse {
+        HttpResponse::json(200, "{\"ok\":true}".to_string())
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            429 => "Too Many Requests",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            character if (character as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", character as u32)),
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Embedded HTTP server translating requests to sends on a target.
+pub struct WowIntegerHttpServer<S: IntegerSender + Send + Sync + 'static> {
+    listener: TcpListener,
+    target: Arc<S>,
+    keys: Arc<EnumWowKey>,
+    errors: ErrorHandler,
+}
+
+impl<S: IntegerSender + Send + Sync + 'static> WowIntegerHttpServer<S> {
+    /// Creates a server listening on the given address (e.g. "127.0.0.1:7076").
+    pub fn bind(listen_address: &str, target: S) -> WowIntResult<WowIntegerHttpServer<S>> {
+        Ok(WowIntegerHttpServer {
+            listener: TcpListener::bind(listen_address)?,
+            target: Arc::new(target),
+            keys: Arc::new(EnumWowKey::new()),
+            errors: ignore_errors(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Receives the accept errors and the errors of the connections that could not be answered.
+    pub fn set_error_handler<F: Fn(&WowIntError) + Send + Sync + 'static>(&mut self, handler: F) {
+        self.errors = Arc::new(handler);
+    }
+
+    /// Accepts connections forever, each request is answered on its own thread.
+    pub fn run(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    (self.errors)(&e.into());
+                    continue;
+                }
+            };
+            let target = Arc::clone(&self.target);
+            let keys = Arc::clone(&self.keys);
+            let errors = Arc::clone(&self.errors);
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, &*target, &keys) {
+                    errors(&e.into());
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+fn handle_connection<S: IntegerSender>(stream: TcpStream, target: &S, keys: &EnumWowKey) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // None when the header is not a number
+    let mut content_length = Some(0);
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok();
+            }
+        }
+    }
+    let response = match content_length {
+        None => HttpResponse::error(400, "Invalid Content-Length"),
+        Some(length) if length > MAX_BODY_SIZE => HttpResponse::error(413, "Body too large"),
+        Some(length) => {
+            let mut body = vec![0u8; length];
+            reader.read_exact(&mut body)?;
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or("");
+            let path = parts.next().unwrap_or("");
+            handle_request(target, keys, method, path, &String::from_utf8_lossy(&body))
+        }
+    };
+    write_response(stream, &response)
+}
+
+fn write_response(mut stream: TcpStream, response: &HttpResponse) -> io::Result<()> {
+    let text = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        response.body.len(),
+        response.body
+    );
+    stream.write_all(text.as_bytes())?;
+    stream.flush()
+}
+
+/// Answers a request, without the network part.
+pub fn handle_request<S: IntegerSender>(target: &S, keys: &EnumWowKey, method: &str, path: &str, body: &str) -> HttpResponse {
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        ("GET", ["keys"]) => list_keys(keys),
+        ("POST", ["players", index, "integer"]) => {
+            let index = match index.parse() {
+                Ok(index) => index,
+                Err(_) => return HttpResponse::error(400, "Invalid player index"),
+            };
+            let value = match body.trim().parse() {
+                Ok(value) => value,
+                Err(_) => return HttpResponse::error(400, "Body must be an integer"),
+            };
+            send_response(target.send_integer_to_target_at_index(index, value))
+        }
+        ("POST", ["players", index, "tap", key]) => {
+            let index = match index.parse() {
+                Ok(index) => index,
+                Err(_) => return HttpResponse::error(400, "Invalid player index"),
+            };
+            let key = match keys.find_key(key) {
+                Ok(key) => key,
+                Err(e) => return HttpResponse::error(404, &e.to_string()),
+            };
+            let duration = match tap_duration(query) {
+                Some(duration) => duration,
+                None => return HttpResponse::error(400, "Invalid duration_ms"),
+            };
+            send_response(tap(target, Some(index), key.press_integer() as i32, duration))
+        }
+        (_, ["keys"]) | (_, ["players", _, "integer"]) | (_, ["players", _, "tap", _]) => {
+            HttpResponse::error(405, "Method not allowed")
+        }
+        _ => HttpResponse::error(404, "Not found"),
+    }
+}
+
+fn tap_duration(query: &str) -> Option<Duration> {
+    for parameter in query.split('&') {
+        if let Some(("duration_ms", value)) = parameter.split_once('=') {
+            let duration = Duration::from_millis(value.parse().ok()?);
+            return Some(duration.min(MAX_TAP_DURATION));
+        }
+    }
+    Some(DEFAULT_TAP_DURATION)
+}
+
+fn send_response(result: WowIntResult<()>) -> HttpResponse {
+    match result {
+        Ok(()) => HttpResponse::ok(),
+        Err(
+            e @ WowIntError::Rejected {
+                reason: DropReason::SourceRateLimited | DropReason::IndexRateLimited,
+                ..
+            },
+        ) => HttpResponse::error(429, &e.to_string()),
+        Err(e @ WowIntError::Rejected { .. }) => HttpResponse::error(403, &e.to_string()),
+        Err(e) => HttpResponse::error(500, &e.to_string()),
+    }
+}
+
+fn list_keys(keys: &EnumWowKey) -> HttpResponse {
+    let entries: Vec<String> = keys
+        .keys()
+        .iter()
+        .map(|key| {
+            format!(
+                "{{\"name\":\"{}\",\"decimal\":{},\"press\":{},\"release\":{}}}",
+                json_escape(key.key_name()),
+                key.window_decimal(),
+                key.press_integer(),
+                key.release_integer()
+            )
+        })
+        .collect();
+    HttpResponse::json(200, format!("[{}]", entries.join(",")))
+}
@@ -0,0 +1,388 @@
+//! # Metrics
+//! Counters of what the senders sent and the relays received, exported in the Prometheus text format.
+//!
+//! - `WowIntegerTarget::with_metrics` counts the integers, bytes, errors and send latency of a target,
+//!   `MetricsSender` does the same for any sender (without the bytes)
+//! - `WowIntegerRelay::set_metrics` counts the datagrams and integers received, and the dropped ones
+//! - `WowIntegerMetricsServer` serves `GET /metrics` for Prometheus
+//!
+//! ```text
+//! wowint_sent_integers_total{target="192.168.1.37:7073",index="2"} 42
+//! wowint_key_presses_total{target="192.168.1.37:7073",index="2",key="Space"} 3
+//! wowint_held_keys{target="192.168.1.37:7073",index="2"} 0
+//! wowint_sent_integers_total{target="mock",index="all"} 1
+//! ```
+//!
+//! Integers sent with `send_integer_to_all` are counted under `index="all"`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::describe::press_name;
+use super::error::{ignore_errors, ErrorHandler, WowIntError, WowIntResult};
+use super::watchdog::{is_press_integer, is_release_integer, HeldKeys, KeyScope};
+use super::IntegerSender;
+
+/// Upper bounds of the send latency histogram, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25];
+/// Idle time after which a scrape connection is closed.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct LatencyHistogram {
+    // Count of each bucket alone, summed when exported
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&bound| seconds <= bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Default)]
+struct SentCounters {
+    integers: u64,
+    bytes: u64,
+    errors: u64,
+    presses: BTreeMap<i32, u64>,
+    releases: u64,
+    // The counters are already per player, the held keys need no player of their own
+    held: HeldKeys<()>,
+    latency: LatencyHistogram,
+}
+
+impl SentCounters {
+    fn count_integer(&mut self, value: i32) {
+        self.integers += 1;
+        if is_press_integer(value) {
+            *self.presses.entry(value).or_insert(0) += 1;
+        } else if is_release_integer(value) {
+            self.releases += 1;
+        }
+        self.held.track((), value);
+    }
+}
+
+#[derive(Default)]
+struct ReceivedCounters {
+    integers: u64,
+    dropped: u64,
+}
+
+#[derive(Default)]
+struct ListenerCounters {
+    datagrams: u64,
+    bytes: u64,
+}
+
+#[derive(Default)]
+struct MetricsState {
+    // By target address and player
+    sent: BTreeMap<(String, KeyScope), SentCounters>,
+    // By listening address and player index
+    received: BTreeMap<(String, i32), ReceivedCounters>,
+    listeners: BTreeMap<String, ListenerCounters>,
+}
+
+/// Counters shared by the senders and relays, usually in an `Arc`.
+#[derive(Default)]
+pub struct IntegerMetrics {
+    state: Mutex<MetricsState>,
+}
+
+impl IntegerMetrics {
+    pub fn new() -> IntegerMetrics {
+        IntegerMetrics::default()
+    }
+
+    /// Counts an integer sent to a target, `bytes` is 0 if the transport does not know it.
+    pub fn record_sent(&self, target: &str, scope: KeyScope, value: i32, bytes: usize, latency: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let counters = state.sent.entry((target.to_string(), scope)).or_default();
+        counters.count_integer(value);
+        counters.bytes += bytes as u64;
+        counters.latency.observe(latency);
+    }
+
+    /// Counts the integers (index, value, bytes) sent to a target in one call,
+    /// with one latency sample under the index of the first one.
+    pub fn record_sent_many(&self, target: &str, integers: &[(i32, i32, usize)], latency: Duration) {
+        let mut state = self.state.lock().unwrap();
+        for &(index, value, bytes) in integers {
+            let counters = state.sent.entry((target.to_string(), KeyScope::Index(index))).or_default();
+            counters.count_integer(value);
+            counters.bytes += bytes as u64;
+        }
+        if let Some(&(index, _, _)) = integers.first() {
+            state.sent.entry((target.to_string(), KeyScope::Index(index))).or_default().latency.observe(latency);
+        }
+    }
+
+    pub fn record_send_error(&self, target: &str, scope: KeyScope) {
+        let mut state = self.state.lock().unwrap();
+        state.sent.entry((target.to_string(), scope)).or_default().errors += 1;
+    }
+
+    pub fn record_datagram(&self, listener: &str, bytes: usize) {
+        let mut state = self.state.lock().unwrap();
+        let counters = state.listeners.entry(listener.to_string()).or_default();
+        counters.datagrams += 1;
+        counters.bytes += bytes as u64;
+    }
+
+    pub fn record_received(&self, listener: &str, index: i32) {
+        let mut state = self.state.lock().unwrap();
+        state.received.entry((listener.to_string(), index)).or_default().integers += 1;
+    }
+
+    pub fn record_dropped(&self, listener: &str, index: i32) {
+        let mut state = self.state.lock().unwrap();
+        state.received.entry((listener.to_string(), index)).or_default().dropped += 1;
+    }
+
+    /// Press integers sent to the player and not released yet.
+    pub fn held_keys(&self, target: &str, scope: KeyScope) -> Vec<i32> {
+        let state = self.state.lock().unwrap();
+        match state.sent.get(&(target.to_string(), scope)) {
+            Some(counters) => {
+                let mut held: Vec<i32> = counters.held.keys().into_iter().map(|(_, press)| press).collect();
+                held.sort();
+                held
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Writes every counter in the Prometheus text format (version 0.0.4).
+    pub fn to_prometheus(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut out = String::new();
+        let sent_labels = |(target, scope): &(String, KeyScope)| match scope {
+            KeyScope::Index(index) => format!("target=\"{}\",index=\"{}\"", escape_label(target), index),
+            KeyScope::All => format!("target=\"{}\",index=\"all\"", escape_label(target)),
+        };
+
+        header(&mut out, "wowint_sent_integers_total", "counter", "Integers sent.");
+        for (key, counters) in &state.sent {
+            let _ = writeln!(out, "wowint_sent_integers_total{{{}}} {}", sent_labels(key), counters.integers);
+        }
+        header(&mut out, "wowint_sent_bytes_total", "counter", "Bytes sent, when the transport knows them.");
+        for (key, counters) in &state.sent {
+            let _ = writeln!(out, "wowint_sent_bytes_total{{{}}} {}", sent_labels(key), counters.bytes);
+        }
+        header(&mut out, "wowint_send_errors_total", "counter", "Integers that could not be sent.");
+        for (key, counters) in &state.sent {
+            let _ = writeln!(out, "wowint_send_errors_total{{{}}} {}", sent_labels(key), counters.errors);
+        }
+        header(&mut out, "wowint_key_presses_total", "counter", "Key and gamepad presses sent, by key.");
+        for (key, counters) in &state.sent {
+            for (press, count) in &counters.presses {
+                let _ = writeln!(
+                    out,
+                    "wowint_key_presses_total{{{},key=\"{}\"}} {}",
+                    sent_labels(key),
+                    escape_label(&press_name(*press)),
+                    count
+                );
+            }
+        }
+        header(&mut out, "wowint_key_releases_total", "counter", "Key and gamepad releases sent.");
+        for (key, counters) in &state.sent {
+            let _ = writeln!(out, "wowint_key_releases_total{{{}}} {}", sent_labels(key), counters.releases);
+        }
+        header(&mut out, "wowint_held_keys", "gauge", "Keys and gamepad buttons pressed and not released.");
+        for (key, counters) in &state.sent {
+            let _ = writeln!(out, "wowint_held_keys{{{}}} {}", sent_labels(key), counters.held.len());
+        }
+        header(&mut out, "wowint_send_latency_seconds", "histogram", "Time spent sending an integer.");
+        for (key, counters) in &state.sent {
+            let labels = sent_labels(key);
+            let histogram = &counters.latency;
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+                cumulative += count;
+                let _ = writeln!(out, "wowint_send_latency_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, cumulative);
+            }
+            let _ = writeln!(out, "wowint_send_latency_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, histogram.count);
+            let _ = writeln!(out, "wowint_send_latency_seconds_sum{{{}}} {}", labels, histogram.sum);
+            let _ = writeln!(out, "wowint_send_latency_seconds_count{{{}}} {}", labels, histogram.count);
+        }
+
+        header(&mut out, "wowint_received_datagrams_total", "counter", "Datagrams received by a listener.");
+        for (listener, counters) in &state.listeners {
+            let _ = writeln!(out, "wowint_received_datagrams_total{{listener=\"{}\"}} {}", escape_label(listener), counters.datagrams);
+        }
+        header(&mut out, "wowint_received_bytes_total", "counter", "Bytes received by a listener.");
+        for (listener, counters) in &state.listeners {
+            let _ = writeln!(out, "wowint_received_bytes_total{{listener=\"{}\"}} {}", escape_label(listener), counters.bytes);
+        }
+        header(&mut out, "wowint_received_integers_total", "counter", "Integers received and accepted.");
+        for ((listener, index), counters) in &state.received {
+            let _ = writeln!(
+                out,
+                "wowint_received_integers_total{{listener=\"{}\",index=\"{}\"}} {}",
+                escape_label(listener),
+                index,
+                counters.integers
+            );
+        }
+        header(&mut out, "wowint_dropped_integers_total", "counter", "Integers received and dropped by a policy.");
+        for ((listener, index), counters) in &state.received {
+            let _ = writeln!(
+                out,
+                "wowint_dropped_integers_total{{listener=\"{}\",index=\"{}\"}} {}",
+                escape_label(listener),
+                index,
+                counters.dropped
+            );
+        }
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn escape_label(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Wraps a sender and counts what it sends under the `target` label.
+pub struct MetricsSender<S: IntegerSender> {
+    sender: S,
+    metrics: Arc<IntegerMetrics>,
+    target: String,
+    // Index counted for `send_integer_to_target`
+    index: i32,
+}
+
+impl<S: IntegerSender> MetricsSender<S> {
+    pub fn new(sender: S, metrics: Arc<IntegerMetrics>, target: &str, index: i32) -> MetricsSender<S> {
+        MetricsSender { sender, metrics, target: target.to_string(), index }
+    }
+
+    pub fn sender(&self) -> &S {
+        &self.sender
+    }
+
+    fn count(&self, scope: KeyScope, value: i32, send: impl FnOnce() -> WowIntResult<()>) -> WowIntResult<()> {
+        let start = Instant::now();
+        let result = send();
+        match result {
+            Ok(()) => self.metrics.record_sent(&self.target, scope, value, 0, start.elapsed()),
+            Err(_) => self.metrics.record_send_error(&self.target, scope),
+        }
+        result
+    }
+}
+
+impl<S: IntegerSender> IntegerSender for MetricsSender<S> {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.count(KeyScope::Index(self.index), value, || self.sender.send_integer_to_target(value))
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.count(KeyScope::Index(index), value, || self.sender.send_integer_to_target_at_index(index, value))
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        self.count(KeyScope::All, value, || self.sender.send_integer_to_all(value))
+    }
+}
+
+/// Serves the metrics on `GET /metrics` for Prometheus.
+pub struct WowIntegerMetricsServer {
+    listener: TcpListener,
+    metrics: Arc<IntegerMetrics>,
+    errors: ErrorHandler,
+}
+
+impl WowIntegerMetricsServer {
+    /// Creates a server listening on the given address (e.g. "127.0.0.1:9473").
+    pub fn bind(listen_address: &str, metrics: Arc<IntegerMetrics>) -> WowIntResult<WowIntegerMetricsServer> {
+        Ok(WowIntegerMetricsServer {
+            listener: TcpListener::bind(listen_address)?,
+            metrics,
+            errors: ignore_errors(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Receives the accept errors and the errors of the scrapes that could not be answered.
+    pub fn set_error_handler<F: Fn(&WowIntError) + Send + Sync + 'static>(&mut self, handler: F) {
+        self.errors = Arc::new(handler);
+    }
+
+    /// Accepts connections forever, each request is answered on its own thread.
+    pub fn run(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    (self.errors)(&e.into());
+                    continue;
+                }
+            };
+            let metrics = Arc::clone(&self.metrics);
+            let errors = Arc::clone(&self.errors);
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, &metrics) {
+                    errors(&e.into());
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Runs the server on a background thread.
+    pub fn spawn(self) -> thread::JoinHandle<io::Result<()>> {
+        thread::spawn(move || self.run())
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, metrics: &IntegerMetrics) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("").split('?').next().unwrap_or("");
+    let (status, body) = match (method, path) {
+        ("GET", "/metrics") => ("200 OK", metrics.to_prometheus()),
+        (_, "/metrics") => ("405 Method Not Allowed", "Method not allowed\n".to_string()),
+        _ => ("404 Not Found", "Not found\n".to_string()),
+    };
+    let text = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(text.as_bytes())?;
+    stream.flush()
+}
@@ -0,0 +1,485 @@
+//! # MIDI
+//! Drives characters from MIDI: a note-on sends the press integer of a key,
+//! the note-off sends its release (+1000), and control changes move the Xbox sticks and triggers.
+//! Standard MIDI Files can be played offline and raw MIDI bytes (from a serial port,
+//! a virtual port or a test) are read with `MidiStreamParser`, so no hardware is needed.
+//!
+//! Mapping file, one rule per line:
+//!
+//! ```text
+//! # note <number> <key name or integer>
+//! note 60 Space
+//! note 62 1104
+//! # cc <number> <axis>, 0 to 127 is -100% to 100% for a stick, 0% to 100% for a trigger
+//! cc 1 left_stick_horizontal
+//! cc 2 left_trigger
+//! # send to the player index of the MIDI channel (1 to 16) instead of the target own index
+//! channel_as_index
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::error::{WowIntError, WowIntResult};
+use super::watchdog::HeldKeys;
+use super::xbox::{XboxAxis, XboxAxisTracker};
+use super::{EnumWowKey, IntegerSender};
+
+/// Tempo of a MIDI file without tempo event: 120 beats per minute.
+const DEFAULT_MICROSECONDS_PER_QUARTER: u64 = 500_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+    /// Channels are 0 to 15.
+    NoteOn { channel: u8, note: u8, velocity: u8 },
+    NoteOff { channel: u8, note: u8, velocity: u8 },
+    ControlChange { channel: u8, controller: u8, value: u8 },
+    /// Any other channel message, not used by the mapping.
+    Other { status: u8 },
+}
+
+/// Number of data bytes following a channel status byte.
+fn data_size(status: u8) -> usize {
+    match status & 0xF0 {
+        0xC0 | 0xD0 => 1,
+        _ => 2,
+    }
+}
+
+fn channel_message(status: u8, data: &[u8]) -> MidiMessage {
+    let channel = status & 0x0F;
+    match (status & 0xF0, data) {
+        (0x90, &[note, velocity]) if velocity > 0 => MidiMessage::NoteOn { channel, note, velocity },
+        // A note-on with a velocity of zero is a note-off
+        (0x80, &[note, velocity]) | (0x90, &[note, velocity]) => MidiMessage::NoteOff { channel, note, velocity },
+        (0xB0, &[controller, value]) => MidiMessage::ControlChange { channel, controller, value },
+        _ => MidiMessage::Other { status },
+    }
+}
+
+/// Reads raw MIDI bytes one at a time, with running status.
+/// System exclusive and real time bytes are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct MidiStreamParser {
+    running_status: Option<u8>,
+    data: Vec<u8>,
+    in_sysex: bool,
+}
+
+impl MidiStreamParser {
+    pub fn new() -> MidiStreamParser {
+        MidiStreamParser::default()
+    }
+
+    /// Adds a byte, returns a message when it is complete.
+    pub fn push(&mut self, byte: u8) -> Option<MidiMessage> {
+        if byte >= 0xF8 {
+            return None;
+        }
+        if byte & 0x80 != 0 {
+            self.data.clear();
+            self.in_sysex = byte == 0xF0;
+            // System common messages cancel the running status
+            self.running_status = if byte < 0xF0 { Some(byte) } else { None };
+            return None;
+        }
+        if self.in_sysex {
+            return None;
+        }
+        let status = self.running_status?;
+        self.data.push(byte);
+        if self.data.len() < data_size(status) {
+            return None;
+        }
+        let message = channel_message(status, &self.data);
+        self.data.clear();
+        Some(message)
+    }
+
+    /// Adds several bytes, returns the messages completed.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Vec<MidiMessage> {
+        bytes.iter().filter_map(|&byte| self.push(byte)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedMidiMessage {
+    /// Time since the start of the file.
+    pub time: Duration,
+    pub message: MidiMessage,
+}
+
+/// Messages of a Standard MIDI File, all tracks merged and sorted by time.
+#[derive(Debug, Clone, Default)]
+pub struct MidiFile {
+    pub messages: Vec<TimedMidiMessage>,
+}
+
+fn invalid(message: &str) -> WowIntError {
+    WowIntError::parse("MIDI file", message)
+}
+
+struct ByteReader<'a> {
+    buf: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn is_empty(&self) -> bool {
+        self.position >= self.buf.len()
+    }
+
+    fn bytes(&mut self, count: usize) -> WowIntResult<&'a [u8]> {
+        let end = self.position.checked_add(count).filter(|&end| end <= self.buf.len());
+        let end = end.ok_or_else(|| invalid("truncated"))?;
+        let bytes = &self.buf[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> WowIntResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> WowIntResult<u32> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Variable length quantity: 7 bits per byte, high bit set on all but the last byte.
+    fn variable_length(&mut self) -> WowIntResult<u32> {
+        let mut value: u32 = 0;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("variable length quantity longer than 4 bytes"))
+    }
+}
+
+/// Event of a track before the ticks are converted to time.
+enum TrackEvent {
+    Message(MidiMessage),
+    Tempo(u64),
+}
+
+fn read_track(data: &[u8], events: &mut Vec<(u64, usize, TrackEvent)>) -> WowIntResult<()> {
+    let mut reader = ByteReader { buf: data, position: 0 };
+    let mut tick: u64 = 0;
+    let mut running_status: Option<u8> = None;
+    while !reader.is_empty() {
+        tick += reader.variable_length()? as u64;
+        let mut status = reader.byte()?;
+        let mut first_data = None;
+        if status & 0x80 == 0 {
+            first_data = Some(status);
+            status = running_status.ok_or_else(|| invalid("data byte without running status"))?;
+        }
+        match status {
+            0xFF => {
+                let meta_type = reader.byte()?;
+                let length = reader.variable_length()? as usize;
+                let meta = reader.bytes(length)?;
+                if meta_type == 0x51 && length == 3 {
+                    let tempo = ((meta[0] as u64) << 16) | ((meta[1] as u64) << 8) | meta[2] as u64;
+                    events.push((tick, events.len(), TrackEvent::Tempo(tempo)));
+                } else if meta_type == 0x2F {
+                    return Ok(());
+                }
+            }
+            0xF0 | 0xF7 => {
+                let length = reader.variable_length()? as usize;
+                reader.bytes(length)?;
+            }
+            _ => {
+                running_status = Some(status);
+                let mut data = Vec::with_capacity(2);
+                if let Some(byte) = first_data {
+                    data.push(byte);
+                }
+                while data.len() < data_size(status) {
+                    data.push(reader.byte()?);
+                }
+                events.push((tick, events.len(), TrackEvent::Message(channel_message(status, &data))));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl MidiFile {
+    /// Reads a Standard MIDI File, the tracks are played together (format 0 and 1).
+    pub fn parse(buf: &[u8]) -> WowIntResult<MidiFile> {
+        let mut reader = ByteReader { buf, position: 0 };
+        if reader.bytes(4)? != b"MThd" {
+            return Err(invalid("missing MThd header"));
+        }
+        let header_length = reader.u32()? as usize;
+        let header = reader.bytes(header_length)?;
+        if header_length < 6 {
+            return Err(invalid("header too short"));
+        }
+        let division = u16::from_be_bytes([header[4], header[5]]);
+        let mut events = Vec::new();
+        while !reader.is_empty() {
+            let chunk_type = reader.bytes(4)?;
+            let length = reader.u32()? as usize;
+            let data = reader.bytes(length)?;
+            if chunk_type == b"MTrk" {
+                read_track(data, &mut events)?;
+            }
+        }
+        // Sort by tick, keeping the file order for events at the same tick
+        events.sort_by_key(|&(tick, order, _)| (tick, order));
+
+        let mut messages = Vec::new();
+        let mut tempo = DEFAULT_MICROSECONDS_PER_QUARTER;
+        let mut last_tick = 0;
+        let mut microseconds: f64 = 0.0;
+        for (tick, _, event) in events {
+            microseconds += (tick - last_tick) as f64 * tick_microseconds(division, tempo);
+            last_tick = tick;
+            match event {
+                TrackEvent::Tempo(new_tempo) => tempo = new_tempo,
+                TrackEvent::Message(message) => messages.push(TimedMidiMessage {
+                    time: Duration::from_micros(microseconds.round() as u64),
+                    message,
+                }),
+            }
+        }
+        Ok(MidiFile { messages })
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> WowIntResult<MidiFile> {
+        MidiFile::parse(&fs::read(path)?)
+    }
+}
+
+fn tick_microseconds(division: u16, tempo: u64) -> f64 {
+    if division & 0x8000 != 0 {
+        // SMPTE: frames per second (negative) and ticks per frame
+        let frames_per_second = -((division >> 8) as u8 as i8) as f64;
+        let ticks_per_frame = (division & 0xFF) as f64;
+        1_000_000.0 / (frames_per_second * ticks_per_frame).max(1.0)
+    } else {
+        tempo as f64 / (division.max(1)) as f64
+    }
+}
+
+/// Which integers the notes and control changes send.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MidiMapping {
+    notes: HashMap<u8, i32>,
+    controls: HashMap<u8, XboxAxis>,
+    channel_as_index: bool,
+}
+
+impl MidiMapping {
+    pub fn new() -> MidiMapping {
+        MidiMapping::default()
+    }
+
+    /// The note sends this press integer, and its release on note-off.
+    pub fn map_note(&mut self, note: u8, press_integer: i32) {
+        self.notes.insert(note, press_integer);
+    }
+
+    pub fn map_control(&mut self, controller: u8, axis: XboxAxis) {
+        self.controls.insert(controller, axis);
+    }
+
+    /// Sends to the player index of the MIDI channel (1 to 16).
+    pub fn set_channel_as_index(&mut self, channel_as_index: bool) {
+        self.channel_as_index = channel_as_index;
+    }
+
+    /// Parses rules written in the text format described in the module documentation.
+    pub fn parse(text: &str) -> WowIntResult<MidiMapping> {
+        let keys = EnumWowKey::new();
+        let mut mapping = MidiMapping::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if !parse_rule(&mut mapping, &keys, line) {
+                return Err(WowIntError::parse_at_line("MIDI mapping", line_number + 1, line));
+            }
+        }
+        Ok(mapping)
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> WowIntResult<MidiMapping> {
+        MidiMapping::parse(&fs::read_to_string(path)?)
+    }
+}
+
+fn parse_rule(mapping: &mut MidiMapping, keys: &EnumWowKey, line: &str) -> bool {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["note", note, key] => {
+            let press = match key.parse::<i32>() {
+                Ok(integer) => Some(integer),
+                Err(_) => keys.get_key_info_ignore_case(key).map(|info| info.press_integer() as i32),
+            };
+            match (note.parse::<u8>(), press) {
+                (Ok(note), Some(press)) if note < 128 => {
+                    mapping.map_note(note, press);
+                    true
+                }
+                _ => false,
+            }
+        }
+        ["cc", controller, axis] => match (controller.parse::<u8>(), XboxAxis::from_name(axis)) {
+            (Ok(controller), Some(axis)) if controller < 128 => {
+                mapping.map_control(controller, axis);
+                true
+            }
+            _ => false,
+        },
+        ["channel_as_index"] => {
+            mapping.set_channel_as_index(true);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Integer to send, the index being `None` for the target own index.
+pub type MidiOutput = (Option<i32>, i32);
+
+/// Applies a mapping to MIDI messages, remembering the stick and trigger levels.
+#[derive(Debug, Clone, Default)]
+pub struct MidiTranslator {
+    mapping: MidiMapping,
+    axes: XboxAxisTracker,
+}
+
+impl MidiTranslator {
+    pub fn new(mapping: MidiMapping) -> MidiTranslator {
+        MidiTranslator { mapping, axes: XboxAxisTracker::new() }
+    }
+
+    /// Returns the integers to send for a message, in order.
+    pub fn translate(&mut self, message: &MidiMessage) -> Vec<MidiOutput> {
+        let (channel, values) = match *message {
+            MidiMessage::NoteOn { channel, note, .. } => {
+                (channel, self.mapping.notes.get(&note).map(|&press| vec![press]))
+            }
+            MidiMessage::NoteOff { channel, note, .. } => {
+                (channel, self.mapping.notes.get(&note).map(|&press| vec![press + 1000]))
+            }
+            MidiMessage::ControlChange { channel, controller, value } => {
+                let player = if self.mapping.channel_as_index { Some(channel as i32 + 1) } else { None };
+                let axis = self.mapping.controls.get(&controller).copied();
+                (channel, axis.map(|axis| self.axes.update(player, axis, control_to_axis(axis, value))))
+            }
+            MidiMessage::Other { .. } => return Vec::new(),
+        };
+        let index = if self.mapping.channel_as_index { Some(channel as i32 + 1) } else { None };
+        values.unwrap_or_default().into_iter().map(|value| (index, value)).collect()
+    }
+
+    /// Translates every message of a file, with its time, without waiting.
+    pub fn translate_file(&mut self, file: &MidiFile) -> Vec<(Duration, MidiOutput)> {
+        let mut outputs = Vec::new();
+        for timed in &file.messages {
+            for output in self.translate(&timed.message) {
+                outputs.push((timed.time, output));
+            }
+        }
+        outputs
+    }
+}
+
+/// Converts a control change value (0 to 127) to an axis value.
+pub fn control_to_axis(axis: XboxAxis, value: u8) -> f32 {
+    if axis.is_trigger() {
+        value as f32 / 127.0
+    } else {
+        ((value as f32 - 64.0) / 63.0).clamp(-1.0, 1.0)
+    }
+}
+
+fn send_output<S: IntegerSender>(target: &S, (index, value): MidiOutput) -> WowIntResult<()> {
+    match index {
+        Some(index) => target.send_integer_to_target_at_index(index, value),
+        None => target.send_integer_to_target(value),
+    }
+}
+
+/// Presses sent during a playback and not released yet.
+#[derive(Default)]
+struct HeldPresses {
+    presses: HeldKeys<Option<i32>>,
+}
+
+impl HeldPresses {
+    fn send<S: IntegerSender>(&mut self, target: &S, output: MidiOutput) -> WowIntResult<()> {
+        send_output(target, output)?;
+        let (index, value) = output;
+        self.presses.track(index, value);
+        Ok(())
+    }
+
+    /// Sends the release of every held press, the first send error is returned.
+    fn release_all<S: IntegerSender>(mut self, target: &S) -> WowIntResult<()> {
+        let mut result = Ok(());
+        for (index, press) in self.presses.take_all() {
+            let sent = send_output(target, (index, press + 1000));
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+}
+
+/// Plays a MIDI file on a target, waiting between the messages as in the file.
+/// The keys still pressed when the playback ends, or fails, are released.
+pub fn play_midi_file<S: IntegerSender>(file: &MidiFile, translator: &mut MidiTranslator, target: &S) -> WowIntResult<()> {
+    let start = Instant::now();
+    let mut held = HeldPresses::default();
+    let mut play = || -> WowIntResult<()> {
+        for (time, output) in translator.translate_file(file) {
+            if let Some(wait) = time.checked_sub(start.elapsed()) {
+                thread::sleep(wait);
+            }
+            held.send(target, output)?;
+        }
+        Ok(())
+    };
+    let played = play();
+    let released = held.release_all(target);
+    played.and(released)
+}
+
+/// Sends the integers of raw MIDI bytes as they are read (e.g. from a serial port).
+/// The keys still pressed when the stream ends, or fails, are released.
+pub fn play_midi_stream<R: io::Read, S: IntegerSender>(mut reader: R, translator: &mut MidiTranslator, target: &S) -> WowIntResult<()> {
+    let mut parser = MidiStreamParser::new();
+    let mut held = HeldPresses::default();
+    let mut buf = [0u8; 256];
+    let mut play = || -> WowIntResult<()> {
+        loop {
+            let size = reader.read(&mut buf)?;
+            if size == 0 {
+                return Ok(());
+            }
+            for message in parser.push_bytes(&buf[..size]) {
+                for output in translator.translate(&message) {
+                    held.send(target, output)?;
+                }
+            }
+        }
+    };
+    let played = play();
+    let released = held.release_all(target);
+    played.and(released)
+}
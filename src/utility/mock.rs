@@ -0,0 +1,173 @@
+//! # Mock sender
+//! In-memory `IntegerSender` for unit tests, no socket is opened.
+//! Records what was sent, can simulate errors and latency,
+//! and has assertions for the usual mistakes (a tap without release, a key left pressed).
+//!
+//! ```text
+//! let mock = MockSender::new();
+//! bot.jump(&mock)?;
+//! mock.assert_tapped("Space");
+//! mock.assert_no_stuck_keys();
+//! ```
+
+use std::collections::HashSet;
+use std::io;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::describe::describe_value;
+use super::error::WowIntResult;
+use super::watchdog::{HeldKeys, KeyScope};
+use super::{EnumWowKey, IntegerSender};
+
+/// Integer recorded by the mock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SentInteger {
+    pub scope: KeyScope,
+    pub value: i32,
+    pub instant: Instant,
+}
+
+#[derive(Default)]
+struct MockState {
+    sent: Vec<SentInteger>,
+    fail_next: usize,
+    always_fail: bool,
+    failures: usize,
+}
+
+#[derive(Default)]
+pub struct MockSender {
+    state: Mutex<MockState>,
+    latency: Duration,
+    index: i32,
+}
+
+impl MockSender {
+    /// Mock of a sender with the own index 0.
+    pub fn new() -> MockSender {
+        MockSender::default()
+    }
+
+    /// Own index, `send_integer_to_target` is recorded as `KeyScope::Index` of it.
+    pub fn with_index(mut self, index: i32) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Waits this long in every send, before it is recorded.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// The next `count` sends fail and are not recorded.
+    pub fn fail_next(&self, count: usize) {
+        self.state.lock().unwrap().fail_next = count;
+    }
+
+    /// Every send fails until it is set back to false.
+    pub fn set_always_fail(&self, always_fail: bool) {
+        self.state.lock().unwrap().always_fail = always_fail;
+    }
+
+    /// Number of sends that failed on purpose.
+    pub fn failures(&self) -> usize {
+        self.state.lock().unwrap().failures
+    }
+
+    /// Integers sent successfully, in order.
+    pub fn sent(&self) -> Vec<SentInteger> {
+        self.state.lock().unwrap().sent.clone()
+    }
+
+    /// Values sent successfully, in order, whatever the player.
+    pub fn values(&self) -> Vec<i32> {
+        self.state.lock().unwrap().sent.iter().map(|sent| sent.value).collect()
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().sent.clear();
+    }
+
+    fn record(&self, scope: KeyScope, value: i32) -> WowIntResult<()> {
+        if !self.latency.is_zero() {
+            thread::sleep(self.latency);
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.always_fail || state.fail_next > 0 {
+            state.fail_next = state.fail_next.saturating_sub(1);
+            state.failures += 1;
+            return Err(io::Error::other(format!("Simulated send error for {}", value)).into());
+        }
+        state.sent.push(SentInteger {
+            scope,
+            value,
+            instant: Instant::now(),
+        });
+        Ok(())
+    }
+
+    /// Press integers without their release (+1000), with their player.
+    /// `RELEASE_ALL` releases the gamepad buttons of its player.
+    pub fn stuck_keys(&self) -> Vec<(KeyScope, i32)> {
+        let mut held = HeldKeys::new();
+        for sent in self.state.lock().unwrap().sent.iter() {
+            held.track(sent.scope, sent.value);
+        }
+        held.keys()
+    }
+
+    /// Panics if a key or a gamepad button was pressed and not released.
+    pub fn assert_no_stuck_keys(&self) {
+        let stuck = self.stuck_keys();
+        if !stuck.is_empty() {
+            let names: Vec<String> = stuck
+                .iter()
+                .map(|(scope, press)| format!("{} ({:?})", describe_value(*press), scope))
+                .collect();
+            panic!("Stuck keys: {}", names.join(", "));
+        }
+    }
+
+    /// Panics unless the key (e.g. "Space") was pressed then released on the same player.
+    pub fn assert_tapped(&self, key: &str) {
+        let keys = EnumWowKey::new();
+        let press = match keys.find_key(key) {
+            Ok(info) => info.press_integer() as i32,
+            Err(e) => panic!("{}", e),
+        };
+        let sent = self.sent();
+        let mut pressed = HashSet::new();
+        for integer in &sent {
+            if integer.value == press {
+                pressed.insert(integer.scope);
+            } else if integer.value == press + 1000 && pressed.contains(&integer.scope) {
+                return;
+            }
+        }
+        let values: Vec<String> = sent.iter().map(|integer| describe_value(integer.value)).collect();
+        panic!("{} was not tapped, sent: [{}]", key, values.join(", "));
+    }
+
+    /// Panics unless the value was sent.
+    pub fn assert_sent(&self, value: i32) {
+        let values = self.values();
+        assert!(values.contains(&value), "{} was not sent, sent: {:?}", describe_value(value), values);
+    }
+}
+
+impl IntegerSender for MockSender {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.record(KeyScope::Index(self.index), value)
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.record(KeyScope::Index(index), value)
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        self.record(KeyScope::All, value)
+    }
+}
@@ -8,63 +8,456 @@
 
 
 
-use std::net::UdpSocket;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use rand::Rng;
 
+use batch::{decode_index_integers, encode_index_integers, send_datagrams, BATCH_MAGIC, MAX_BATCH_INTEGERS};
+use clock::{ClockEstimate, ClockSample, ClockTracker};
+use encrypted::{EncryptedPacketDecoder, PacketEncrypter};
+use error::{WowIntError, WowIntResult};
+use metrics::IntegerMetrics;
+use signed::{PacketSigner, SignedPacketValidator};
+use watchdog::KeyScope;
+
+pub mod batch;
+pub mod clock;
+pub mod describe;
+pub mod encrypted;
+pub mod error;
+pub mod http;
+pub mod metrics;
+pub mod midi;
+pub mod mock;
+pub mod mqtt;
+pub mod osc;
+pub mod panic_stop;
+pub mod pcap;
+pub mod pipe;
+pub mod policy;
+pub mod record;
+pub mod relay;
+pub mod reliable;
+pub mod session;
+pub mod signed;
+pub mod simulator;
+pub mod tcp;
+pub mod watchdog;
+pub mod websocket;
+pub mod xbox;
+
 pub struct WowIntegerTarget {
-    // Player IPv4 address to target
+    // Player IP address or host name to target, without brackets
     ip: String,
     // Player port to target
     port: u16,
     // Player index to control
     index: i32,
+    // How the packets are written
+    framing: PacketFraming,
+    // Address resolved at construction, otherwise at each send
+    resolved: Option<SocketAddr>,
+    // Packs the integers of `send_many` in batch datagrams
+    batch_datagrams: bool,
+    // Counts what is sent, see `metrics`
+    metrics: Option<Arc<IntegerMetrics>>,
 }
 
 impl WowIntegerTarget {
     //! Creates a new player with the given IP, port, and index.
+    //! The IP (or host name) is only resolved when sending, use `try_new` to check it upfront.
     pub fn new(ip: &str, port: u16, index: i32) -> WowIntegerTarget {
         WowIntegerTarget {
-            ip: ip.to_string(),
+            ip: strip_brackets(ip).to_string(),
             port,
             index,
+            framing: PacketFraming::Plain,
+            resolved: None,
+            batch_datagrams: false,
+            metrics: None,
+        }
+    }
+
+    /// Creates a player from an IP address or a host name, resolved now.
+    /// IPv6 addresses are accepted with or without brackets ("::1", "[::1]").
+    pub fn try_new(host: &str, port: u16, index: i32) -> WowIntResult<WowIntegerTarget> {
+        let address = resolve_address(host, port)?;
+        let mut target = WowIntegerTarget::new(host, port, index);
+        target.resolved = Some(address);
+        Ok(target)
+    }
+
+    /// Creates a player from "ip:port", "host:port" or "[ipv6]:port", resolved now.
+    pub fn try_from_address(address: &str, index: i32) -> WowIntResult<WowIntegerTarget> {
+        let (host, port) = split_address(address)?;
+        WowIntegerTarget::try_new(host, port, index)
+    }
+
+    pub fn from_socket_addr(address: SocketAddr, index: i32) -> WowIntegerTarget {
+        let mut target = WowIntegerTarget::new(&address.ip().to_string(), address.port(), index);
+        target.resolved = Some(address);
+        target
+    }
+
+    /// Creates a player from the first address of anything resolving to socket addresses.
+    /// Fails with `WowIntError::Resolve` naming the addresses if none is found.
+    pub fn from_socket_addrs<A: ToSocketAddrs + std::fmt::Debug>(addresses: A, index: i32) -> WowIntResult<WowIntegerTarget> {
+        let resolve_error = |source| WowIntError::Resolve {
+            host: format!("{:?}", addresses).trim_matches('"').to_string(),
+            source,
+        };
+        let address = addresses
+            .to_socket_addrs()
+            .map_err(resolve_error)?
+            .next()
+            .ok_or_else(|| resolve_error(std::io::Error::new(std::io::ErrorKind::NotFound, "no address found")))?;
+        Ok(WowIntegerTarget::from_socket_addr(address, index))
+    }
+
+    /// Uses the given framing for the packets sent.
+    pub fn with_framing(mut self, framing: PacketFraming) -> WowIntegerTarget {
+        self.framing = framing;
+        self
+    }
+
+    /// Signs every packet sent with the shared key (see `signed`).
+    pub fn with_signing_key(self, key: &[u8]) -> WowIntegerTarget {
+        self.with_framing(PacketFraming::Signed(PacketSigner::new(key)))
+    }
+
+    /// Encrypts every packet sent with the pre-shared key (see `encrypted`).
+    pub fn with_encryption_key(self, pre_shared_key: &[u8]) -> WowIntegerTarget {
+        self.with_framing(PacketFraming::Encrypted(PacketEncrypter::new(pre_shared_key)))
+    }
+
+    /// Packs the integers of `send_many` in batch datagrams (see `batch`), plain framing only.
+    /// The player must decode them with `decode_index_integers`.
+    pub fn with_batch_datagrams(mut self, batch_datagrams: bool) -> WowIntegerTarget {
+        self.batch_datagrams = batch_datagrams;
+        self
+    }
+
+    /// Counts the integers, bytes, errors and send latency under the address of the player.
+    pub fn with_metrics(mut self, metrics: Arc<IntegerMetrics>) -> WowIntegerTarget {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn framing(&self) -> &PacketFraming {
+        &self.framing
+    }
+
+    /// Address of the player as "ip:port", or "[ipv6]:port".
+    pub fn address(&self) -> String {
+        format_address(&self.ip, self.port)
+    }
+
+    /// Pings the player (see `clock`), its relay must be running to answer.
+    pub fn ping(&self, timeout: Duration) -> WowIntResult<ClockSample> {
+        clock::ping(self.socket_addr()?, timeout)
+    }
+
+    /// Round trip and clock offset to the player, from `count` pings.
+    pub fn measure_clock(&self, count: usize, timeout: Duration) -> WowIntResult<ClockEstimate> {
+        clock::measure_clock(self.socket_addr()?, count, timeout)
+    }
+
+    /// Pings the player every `interval` (at least `clock::MIN_TRACK_INTERVAL`) in the background, see `ClockTracker::estimate`.
+    pub fn track_clock(&self, interval: Duration) -> WowIntResult<ClockTracker> {
+        ClockTracker::start(self.socket_addr()?, interval)
+    }
+
+    /// Socket address of the player, resolved now if it was not at construction.
+    pub fn socket_addr(&self) -> WowIntResult<SocketAddr> {
+        match self.resolved {
+            Some(address) => Ok(address),
+            None => resolve_address(&self.ip, self.port),
         }
     }
 }
 
-pub trait IntegerUdpSender {
-    /// Sends an integer to the target player using UDP.
-    fn send_integer_to_target(&self, value: i32) -> std::io::Result<()>;
+fn strip_brackets(host: &str) -> &str {
+    host.strip_prefix('[').and_then(|host| host.strip_suffix(']')).unwrap_or(host)
+}
+
+/// Writes "host:port", with brackets around IPv6 addresses.
+pub fn format_address(host: &str, port: u16) -> String {
+    let host = strip_brackets(host);
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// Splits "host:port" or "[ipv6]:port" into the host (without brackets) and the port.
+pub fn split_address(address: &str) -> WowIntResult<(&str, u16)> {
+    let invalid = |message: &str| WowIntError::InvalidAddress {
+        address: address.to_string(),
+        message: message.to_string(),
+    };
+    let (host, port) = match address.strip_prefix('[') {
+        Some(rest) => {
+            let (host, port) = rest.split_once(']').ok_or_else(|| invalid("missing ]"))?;
+            (host, port.strip_prefix(':').ok_or_else(|| invalid("missing port"))?)
+        }
+        None => {
+            let (host, port) = address.rsplit_once(':').ok_or_else(|| invalid("missing port"))?;
+            if host.contains(':') {
+                return Err(invalid("IPv6 addresses need brackets, e.g. [::1]:7073"));
+            }
+            (host, port)
+        }
+    };
+    let port = port.parse().map_err(|_| invalid("invalid port"))?;
+    Ok((host, port))
+}
+
+/// Resolves an IP address or a host name.
+/// Fails with `InvalidAddress` if the text can not be an address, `Resolve` if the name is unknown.
+pub fn resolve_address(host: &str, port: u16) -> WowIntResult<SocketAddr> {
+    let host = strip_brackets(host);
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, port));
+    }
+    let valid_host_name = !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        });
+    if !valid_host_name {
+        return Err(WowIntError::InvalidAddress {
+            address: host.to_string(),
+            message: "not an IP address or a host name".to_string(),
+        });
+    }
+    let resolve_error = |source| WowIntError::Resolve { host: host.to_string(), source };
+    (host, port)
+        .to_socket_addrs()
+        .map_err(resolve_error)?
+        .next()
+        .ok_or_else(|| resolve_error(std::io::Error::new(std::io::ErrorKind::NotFound, "no address found")))
+}
+
+/// How an index and an integer are written in a datagram or a stream frame.
+pub enum PacketFraming {
+    /// 8 bytes: index + integer.
+    Plain,
+    /// 36 bytes signed with a shared key.
+    Signed(PacketSigner),
+    /// 36 bytes encrypted with a pre-shared key.
+    Encrypted(PacketEncrypter),
+}
+
+impl PacketFraming {
+    pub fn encode(&self, index: i32, value: i32) -> Vec<u8> {
+        match self {
+            PacketFraming::Plain => encode_index_integer(index, value).to_vec(),
+            PacketFraming::Signed(signer) => signer.encode(index, value).to_vec(),
+            PacketFraming::Encrypted(encrypter) => encrypter.encode(index, value).to_vec(),
+        }
+    }
+}
+
+/// Reads the datagrams written with the matching `PacketFraming`.
+pub enum PacketDecoder {
+    Plain,
+    Signed(SignedPacketValidator),
+    Encrypted(EncryptedPacketDecoder),
+}
+
+impl PacketDecoder {
+    /// Returns the index and integer of a datagram.
+    /// A plain datagram of the wrong size is ignored (`Ok(None)`),
+    /// a signed or encrypted one that is not valid is an error.
+    pub fn decode(&mut self, buf: &[u8]) -> WowIntResult<Option<(i32, i32)>> {
+        match self {
+            PacketDecoder::Plain => Ok(decode_index_integer(buf)),
+            PacketDecoder::Signed(validator) => validator.validate(buf).map(Some),
+            PacketDecoder::Encrypted(decoder) => decoder.decode(buf).map(Some),
+        }
+    }
+
+    /// Like `decode`, and also reads the plain batch datagrams (see `batch`).
+    /// Returns an empty list for an ignored datagram.
+    pub fn decode_many(&mut self, buf: &[u8]) -> WowIntResult<Vec<(i32, i32)>> {
+        match self {
+            PacketDecoder::Plain => Ok(decode_index_integers(buf).unwrap_or_default()),
+            _ => Ok(self.decode(buf)?.into_iter().collect()),
+        }
+    }
+}
+
+/// Sends integers to players, whatever the transport (UDP, TCP...).
+pub trait IntegerSender {
+    /// Sends an integer to the target player.
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()>;
 
     /// Sends an integer to a target player at a specific index.
-    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> std::io::Result<()>;
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()>;
 
-    /// Sends an integer to all players using UDP.
-    fn send_integer_to_all(&self, value: i32) -> std::io::Result<()>;
+    /// Sends an integer to all players.
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()>;
+
+    /// Sends pairs of index and integer, in order.
+    /// Stops at the first error, transports able to batch them override it.
+    fn send_many(&self, integers: &[(i32, i32)]) -> WowIntResult<()> {
+        for &(index, value) in integers {
+            self.send_integer_to_target_at_index(index, value)?;
+        }
+        Ok(())
+    }
 }
 
-impl IntegerUdpSender for WowIntegerTarget {
+/// Previous name of `IntegerSender`, from when UDP was the only transport.
+pub use self::IntegerSender as IntegerUdpSender;
+
+impl IntegerSender for WowIntegerTarget {
     /// Sends an integer to the target player using UDP.
-    fn send_integer_to_target(&self, value: i32) -> std::io::Result<()> {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
         self.send_integer_to_target_at_index(self.index, value)
     }
 
     /// Sends an integer to the target player at a specific index using UDP.
-    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> std::io::Result<()> {
-        let socket = UdpSocket::bind("0.0.0.0:0")?;
-        let addr = format!("{}:{}", self.ip, self.port);
-        let mut buf = [0u8; 8];
-        buf[..4].copy_from_slice(&index.to_le_bytes());
-        buf[4..].copy_from_slice(&value.to_le_bytes());
-        socket.send_to(&buf, addr)?;
-        Ok(())
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        let start = Instant::now();
+        let buf = self.framing.encode(index, value);
+        let result = self.socket_addr().and_then(|addr| {
+            let send_error = |source| WowIntError::Send { target: self.address(), index, value, source };
+            let socket = bind_udp_socket(addr).map_err(send_error)?;
+            socket.send_to(&buf, addr).map_err(send_error)?;
+            Ok(())
+        });
+        if let Some(metrics) = &self.metrics {
+            match result {
+                Ok(()) => metrics.record_sent(&self.address(), KeyScope::Index(index), value, buf.len(), start.elapsed()),
+                Err(_) => metrics.record_send_error(&self.address(), KeyScope::Index(index)),
+            }
+        }
+        result
     }
 
     /// Sends an integer to all players using UDP.
-    fn send_integer_to_all(&self, value: i32) -> std::io::Result<()> {
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
         // Assuming you want to send to multiple players, modify as needed.
         // Currently sending to a single player (use multicast or broadcast for more).
         self.send_integer_to_target(value)
     }
+
+    /// Sends every integer with one socket, in one system call on Linux.
+    /// The error is the one of the first integer that was not sent.
+    fn send_many(&self, integers: &[(i32, i32)]) -> WowIntResult<()> {
+        if integers.is_empty() {
+            return Ok(());
+        }
+        let start = Instant::now();
+        let batch = self.batch_datagrams && matches!(self.framing, PacketFraming::Plain);
+        let integers_per_datagram = if batch { MAX_BATCH_INTEGERS } else { 1 };
+        let datagrams: Vec<Vec<u8>> = if batch {
+            integers.chunks(MAX_BATCH_INTEGERS).map(encode_index_integers).collect()
+        } else {
+            integers.iter().map(|&(index, value)| self.framing.encode(index, value)).collect()
+        };
+        // Number of integers sent, the ones after it failed
+        let (sent, result) = match self.socket_addr() {
+            Ok(addr) => {
+                let outcome = bind_udp_socket(addr)
+                    .map_err(|source| (0, source))
+                    .and_then(|socket| send_datagrams(&socket, &datagrams, addr));
+                match outcome {
+                    Ok(()) => (integers.len(), Ok(())),
+                    Err((datagrams_sent, source)) => {
+                        let sent = datagrams_sent * integers_per_datagram;
+                        let (index, value) = integers[sent];
+                        (sent, Err(WowIntError::Send { target: self.address(), index, value, source }))
+                    }
+                }
+            }
+            Err(e) => (0, Err(e)),
+        };
+        if let Some(metrics) = &self.metrics {
+            let address = self.address();
+            let counted: Vec<(i32, i32, usize)> = integers[..sent]
+                .iter()
+                .enumerate()
+                .map(|(position, &(index, value))| {
+                    // A batch datagram counts its magic with its first integer
+                    let bytes = match batch {
+                        true if position % MAX_BATCH_INTEGERS == 0 => BATCH_MAGIC.len() + 8,
+                        true => 8,
+                        false => datagrams[position].len(),
+                    };
+                    (index, value, bytes)
+                })
+                .collect();
+            metrics.record_sent_many(&address, &counted, start.elapsed());
+            for &(index, _) in &integers[sent..] {
+                metrics.record_send_error(&address, KeyScope::Index(index));
+            }
+        }
+        result
+    }
+}
+
+fn bind_udp_socket(target: SocketAddr) -> std::io::Result<UdpSocket> {
+    UdpSocket::bind(if target.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" })
+}
+
+/// Lets a shared sender (e.g. a watchdog also used by a thread) be used as a sender.
+impl<T: IntegerSender + ?Sized> IntegerSender for std::sync::Arc<T> {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        (**self).send_integer_to_target(value)
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        (**self).send_integer_to_target_at_index(index, value)
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        (**self).send_integer_to_all(value)
+    }
+
+    fn send_many(&self, integers: &[(i32, i32)]) -> WowIntResult<()> {
+        (**self).send_many(integers)
+    }
+}
+
+/// How long a tap holds its key when no duration is given.
+pub const DEFAULT_TAP_DURATION: Duration = Duration::from_millis(100);
+/// Longest tap, so a remote command can not hold a key (and a thread) for long.
+pub const MAX_TAP_DURATION: Duration = Duration::from_secs(10);
+
+/// Presses a key, waits for the duration (at most `MAX_TAP_DURATION`) then releases it.
+/// Sent to the player at `index`, or to the target of the sender when `None`.
+pub fn tap<S: IntegerSender + ?Sized>(sender: &S, index: Option<i32>, press: i32, duration: Duration) -> WowIntResult<()> {
+    let send = |value| match index {
+        Some(index) => sender.send_integer_to_target_at_index(index, value),
+        None => sender.send_integer_to_target(value),
+    };
+    send(press)?;
+    std::thread::sleep(duration.min(MAX_TAP_DURATION));
+    send(press + 1000)
+}
+
+/// Encodes an index and an integer as the 8 bytes (little endian) sent on the network.
+pub fn encode_index_integer(index: i32, value: i32) -> [u8; 8] {
+    let mut buf = [0u8; 8];
+    buf[..4].copy_from_slice(&index.to_le_bytes());
+    buf[4..].copy_from_slice(&value.to_le_bytes());
+    buf
+}
+
+/// Decodes a received datagram into an index and an integer.
+/// 8 bytes are read as index + integer, 4 bytes as an integer for index 0.
+pub fn decode_index_integer(buf: &[u8]) -> Option<(i32, i32)> {
+    match buf.len() {
+        4 => Some((0, i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]))),
+        8 => Some((
+            i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]),
+            i32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]),
+        )),
+        _ => None,
+    }
 }
 
 /// Returns a random float between -1.0 and 1.0.
@@ -80,12 +473,12 @@ pub fn get_random_integer_between(min: i32, max: i32) -> i32 {
     rand::thread_rng().gen_range(min..max)
 }
 
-pub fn get_random_integer_from_list(list: &Vec<i32>) -> i32 {
+pub fn get_random_integer_from_list(list: &[i32]) -> i32 {
     if list.is_empty() {
-        return 0;
+        0
     } else {
         let index = rand::thread_rng().gen_range(0..list.len());
-        return list[index];
+        list[index]
     }
 }
 
@@ -93,11 +486,38 @@ pub fn get_random_integer_from_list(list: &Vec<i32>) -> i32 {
 
 
 pub struct WowKeyInfo {
-    keyName: String,
-    windowDecimal: u8,
-    windowHexadecimal: u16,
-    pressInteger: u16,
-    releaseInteger: u16,
+    key_name: String,
+    window_decimal: u8,
+    window_hexadecimal: u16,
+    press_integer: u16,
+    release_integer: u16,
+}
+
+impl WowKeyInfo {
+    /// Name of the key as used in the registry (e.g. "Space").
+    pub fn key_name(&self) -> &str {
+        &self.key_name
+    }
+
+    /// Windows virtual key code in decimal.
+    pub fn window_decimal(&self) -> u8 {
+        self.window_decimal
+    }
+
+    /// Windows virtual key code in hexadecimal.
+    pub fn window_hexadecimal(&self) -> u16 {
+        self.window_hexadecimal
+    }
+
+    /// Integer to send to press the key.
+    pub fn press_integer(&self) -> u16 {
+        self.press_integer
+    }
+
+    /// Integer to send to release the key.
+    pub fn release_integer(&self) -> u16 {
+        self.release_integer
+    }
 }
 
 
@@ -107,201 +527,225 @@ pub struct EnumWowKey {
 
 impl EnumWowKey {
 
+    /// Returns every key of the registry.
+    /// Some keys have several names (e.g. "Esc" and "Escape") and appear more than once.
+    pub fn keys(&self) -> &[WowKeyInfo] {
+        &self.list
+    }
+
     pub fn get_key_info(&self, key: &str) -> Option<&WowKeyInfo> {
-        self.list.iter().find(|&x| x.keyName == key)
+        self.list.iter().find(|&x| x.key_name == key)
+    }
+
+    /// Same as `get_key_info_ignore_case`, failing with `UnknownKey`.
+    pub fn find_key(&self, key: &str) -> WowIntResult<&WowKeyInfo> {
+        self.get_key_info_ignore_case(key).ok_or_else(|| WowIntError::unknown_key(key))
+    }
+
+    /// Same as `get_key_info` but "space" also finds "Space".
+    pub fn get_key_info_ignore_case(&self, key: &str) -> Option<&WowKeyInfo> {
+        self.get_key_info(key)
+            .or_else(|| self.list.iter().find(|&x| x.key_name.eq_ignore_ascii_case(key)))
     }
 
     pub fn get_key_info_by_decimal(&self, decimal: u8) -> Option<&WowKeyInfo> {
-        self.list.iter().find(|&x| x.windowDecimal == decimal)
+        self.list.iter().find(|&x| x.window_decimal == decimal)
     }
 
     pub fn get_key_info_by_hexadecimal(&self, hexadecimal: u16) -> Option<&WowKeyInfo> {
-        self.list.iter().find(|&x| x.windowHexadecimal == hexadecimal)
+        self.list.iter().find(|&x| x.window_hexadecimal == hexadecimal)
     }
 
     pub fn get_key_info_by_press(&self, press: u16) -> Option<&WowKeyInfo> {
-        self.list.iter().find(|&x| x.pressInteger == press)
+        self.list.iter().find(|&x| x.press_integer == press)
     }
 
     pub fn get_key_info_by_release(&self, release: u16) -> Option<&WowKeyInfo> {
-        self.list.iter().find(|&x| x.releaseInteger == release)
+        self.list.iter().find(|&x| x.release_integer == release)
     }
 
 
 
-    fn new() -> Self {
+    /// Creates the registry of all the keys known by the game.
+    pub fn new() -> Self {
         EnumWowKey {
             list: vec![
-                WowKeyInfo { keyName: "Backspace".to_string(), windowDecimal: 8, windowHexadecimal: 0x08, pressInteger: 1008, releaseInteger: 2008 },
-                WowKeyInfo { keyName: "Tab".to_string(), windowDecimal: 9, windowHexadecimal: 0x09, pressInteger: 1009, releaseInteger: 2009 },
-                WowKeyInfo { keyName: "Clear".to_string(), windowDecimal: 12, windowHexadecimal: 0x0C, pressInteger: 1012, releaseInteger: 2012 },
-                WowKeyInfo { keyName: "Enter".to_string(), windowDecimal: 13, windowHexadecimal: 0x0D, pressInteger: 1013, releaseInteger: 2013 },
-                WowKeyInfo { keyName: "Shift".to_string(), windowDecimal: 16, windowHexadecimal: 0x10, pressInteger: 1016, releaseInteger: 2016 },
-                WowKeyInfo { keyName: "Ctrl".to_string(), windowDecimal: 17, windowHexadecimal: 0x11, pressInteger: 1017, releaseInteger: 2017 },
-                WowKeyInfo { keyName: "Alt".to_string(), windowDecimal: 18, windowHexadecimal: 0x12, pressInteger: 1018, releaseInteger: 2018 },
-                WowKeyInfo { keyName: "Pause".to_string(), windowDecimal: 19, windowHexadecimal: 0x13, pressInteger: 1019, releaseInteger: 2019 },
-                WowKeyInfo { keyName: "CapsLock".to_string(), windowDecimal: 20, windowHexadecimal: 0x14, pressInteger: 1020, releaseInteger: 2020 },
-                WowKeyInfo { keyName: "Esc".to_string(), windowDecimal: 27, windowHexadecimal: 0x1B, pressInteger: 1027, releaseInteger: 2027 },
-                WowKeyInfo { keyName: "Escape".to_string(), windowDecimal: 27, windowHexadecimal: 0x1B, pressInteger: 1027, releaseInteger: 2027 },
-                WowKeyInfo { keyName: "Space".to_string(), windowDecimal: 32, windowHexadecimal: 0x20, pressInteger: 1032, releaseInteger: 2032 },
-                WowKeyInfo { keyName: "PageUp".to_string(), windowDecimal: 33, windowHexadecimal: 0x21, pressInteger: 1033, releaseInteger: 2033 },
-                WowKeyInfo { keyName: "PageDown".to_string(), windowDecimal: 34, windowHexadecimal: 0x22, pressInteger: 1034, releaseInteger: 2034 },
-                WowKeyInfo { keyName: "End".to_string(), windowDecimal: 35, windowHexadecimal: 0x23, pressInteger: 1035, releaseInteger: 2035 },
-                WowKeyInfo { keyName: "Home".to_string(), windowDecimal: 36, windowHexadecimal: 0x24, pressInteger: 1036, releaseInteger: 2036 },
-                WowKeyInfo { keyName: "LeftArrow".to_string(), windowDecimal: 37, windowHexadecimal: 0x25, pressInteger: 1037, releaseInteger: 2037 },
-                WowKeyInfo { keyName: "Left".to_string(), windowDecimal: 37, windowHexadecimal: 0x25, pressInteger: 1037, releaseInteger: 2037 },
-                WowKeyInfo { keyName: "UpArrow".to_string(), windowDecimal: 38, windowHexadecimal: 0x26, pressInteger: 1038, releaseInteger: 2038 },
-                WowKeyInfo { keyName: "Up".to_string(), windowDecimal: 38, windowHexadecimal: 0x26, pressInteger: 1038, releaseInteger: 2038 },
-                WowKeyInfo { keyName: "RightArrow".to_string(), windowDecimal: 39, windowHexadecimal: 0x27, pressInteger: 1039, releaseInteger: 2039 },
-                WowKeyInfo { keyName: "Right".to_string(), windowDecimal: 39, windowHexadecimal: 0x27, pressInteger: 1039, releaseInteger: 2039 },
-                WowKeyInfo { keyName: "DownArrow".to_string(), windowDecimal: 40, windowHexadecimal: 0x28, pressInteger: 1040, releaseInteger: 2040 },
-                WowKeyInfo { keyName: "Down".to_string(), windowDecimal: 40, windowHexadecimal: 0x28, pressInteger: 1040, releaseInteger: 2040 },
-                WowKeyInfo { keyName: "Select".to_string(), windowDecimal: 41, windowHexadecimal: 0x29, pressInteger: 1041, releaseInteger: 2041 },
-                WowKeyInfo { keyName: "Print".to_string(), windowDecimal: 42, windowHexadecimal: 0x2A, pressInteger: 1042, releaseInteger: 2042 },
-                WowKeyInfo { keyName: "Execute".to_string(), windowDecimal: 43, windowHexadecimal: 0x2B, pressInteger: 1043, releaseInteger: 2043 },
-                WowKeyInfo { keyName: "PrintScreen".to_string(), windowDecimal: 44, windowHexadecimal: 0x2C, pressInteger: 1044, releaseInteger: 2044 },
-                WowKeyInfo { keyName: "Insert".to_string(), windowDecimal: 45, windowHexadecimal: 0x2D, pressInteger: 1045, releaseInteger: 2045 },
-                WowKeyInfo { keyName: "Delete".to_string(), windowDecimal: 46, windowHexadecimal: 0x2E, pressInteger: 1046, releaseInteger: 2046 },
-                WowKeyInfo { keyName: "0".to_string(), windowDecimal: 48, windowHexadecimal: 0x30, pressInteger: 1048, releaseInteger: 2048 },
-                WowKeyInfo { keyName: "1".to_string(), windowDecimal: 49, windowHexadecimal: 0x31, pressInteger: 1049, releaseInteger: 2049 },
-                WowKeyInfo { keyName: "2".to_string(), windowDecimal: 50, windowHexadecimal: 0x32, pressInteger: 1050, releaseInteger: 2050 },
-                WowKeyInfo { keyName: "3".to_string(), windowDecimal: 51, windowHexadecimal: 0x33, pressInteger: 1051, releaseInteger: 2051 },
-                WowKeyInfo { keyName: "4".to_string(), windowDecimal: 52, windowHexadecimal: 0x34, pressInteger: 1052, releaseInteger: 2052 },
-                WowKeyInfo { keyName: "5".to_string(), windowDecimal: 53, windowHexadecimal: 0x35, pressInteger: 1053, releaseInteger: 2053 },
-                WowKeyInfo { keyName: "6".to_string(), windowDecimal: 54, windowHexadecimal: 0x36, pressInteger: 1054, releaseInteger: 2054 },
-                WowKeyInfo { keyName: "7".to_string(), windowDecimal: 55, windowHexadecimal: 0x37, pressInteger: 1055, releaseInteger: 2055 },
-                WowKeyInfo { keyName: "8".to_string(), windowDecimal: 56, windowHexadecimal: 0x38, pressInteger: 1056, releaseInteger: 2056 },
-                WowKeyInfo { keyName: "9".to_string(), windowDecimal: 57, windowHexadecimal: 0x39, pressInteger: 1057, releaseInteger: 2057 },
-                WowKeyInfo { keyName: "A".to_string(), windowDecimal: 65, windowHexadecimal: 0x41, pressInteger: 1065, releaseInteger: 2065 },
-                WowKeyInfo { keyName: "B".to_string(), windowDecimal: 66, windowHexadecimal: 0x42, pressInteger: 1066, releaseInteger: 2066 },
-                WowKeyInfo { keyName: "C".to_string(), windowDecimal: 67, windowHexadecimal: 0x43, pressInteger: 1067, releaseInteger: 2067 },
-                WowKeyInfo { keyName: "D".to_string(), windowDecimal: 68, windowHexadecimal: 0x44, pressInteger: 1068, releaseInteger: 2068 },
-                WowKeyInfo { keyName: "E".to_string(), windowDecimal: 69, windowHexadecimal: 0x45, pressInteger: 1069, releaseInteger: 2069 },
-                WowKeyInfo { keyName: "F".to_string(), windowDecimal: 70, windowHexadecimal: 0x46, pressInteger: 1070, releaseInteger: 2070 },
-                WowKeyInfo { keyName: "G".to_string(), windowDecimal: 71, windowHexadecimal: 0x47, pressInteger: 1071, releaseInteger: 2071 },
-                WowKeyInfo { keyName: "H".to_string(), windowDecimal: 72, windowHexadecimal: 0x48, pressInteger: 1072, releaseInteger: 2072 },
-                WowKeyInfo { keyName: "I".to_string(), windowDecimal: 73, windowHexadecimal: 0x49, pressInteger: 1073, releaseInteger: 2073 },
-                WowKeyInfo { keyName: "J".to_string(), windowDecimal: 74, windowHexadecimal: 0x4A, pressInteger: 1074, releaseInteger: 2074 },
-                WowKeyInfo { keyName: "K".to_string(), windowDecimal: 75, windowHexadecimal: 0x4B, pressInteger: 1075, releaseInteger: 2075 },
-                WowKeyInfo { keyName: "L".to_string(), windowDecimal: 76, windowHexadecimal: 0x4C, pressInteger: 1076, releaseInteger: 2076 },
-                WowKeyInfo { keyName: "M".to_string(), windowDecimal: 77, windowHexadecimal: 0x4D, pressInteger: 1077, releaseInteger: 2077 },
-                WowKeyInfo { keyName: "N".to_string(), windowDecimal: 78, windowHexadecimal: 0x4E, pressInteger: 1078, releaseInteger: 2078 },
-                WowKeyInfo { keyName: "O".to_string(), windowDecimal: 79, windowHexadecimal: 0x4F, pressInteger: 1079, releaseInteger: 2079 },
-                WowKeyInfo { keyName: "P".to_string(), windowDecimal: 80, windowHexadecimal: 0x50, pressInteger: 1080, releaseInteger: 2080 },
-                WowKeyInfo { keyName: "Q".to_string(), windowDecimal: 81, windowHexadecimal: 0x51, pressInteger: 1081, releaseInteger: 2081 },
-                WowKeyInfo { keyName: "R".to_string(), windowDecimal: 82, windowHexadecimal: 0x52, pressInteger: 1082, releaseInteger: 2082 },
-                WowKeyInfo { keyName: "S".to_string(), windowDecimal: 83, windowHexadecimal: 0x53, pressInteger: 1083, releaseInteger: 2083 },
-                WowKeyInfo { keyName: "T".to_string(), windowDecimal: 84, windowHexadecimal: 0x54, pressInteger: 1084, releaseInteger: 2084 },
-                WowKeyInfo { keyName: "U".to_string(), windowDecimal: 85, windowHexadecimal: 0x55, pressInteger: 1085, releaseInteger: 2085 },
-                WowKeyInfo { keyName: "V".to_string(), windowDecimal: 86, windowHexadecimal: 0x56, pressInteger: 1086, releaseInteger: 2086 },
-                WowKeyInfo { keyName: "W".to_string(), windowDecimal: 87, windowHexadecimal: 0x57, pressInteger: 1087, releaseInteger: 2087 },
-                WowKeyInfo { keyName: "X".to_string(), windowDecimal: 88, windowHexadecimal: 0x58, pressInteger: 1088, releaseInteger: 2088 },
-                WowKeyInfo { keyName: "Y".to_string(), windowDecimal: 89, windowHexadecimal: 0x59, pressInteger: 1089, releaseInteger: 2089 },
-                WowKeyInfo { keyName: "Z".to_string(), windowDecimal: 90, windowHexadecimal: 0x5A, pressInteger: 1090, releaseInteger: 2090 },
-                WowKeyInfo { keyName: "LeftWindow".to_string(), windowDecimal: 91, windowHexadecimal: 0x5B, pressInteger: 1091, releaseInteger: 2091 },
-                WowKeyInfo { keyName: "RightWindow".to_string(), windowDecimal: 92, windowHexadecimal: 0x5C, pressInteger: 1092, releaseInteger: 2092 },
-                WowKeyInfo { keyName: "Applications".to_string(), windowDecimal: 93, windowHexadecimal: 0x5D, pressInteger: 1093, releaseInteger: 2093 },
-                WowKeyInfo { keyName: "Sleep".to_string(), windowDecimal: 95, windowHexadecimal: 0x5F, pressInteger: 1095, releaseInteger: 2095 },
-                WowKeyInfo { keyName: "NumLock".to_string(), windowDecimal: 144, windowHexadecimal: 0x90, pressInteger: 1144, releaseInteger: 2144 },
-                WowKeyInfo { keyName: "ScrollLock".to_string(), windowDecimal: 145, windowHexadecimal: 0x91, pressInteger: 1145, releaseInteger: 2145 },
+                WowKeyInfo { key_name: "Backspace".to_string(), window_decimal: 8, window_hexadecimal: 0x08, press_integer: 1008, release_integer: 2008 },
+                WowKeyInfo { key_name: "Tab".to_string(), window_decimal: 9, window_hexadecimal: 0x09, press_integer: 1009, release_integer: 2009 },
+                WowKeyInfo { key_name: "Clear".to_string(), window_decimal: 12, window_hexadecimal: 0x0C, press_integer: 1012, release_integer: 2012 },
+                WowKeyInfo { key_name: "Enter".to_string(), window_decimal: 13, window_hexadecimal: 0x0D, press_integer: 1013, release_integer: 2013 },
+                WowKeyInfo { key_name: "Shift".to_string(), window_decimal: 16, window_hexadecimal: 0x10, press_integer: 1016, release_integer: 2016 },
+                WowKeyInfo { key_name: "Ctrl".to_string(), window_decimal: 17, window_hexadecimal: 0x11, press_integer: 1017, release_integer: 2017 },
+                WowKeyInfo { key_name: "Alt".to_string(), window_decimal: 18, window_hexadecimal: 0x12, press_integer: 1018, release_integer: 2018 },
+                WowKeyInfo { key_name: "Pause".to_string(), window_decimal: 19, window_hexadecimal: 0x13, press_integer: 1019, release_integer: 2019 },
+                WowKeyInfo { key_name: "CapsLock".to_string(), window_decimal: 20, window_hexadecimal: 0x14, press_integer: 1020, release_integer: 2020 },
+                WowKeyInfo { key_name: "Esc".to_string(), window_decimal: 27, window_hexadecimal: 0x1B, press_integer: 1027, release_integer: 2027 },
+                WowKeyInfo { key_name: "Escape".to_string(), window_decimal: 27, window_hexadecimal: 0x1B, press_integer: 1027, release_integer: 2027 },
+                WowKeyInfo { key_name: "Space".to_string(), window_decimal: 32, window_hexadecimal: 0x20, press_integer: 1032, release_integer: 2032 },
+                WowKeyInfo { key_name: "PageUp".to_string(), window_decimal: 33, window_hexadecimal: 0x21, press_integer: 1033, release_integer: 2033 },
+                WowKeyInfo { key_name: "PageDown".to_string(), window_decimal: 34, window_hexadecimal: 0x22, press_integer: 1034, release_integer: 2034 },
+                WowKeyInfo { key_name: "End".to_string(), window_decimal: 35, window_hexadecimal: 0x23, press_integer: 1035, release_integer: 2035 },
+                WowKeyInfo { key_name: "Home".to_string(), window_decimal: 36, window_hexadecimal: 0x24, press_integer: 1036, release_integer: 2036 },
+                WowKeyInfo { key_name: "LeftArrow".to_string(), window_decimal: 37, window_hexadecimal: 0x25, press_integer: 1037, release_integer: 2037 },
+                WowKeyInfo { key_name: "Left".to_string(), window_decimal: 37, window_hexadecimal: 0x25, press_integer: 1037, release_integer: 2037 },
+                WowKeyInfo { key_name: "UpArrow".to_string(), window_decimal: 38, window_hexadecimal: 0x26, press_integer: 1038, release_integer: 2038 },
+                WowKeyInfo { key_name: "Up".to_string(), window_decimal: 38, window_hexadecimal: 0x26, press_integer: 1038, release_integer: 2038 },
+                WowKeyInfo { key_name: "RightArrow".to_string(), window_decimal: 39, window_hexadecimal: 0x27, press_integer: 1039, release_integer: 2039 },
+                WowKeyInfo { key_name: "Right".to_string(), window_decimal: 39, window_hexadecimal: 0x27, press_integer: 1039, release_integer: 2039 },
+                WowKeyInfo { key_name: "DownArrow".to_string(), window_decimal: 40, window_hexadecimal: 0x28, press_integer: 1040, release_integer: 2040 },
+                WowKeyInfo { key_name: "Down".to_string(), window_decimal: 40, window_hexadecimal: 0x28, press_integer: 1040, release_integer: 2040 },
+                WowKeyInfo { key_name: "Select".to_string(), window_decimal: 41, window_hexadecimal: 0x29, press_integer: 1041, release_integer: 2041 },
+                WowKeyInfo { key_name: "Print".to_string(), window_decimal: 42, window_hexadecimal: 0x2A, press_integer: 1042, release_integer: 2042 },
+                WowKeyInfo { key_name: "Execute".to_string(), window_decimal: 43, window_hexadecimal: 0x2B, press_integer: 1043, release_integer: 2043 },
+                WowKeyInfo { key_name: "PrintScreen".to_string(), window_decimal: 44, window_hexadecimal: 0x2C, press_integer: 1044, release_integer: 2044 },
+                WowKeyInfo { key_name: "Insert".to_string(), window_decimal: 45, window_hexadecimal: 0x2D, press_integer: 1045, release_integer: 2045 },
+                WowKeyInfo { key_name: "Delete".to_string(), window_decimal: 46, window_hexadecimal: 0x2E, press_integer: 1046, release_integer: 2046 },
+                WowKeyInfo { key_name: "0".to_string(), window_decimal: 48, window_hexadecimal: 0x30, press_integer: 1048, release_integer: 2048 },
+                WowKeyInfo { key_name: "1".to_string(), window_decimal: 49, window_hexadecimal: 0x31, press_integer: 1049, release_integer: 2049 },
+                WowKeyInfo { key_name: "2".to_string(), window_decimal: 50, window_hexadecimal: 0x32, press_integer: 1050, release_integer: 2050 },
+                WowKeyInfo { key_name: "3".to_string(), window_decimal: 51, window_hexadecimal: 0x33, press_integer: 1051, release_integer: 2051 },
+                WowKeyInfo { key_name: "4".to_string(), window_decimal: 52, window_hexadecimal: 0x34, press_integer: 1052, release_integer: 2052 },
+                WowKeyInfo { key_name: "5".to_string(), window_decimal: 53, window_hexadecimal: 0x35, press_integer: 1053, release_integer: 2053 },
+                WowKeyInfo { key_name: "6".to_string(), window_decimal: 54, window_hexadecimal: 0x36, press_integer: 1054, release_integer: 2054 },
+                WowKeyInfo { key_name: "7".to_string(), window_decimal: 55, window_hexadecimal: 0x37, press_integer: 1055, release_integer: 2055 },
+                WowKeyInfo { key_name: "8".to_string(), window_decimal: 56, window_hexadecimal: 0x38, press_integer: 1056, release_integer: 2056 },
+                WowKeyInfo { key_name: "9".to_string(), window_decimal: 57, window_hexadecimal: 0x39, press_integer: 1057, release_integer: 2057 },
+                WowKeyInfo { key_name: "A".to_string(), window_decimal: 65, window_hexadecimal: 0x41, press_integer: 1065, release_integer: 2065 },
+                WowKeyInfo { key_name: "B".to_string(), window_decimal: 66, window_hexadecimal: 0x42, press_integer: 1066, release_integer: 2066 },
+                WowKeyInfo { key_name: "C".to_string(), window_decimal: 67, window_hexadecimal: 0x43, press_integer: 1067, release_integer: 2067 },
+                WowKeyInfo { key_name: "D".to_string(), window_decimal: 68, window_hexadecimal: 0x44, press_integer: 1068, release_integer: 2068 },
+                WowKeyInfo { key_name: "E".to_string(), window_decimal: 69, window_hexadecimal: 0x45, press_integer: 1069, release_integer: 2069 },
+                WowKeyInfo { key_name: "F".to_string(), window_decimal: 70, window_hexadecimal: 0x46, press_integer: 1070, release_integer: 2070 },
+                WowKeyInfo { key_name: "G".to_string(), window_decimal: 71, window_hexadecimal: 0x47, press_integer: 1071, release_integer: 2071 },
+                WowKeyInfo { key_name: "H".to_string(), window_decimal: 72, window_hexadecimal: 0x48, press_integer: 1072, release_integer: 2072 },
+                WowKeyInfo { key_name: "I".to_string(), window_decimal: 73, window_hexadecimal: 0x49, press_integer: 1073, release_integer: 2073 },
+                WowKeyInfo { key_name: "J".to_string(), window_decimal: 74, window_hexadecimal: 0x4A, press_integer: 1074, release_integer: 2074 },
+                WowKeyInfo { key_name: "K".to_string(), window_decimal: 75, window_hexadecimal: 0x4B, press_integer: 1075, release_integer: 2075 },
+                WowKeyInfo { key_name: "L".to_string(), window_decimal: 76, window_hexadecimal: 0x4C, press_integer: 1076, release_integer: 2076 },
+                WowKeyInfo { key_name: "M".to_string(), window_decimal: 77, window_hexadecimal: 0x4D, press_integer: 1077, release_integer: 2077 },
+                WowKeyInfo { key_name: "N".to_string(), window_decimal: 78, window_hexadecimal: 0x4E, press_integer: 1078, release_integer: 2078 },
+                WowKeyInfo { key_name: "O".to_string(), window_decimal: 79, window_hexadecimal: 0x4F, press_integer: 1079, release_integer: 2079 },
+                WowKeyInfo { key_name: "P".to_string(), window_decimal: 80, window_hexadecimal: 0x50, press_integer: 1080, release_integer: 2080 },
+                WowKeyInfo { key_name: "Q".to_string(), window_decimal: 81, window_hexadecimal: 0x51, press_integer: 1081, release_integer: 2081 },
+                WowKeyInfo { key_name: "R".to_string(), window_decimal: 82, window_hexadecimal: 0x52, press_integer: 1082, release_integer: 2082 },
+                WowKeyInfo { key_name: "S".to_string(), window_decimal: 83, window_hexadecimal: 0x53, press_integer: 1083, release_integer: 2083 },
+                WowKeyInfo { key_name: "T".to_string(), window_decimal: 84, window_hexadecimal: 0x54, press_integer: 1084, release_integer: 2084 },
+                WowKeyInfo { key_name: "U".to_string(), window_decimal: 85, window_hexadecimal: 0x55, press_integer: 1085, release_integer: 2085 },
+                WowKeyInfo { key_name: "V".to_string(), window_decimal: 86, window_hexadecimal: 0x56, press_integer: 1086, release_integer: 2086 },
+                WowKeyInfo { key_name: "W".to_string(), window_decimal: 87, window_hexadecimal: 0x57, press_integer: 1087, release_integer: 2087 },
+                WowKeyInfo { key_name: "X".to_string(), window_decimal: 88, window_hexadecimal: 0x58, press_integer: 1088, release_integer: 2088 },
+                WowKeyInfo { key_name: "Y".to_string(), window_decimal: 89, window_hexadecimal: 0x59, press_integer: 1089, release_integer: 2089 },
+                WowKeyInfo { key_name: "Z".to_string(), window_decimal: 90, window_hexadecimal: 0x5A, press_integer: 1090, release_integer: 2090 },
+                WowKeyInfo { key_name: "LeftWindow".to_string(), window_decimal: 91, window_hexadecimal: 0x5B, press_integer: 1091, release_integer: 2091 },
+                WowKeyInfo { key_name: "RightWindow".to_string(), window_decimal: 92, window_hexadecimal: 0x5C, press_integer: 1092, release_integer: 2092 },
+                WowKeyInfo { key_name: "Applications".to_string(), window_decimal: 93, window_hexadecimal: 0x5D, press_integer: 1093, release_integer: 2093 },
+                WowKeyInfo { key_name: "Sleep".to_string(), window_decimal: 95, window_hexadecimal: 0x5F, press_integer: 1095, release_integer: 2095 },
+                WowKeyInfo { key_name: "NumLock".to_string(), window_decimal: 144, window_hexadecimal: 0x90, press_integer: 1144, release_integer: 2144 },
+                WowKeyInfo { key_name: "ScrollLock".to_string(), window_decimal: 145, window_hexadecimal: 0x91, press_integer: 1145, release_integer: 2145 },
             
         
         
-                WowKeyInfo { keyName: "Numpad0".to_string(), windowDecimal: 96, windowHexadecimal: 0x60, pressInteger: 1096, releaseInteger: 2096 },
-                WowKeyInfo { keyName: "Numpad1".to_string(), windowDecimal: 97, windowHexadecimal: 0x61, pressInteger: 1097, releaseInteger: 2097 },
-                WowKeyInfo { keyName: "Numpad2".to_string(), windowDecimal: 98, windowHexadecimal: 0x62, pressInteger: 1098, releaseInteger: 2098 },
-                WowKeyInfo { keyName: "Numpad3".to_string(), windowDecimal: 99, windowHexadecimal: 0x63, pressInteger: 1099, releaseInteger: 2099 },
-                WowKeyInfo { keyName: "Numpad4".to_string(), windowDecimal: 100, windowHexadecimal: 0x64, pressInteger: 1100, releaseInteger: 2100 },
-                WowKeyInfo { keyName: "Numpad5".to_string(), windowDecimal: 101, windowHexadecimal: 0x65, pressInteger: 1101, releaseInteger: 2101 },
-                WowKeyInfo { keyName: "Numpad6".to_string(), windowDecimal: 102, windowHexadecimal: 0x66, pressInteger: 1102, releaseInteger: 2102 },
-                WowKeyInfo { keyName: "Numpad7".to_string(), windowDecimal: 103, windowHexadecimal: 0x67, pressInteger: 1103, releaseInteger: 2103 },
-                WowKeyInfo { keyName: "Numpad8".to_string(), windowDecimal: 104, windowHexadecimal: 0x68, pressInteger: 1104, releaseInteger: 2104 },
-                WowKeyInfo { keyName: "Numpad9".to_string(), windowDecimal: 105, windowHexadecimal: 0x69, pressInteger: 1105, releaseInteger: 2105 },
-                WowKeyInfo { keyName: "Multiply".to_string(), windowDecimal: 106, windowHexadecimal: 0x6A, pressInteger: 1106, releaseInteger: 2106 },
-                WowKeyInfo { keyName: "Add".to_string(), windowDecimal: 107, windowHexadecimal: 0x6B, pressInteger: 1107, releaseInteger: 2107 },
-                WowKeyInfo { keyName: "Separator".to_string(), windowDecimal: 108, windowHexadecimal: 0x6C, pressInteger: 1108, releaseInteger: 2108 },
-                WowKeyInfo { keyName: "Subtract".to_string(), windowDecimal: 109, windowHexadecimal: 0x6D, pressInteger: 1109, releaseInteger: 2109 },
-                WowKeyInfo { keyName: "Decimal".to_string(), windowDecimal: 110, windowHexadecimal: 0x6E, pressInteger: 1110, releaseInteger: 2110 },
-                WowKeyInfo { keyName: "Divide".to_string(), windowDecimal: 111, windowHexadecimal: 0x6F, pressInteger: 1111, releaseInteger: 2111 },
-                WowKeyInfo { keyName: "F1".to_string(), windowDecimal: 112, windowHexadecimal: 0x70, pressInteger: 1112, releaseInteger: 2112 },
-                WowKeyInfo { keyName: "F2".to_string(), windowDecimal: 113, windowHexadecimal: 0x71, pressInteger: 1113, releaseInteger: 2113 },
-                WowKeyInfo { keyName: "F3".to_string(), windowDecimal: 114, windowHexadecimal: 0x72, pressInteger: 1114, releaseInteger: 2114 },
-                WowKeyInfo { keyName: "F4".to_string(), windowDecimal: 115, windowHexadecimal: 0x73, pressInteger: 1115, releaseInteger: 2115 },
-                WowKeyInfo { keyName: "F5".to_string(), windowDecimal: 116, windowHexadecimal: 0x74, pressInteger: 1116, releaseInteger: 2116 },
-                WowKeyInfo { keyName: "F6".to_string(), windowDecimal: 117, windowHexadecimal: 0x75, pressInteger: 1117, releaseInteger: 2117 },
-                WowKeyInfo { keyName: "F7".to_string(), windowDecimal: 118, windowHexadecimal: 0x76, pressInteger: 1118, releaseInteger: 2118 },
-                WowKeyInfo { keyName: "F8".to_string(), windowDecimal: 119, windowHexadecimal: 0x77, pressInteger: 1119, releaseInteger: 2119 },
-                WowKeyInfo { keyName: "F9".to_string(), windowDecimal: 120, windowHexadecimal: 0x78, pressInteger: 1120, releaseInteger: 2120 },
-                WowKeyInfo { keyName: "F10".to_string(), windowDecimal: 121, windowHexadecimal: 0x79, pressInteger: 1121, releaseInteger: 2121 },
-                WowKeyInfo { keyName: "F11".to_string(), windowDecimal: 122, windowHexadecimal: 0x7A, pressInteger: 1122, releaseInteger: 2122 },
-                WowKeyInfo { keyName: "F12".to_string(), windowDecimal: 123, windowHexadecimal: 0x7B, pressInteger: 1123, releaseInteger: 2123 },
-                WowKeyInfo { keyName: "F13".to_string(), windowDecimal: 124, windowHexadecimal: 0x7C, pressInteger: 1124, releaseInteger: 2124 },
-                WowKeyInfo { keyName: "F14".to_string(), windowDecimal: 125, windowHexadecimal: 0x7D, pressInteger: 1125, releaseInteger: 2125 },
-                WowKeyInfo { keyName: "F15".to_string(), windowDecimal: 126, windowHexadecimal: 0x7E, pressInteger: 1126, releaseInteger: 2126 },
-                WowKeyInfo { keyName: "F16".to_string(), windowDecimal: 127, windowHexadecimal: 0x7F, pressInteger: 1127, releaseInteger: 2127 },
-                WowKeyInfo { keyName: "F17".to_string(), windowDecimal: 128, windowHexadecimal: 0x80, pressInteger: 1128, releaseInteger: 2128 },
-                WowKeyInfo { keyName: "F18".to_string(), windowDecimal: 129, windowHexadecimal: 0x81, pressInteger: 1129, releaseInteger: 2129 },
-                WowKeyInfo { keyName: "F19".to_string(), windowDecimal: 130, windowHexadecimal: 0x82, pressInteger: 1130, releaseInteger: 2130 },
-                WowKeyInfo { keyName: "F20".to_string(), windowDecimal: 131, windowHexadecimal: 0x83, pressInteger: 1131, releaseInteger: 2131 },
-                WowKeyInfo { keyName: "F21".to_string(), windowDecimal: 132, windowHexadecimal: 0x84, pressInteger: 1132, releaseInteger: 2132 },
-                WowKeyInfo { keyName: "F22".to_string(), windowDecimal: 133, windowHexadecimal: 0x85, pressInteger: 1133, releaseInteger: 2133 },
-                WowKeyInfo { keyName: "F23".to_string(), windowDecimal: 134, windowHexadecimal: 0x86, pressInteger: 1134, releaseInteger: 2134 },
-                WowKeyInfo { keyName: "F24".to_string(), windowDecimal: 135, windowHexadecimal: 0x87, pressInteger: 1135, releaseInteger: 2135 },
-                WowKeyInfo { keyName: "NumLock".to_string(), windowDecimal: 144, windowHexadecimal: 0x90, pressInteger: 1144, releaseInteger: 2144 },
-                WowKeyInfo { keyName: "ScrollLock".to_string(), windowDecimal: 145, windowHexadecimal: 0x91, pressInteger: 1145, releaseInteger: 2145 },
-                WowKeyInfo { keyName: "LeftShift".to_string(), windowDecimal: 160, windowHexadecimal: 0xA0, pressInteger: 1160, releaseInteger: 2160 },
-                WowKeyInfo { keyName: "RightShift".to_string(), windowDecimal: 161, windowHexadecimal: 0xA1, pressInteger: 1161, releaseInteger: 2161 },
-                WowKeyInfo { keyName: "LeftControl".to_string(), windowDecimal: 162, windowHexadecimal: 0xA2, pressInteger: 1162, releaseInteger: 2162 },
-                WowKeyInfo { keyName: "RightControl".to_string(), windowDecimal: 163, windowHexadecimal: 0xA3, pressInteger: 1163, releaseInteger: 2163 },
-                WowKeyInfo { keyName: "LeftAlt".to_string(), windowDecimal: 164, windowHexadecimal: 0xA4, pressInteger: 1164, releaseInteger: 2164 },
-                WowKeyInfo { keyName: "RightAlt".to_string(), windowDecimal: 165, windowHexadecimal: 0xA5, pressInteger: 1165, releaseInteger: 2165 },
-                WowKeyInfo { keyName: "LeftMenu".to_string(), windowDecimal: 164, windowHexadecimal: 0xA4, pressInteger: 1164, releaseInteger: 2164 },
-                WowKeyInfo { keyName: "RightMenu".to_string(), windowDecimal: 165, windowHexadecimal: 0xA5, pressInteger: 1165, releaseInteger: 2165 },
-                WowKeyInfo { keyName: "BrowserBack".to_string(), windowDecimal: 166, windowHexadecimal: 0xA6, pressInteger: 1166, releaseInteger: 2166 },
-                WowKeyInfo { keyName: "BrowserForward".to_string(), windowDecimal: 167, windowHexadecimal: 0xA7, pressInteger: 1167, releaseInteger: 2167 },
-                WowKeyInfo { keyName: "BrowserRefresh".to_string(), windowDecimal: 168, windowHexadecimal: 0xA8, pressInteger: 1168, releaseInteger: 2168 },
-                WowKeyInfo { keyName: "BrowserStop".to_string(), windowDecimal: 169, windowHexadecimal: 0xA9, pressInteger: 1169, releaseInteger: 2169 },
-                WowKeyInfo { keyName: "BrowserSearch".to_string(), windowDecimal: 170, windowHexadecimal: 0xAA, pressInteger: 1170, releaseInteger: 2170 },
-                WowKeyInfo { keyName: "BrowserFavorites".to_string(), windowDecimal: 171, windowHexadecimal: 0xAB, pressInteger: 1171, releaseInteger: 2171 },
-                WowKeyInfo { keyName: "BrowserHome".to_string(), windowDecimal: 172, windowHexadecimal: 0xAC, pressInteger: 1172, releaseInteger: 2172 },
-                WowKeyInfo { keyName: "VolumeMute".to_string(), windowDecimal: 173, windowHexadecimal: 0xAD, pressInteger: 1173, releaseInteger: 2173 },
-                WowKeyInfo { keyName: "VolumeDown".to_string(), windowDecimal: 174, windowHexadecimal: 0xAE, pressInteger: 1174, releaseInteger: 2174 },
-                WowKeyInfo { keyName: "VolumeUp".to_string(), windowDecimal: 175, windowHexadecimal: 0xAF, pressInteger: 1175, releaseInteger: 2175 },
-                WowKeyInfo { keyName: "MediaNextTrack".to_string(), windowDecimal: 176, windowHexadecimal: 0xB0, pressInteger: 1176, releaseInteger: 2176 },
-                WowKeyInfo { keyName: "MediaPreviousTrack".to_string(), windowDecimal: 177, windowHexadecimal: 0xB1, pressInteger: 1177, releaseInteger: 2177 },
-                WowKeyInfo { keyName: "MediaStop".to_string(), windowDecimal: 178, windowHexadecimal: 0xB2, pressInteger: 1178, releaseInteger: 2178 },
-                WowKeyInfo { keyName: "MediaPlay".to_string(), windowDecimal: 179, windowHexadecimal: 0xB3, pressInteger: 1179, releaseInteger: 2179 },
-                WowKeyInfo { keyName: "LaunchMail".to_string(), windowDecimal: 180, windowHexadecimal: 0xB4, pressInteger: 1180, releaseInteger: 2180 },
-                WowKeyInfo { keyName: "LaunchMediaSelect".to_string(), windowDecimal: 181, windowHexadecimal: 0xB5, pressInteger: 1181, releaseInteger: 2181 },
-                WowKeyInfo { keyName: "LaunchApp1".to_string(), windowDecimal: 182, windowHexadecimal: 0xB6, pressInteger: 1182, releaseInteger: 2182 },
-                WowKeyInfo { keyName: "LaunchApp2".to_string(), windowDecimal: 183, windowHexadecimal: 0xB7, pressInteger: 1183, releaseInteger: 2183 },
-                WowKeyInfo { keyName: "OEM1".to_string(), windowDecimal: 186, windowHexadecimal: 0xBA, pressInteger: 1186, releaseInteger: 2186 },
-                WowKeyInfo { keyName: "OEMPlus".to_string(), windowDecimal: 187, windowHexadecimal: 0xBB, pressInteger: 1187, releaseInteger: 2187 },
-                WowKeyInfo { keyName: "OEMComma".to_string(), windowDecimal: 188, windowHexadecimal: 0xBC, pressInteger: 1188, releaseInteger: 2188 },
-                WowKeyInfo { keyName: "OEMMinus".to_string(), windowDecimal: 189, windowHexadecimal: 0xBD, pressInteger: 1189, releaseInteger: 2189 },
-                WowKeyInfo { keyName: "OEMPeriod".to_string(), windowDecimal: 190, windowHexadecimal: 0xBE, pressInteger: 1190, releaseInteger: 2190 },
-                WowKeyInfo { keyName: "OEM2".to_string(), windowDecimal: 191, windowHexadecimal: 0xBF, pressInteger: 1191, releaseInteger: 2191 },
-                WowKeyInfo { keyName: "OEM3".to_string(), windowDecimal: 192, windowHexadecimal: 0xC0, pressInteger: 1192, releaseInteger: 2192 },
-                WowKeyInfo { keyName: "OEM4".to_string(), windowDecimal: 219, windowHexadecimal: 0xDB, pressInteger: 1219, releaseInteger: 2219 },
-                WowKeyInfo { keyName: "OEM5".to_string(), windowDecimal: 220, windowHexadecimal: 0xDC, pressInteger: 1220, releaseInteger: 2220 },
-                WowKeyInfo { keyName: "OEM6".to_string(), windowDecimal: 221, windowHexadecimal: 0xDD, pressInteger: 1221, releaseInteger: 2221 },
-                WowKeyInfo { keyName: "OEM7".to_string(), windowDecimal: 222, windowHexadecimal: 0xDE, pressInteger: 1222, releaseInteger: 2222 },
-                WowKeyInfo { keyName: "OEM8".to_string(), windowDecimal: 223, windowHexadecimal: 0xDF, pressInteger: 1223, releaseInteger: 2223 },
-                WowKeyInfo { keyName: "OEM102".to_string(), windowDecimal: 226, windowHexadecimal: 0xE2, pressInteger: 1226, releaseInteger: 2226 },
-                WowKeyInfo { keyName: "ProcessKey".to_string(), windowDecimal: 229, windowHexadecimal: 0xE5, pressInteger: 1229, releaseInteger: 2229 },
-                WowKeyInfo { keyName: "Packet".to_string(), windowDecimal: 231, windowHexadecimal: 0xE7, pressInteger: 1231, releaseInteger: 2231 },
-                WowKeyInfo { keyName: "Attn".to_string(), windowDecimal: 246, windowHexadecimal: 0xF6, pressInteger: 1246, releaseInteger: 2246 },
-                WowKeyInfo { keyName: "CrSel".to_string(), windowDecimal: 247, windowHexadecimal: 0xF7, pressInteger: 1247, releaseInteger: 2247 },
-                WowKeyInfo { keyName: "ExSel".to_string(), windowDecimal: 248, windowHexadecimal: 0xF8, pressInteger: 1248, releaseInteger: 2248 },
-                WowKeyInfo { keyName: "EraseEOF".to_string(), windowDecimal: 249, windowHexadecimal: 0xF9, pressInteger: 1249, releaseInteger: 2249 },
-                WowKeyInfo { keyName: "Play".to_string(), windowDecimal: 250, windowHexadecimal: 0xFA, pressInteger: 1250, releaseInteger: 2250 },
-                WowKeyInfo { keyName: "Zoom".to_string(), windowDecimal: 251, windowHexadecimal: 0xFB, pressInteger: 1251, releaseInteger: 2251 },
-                WowKeyInfo { keyName: "PA1".to_string(), windowDecimal: 253, windowHexadecimal: 0xFD, pressInteger: 1253, releaseInteger: 2253 }
+                WowKeyInfo { key_name: "Numpad0".to_string(), window_decimal: 96, window_hexadecimal: 0x60, press_integer: 1096, release_integer: 2096 },
+                WowKeyInfo { key_name: "Numpad1".to_string(), window_decimal: 97, window_hexadecimal: 0x61, press_integer: 1097, release_integer: 2097 },
+                WowKeyInfo { key_name: "Numpad2".to_string(), window_decimal: 98, window_hexadecimal: 0x62, press_integer: 1098, release_integer: 2098 },
+                WowKeyInfo { key_name: "Numpad3".to_string(), window_decimal: 99, window_hexadecimal: 0x63, press_integer: 1099, release_integer: 2099 },
+                WowKeyInfo { key_name: "Numpad4".to_string(), window_decimal: 100, window_hexadecimal: 0x64, press_integer: 1100, release_integer: 2100 },
+                WowKeyInfo { key_name: "Numpad5".to_string(), window_decimal: 101, window_hexadecimal: 0x65, press_integer: 1101, release_integer: 2101 },
+                WowKeyInfo { key_name: "Numpad6".to_string(), window_decimal: 102, window_hexadecimal: 0x66, press_integer: 1102, release_integer: 2102 },
+                WowKeyInfo { key_name: "Numpad7".to_string(), window_decimal: 103, window_hexadecimal: 0x67, press_integer: 1103, release_integer: 2103 },
+                WowKeyInfo { key_name: "Numpad8".to_string(), window_decimal: 104, window_hexadecimal: 0x68, press_integer: 1104, release_integer: 2104 },
+                WowKeyInfo { key_name: "Numpad9".to_string(), window_decimal: 105, window_hexadecimal: 0x69, press_integer: 1105, release_integer: 2105 },
+                WowKeyInfo { key_name: "Multiply".to_string(), window_decimal: 106, window_hexadecimal: 0x6A, press_integer: 1106, release_integer: 2106 },
+                WowKeyInfo { key_name: "Add".to_string(), window_decimal: 107, window_hexadecimal: 0x6B, press_integer: 1107, release_integer: 2107 },
+                WowKeyInfo { key_name: "Separator".to_string(), window_decimal: 108, window_hexadecimal: 0x6C, press_integer: 1108, release_integer: 2108 },
+                WowKeyInfo { key_name: "Subtract".to_string(), window_decimal: 109, window_hexadecimal: 0x6D, press_integer: 1109, release_integer: 2109 },
+                WowKeyInfo { key_name: "Decimal".to_string(), window_decimal: 110, window_hexadecimal: 0x6E, press_integer: 1110, release_integer: 2110 },
+                WowKeyInfo { key_name: "Divide".to_string(), window_decimal: 111, window_hexadecimal: 0x6F, press_integer: 1111, release_integer: 2111 },
+                WowKeyInfo { key_name: "F1".to_string(), window_decimal: 112, window_hexadecimal: 0x70, press_integer: 1112, release_integer: 2112 },
+                WowKeyInfo { key_name: "F2".to_string(), window_decimal: 113, window_hexadecimal: 0x71, press_integer: 1113, release_integer: 2113 },
+                WowKeyInfo { key_name: "F3".to_string(), window_decimal: 114, window_hexadecimal: 0x72, press_integer: 1114, release_integer: 2114 },
+                WowKeyInfo { key_name: "F4".to_string(), window_decimal: 115, window_hexadecimal: 0x73, press_integer: 1115, release_integer: 2115 },
+                WowKeyInfo { key_name: "F5".to_string(), window_decimal: 116, window_hexadecimal: 0x74, press_integer: 1116, release_integer: 2116 },
+                WowKeyInfo { key_name: "F6".to_string(), window_decimal: 117, window_hexadecimal: 0x75, press_integer: 1117, release_integer: 2117 },
+                WowKeyInfo { key_name: "F7".to_string(), window_decimal: 118, window_hexadecimal: 0x76, press_integer: 1118, release_integer: 2118 },
+                WowKeyInfo { key_name: "F8".to_string(), window_decimal: 119, window_hexadecimal: 0x77, press_integer: 1119, release_integer: 2119 },
+                WowKeyInfo { key_name: "F9".to_string(), window_decimal: 120, window_hexadecimal: 0x78, press_integer: 1120, release_integer: 2120 },
+                WowKeyInfo { key_name: "F10".to_string(), window_decimal: 121, window_hexadecimal: 0x79, press_integer: 1121, release_integer: 2121 },
+                WowKeyInfo { key_name: "F11".to_string(), window_decimal: 122, window_hexadecimal: 0x7A, press_integer: 1122, release_integer: 2122 },
+                WowKeyInfo { key_name: "F12".to_string(), window_decimal: 123, window_hexadecimal: 0x7B, press_integer: 1123, release_integer: 2123 },
+                WowKeyInfo { key_name: "F13".to_string(), window_decimal: 124, window_hexadecimal: 0x7C, press_integer: 1124, release_integer: 2124 },
+                WowKeyInfo { key_name: "F14".to_string(), window_decimal: 125, window_hexadecimal: 0x7D, press_integer: 1125, release_integer: 2125 },
+                WowKeyInfo { key_name: "F15".to_string(), window_decimal: 126, window_hexadecimal: 0x7E, press_integer: 1126, release_integer: 2126 },
+                WowKeyInfo { key_name: "F16".to_string(), window_decimal: 127, window_hexadecimal: 0x7F, press_integer: 1127, release_integer: 2127 },
+                WowKeyInfo { key_name: "F17".to_string(), window_decimal: 128, window_hexadecimal: 0x80, press_integer: 1128, release_integer: 2128 },
+                WowKeyInfo { key_name: "F18".to_string(), window_decimal: 129, window_hexadecimal: 0x81, press_integer: 1129, release_integer: 2129 },
+                WowKeyInfo { key_name: "F19".to_string(), window_decimal: 130, window_hexadecimal: 0x82, press_integer: 1130, release_integer: 2130 },
+                WowKeyInfo { key_name: "F20".to_string(), window_decimal: 131, window_hexadecimal: 0x83, press_integer: 1131, release_integer: 2131 },
+                WowKeyInfo { key_name: "F21".to_string(), window_decimal: 132, window_hexadecimal: 0x84, press_integer: 1132, release_integer: 2132 },
+                WowKeyInfo { key_name: "F22".to_string(), window_decimal: 133, window_hexadecimal: 0x85, press_integer: 1133, release_integer: 2133 },
+                WowKeyInfo { key_name: "F23".to_string(), window_decimal: 134, window_hexadecimal: 0x86, press_integer: 1134, release_integer: 2134 },
+                WowKeyInfo { key_name: "F24".to_string(), window_decimal: 135, window_hexadecimal: 0x87, press_integer: 1135, release_integer: 2135 },
+                WowKeyInfo { key_name: "NumLock".to_string(), window_decimal: 144, window_hexadecimal: 0x90, press_integer: 1144, release_integer: 2144 },
+                WowKeyInfo { key_name: "ScrollLock".to_string(), window_decimal: 145, window_hexadecimal: 0x91, press_integer: 1145, release_integer: 2145 },
+                WowKeyInfo { key_name: "LeftShift".to_string(), window_decimal: 160, window_hexadecimal: 0xA0, press_integer: 1160, release_integer: 2160 },
+                WowKeyInfo { key_name: "RightShift".to_string(), window_decimal: 161, window_hexadecimal: 0xA1, press_integer: 1161, release_integer: 2161 },
+                WowKeyInfo { key_name: "LeftControl".to_string(), window_decimal: 162, window_hexadecimal: 0xA2, press_integer: 1162, release_integer: 2162 },
+                WowKeyInfo { key_name: "RightControl".to_string(), window_decimal: 163, window_hexadecimal: 0xA3, press_integer: 1163, release_integer: 2163 },
+                WowKeyInfo { key_name: "LeftAlt".to_string(), window_decimal: 164, window_hexadecimal: 0xA4, press_integer: 1164, release_integer: 2164 },
+                WowKeyInfo { key_name: "RightAlt".to_string(), window_decimal: 165, window_hexadecimal: 0xA5, press_integer: 1165, release_integer: 2165 },
+                WowKeyInfo { key_name: "LeftMenu".to_string(), window_decimal: 164, window_hexadecimal: 0xA4, press_integer: 1164, release_integer: 2164 },
+                WowKeyInfo { key_name: "RightMenu".to_string(), window_decimal: 165, window_hexadecimal: 0xA5, press_integer: 1165, release_integer: 2165 },
+                WowKeyInfo { key_name: "BrowserBack".to_string(), window_decimal: 166, window_hexadecimal: 0xA6, press_integer: 1166, release_integer: 2166 },
+                WowKeyInfo { key_name: "BrowserForward".to_string(), window_decimal: 167, window_hexadecimal: 0xA7, press_integer: 1167, release_integer: 2167 },
+                WowKeyInfo { key_name: "BrowserRefresh".to_string(), window_decimal: 168, window_hexadecimal: 0xA8, press_integer: 1168, release_integer: 2168 },
+                WowKeyInfo { key_name: "BrowserStop".to_string(), window_decimal: 169, window_hexadecimal: 0xA9, press_integer: 1169, release_integer: 2169 },
+                WowKeyInfo { key_name: "BrowserSearch".to_string(), window_decimal: 170, window_hexadecimal: 0xAA, press_integer: 1170, release_integer: 2170 },
+                WowKeyInfo { key_name: "BrowserFavorites".to_string(), window_decimal: 171, window_hexadecimal: 0xAB, press_integer: 1171, release_integer: 2171 },
+                WowKeyInfo { key_name: "BrowserHome".to_string(), window_decimal: 172, window_hexadecimal: 0xAC, press_integer: 1172, release_integer: 2172 },
+                WowKeyInfo { key_name: "VolumeMute".to_string(), window_decimal: 173, window_hexadecimal: 0xAD, press_integer: 1173, release_integer: 2173 },
+                WowKeyInfo { key_name: "VolumeDown".to_string(), window_decimal: 174, window_hexadecimal: 0xAE, press_integer: 1174, release_integer: 2174 },
+                WowKeyInfo { key_name: "VolumeUp".to_string(), window_decimal: 175, window_hexadecimal: 0xAF, press_integer: 1175, release_integer: 2175 },
+                WowKeyInfo { key_name: "MediaNextTrack".to_string(), window_decimal: 176, window_hexadecimal: 0xB0, press_integer: 1176, release_integer: 2176 },
+                WowKeyInfo { key_name: "MediaPreviousTrack".to_string(), window_decimal: 177, window_hexadecimal: 0xB1, press_integer: 1177, release_integer: 2177 },
+                WowKeyInfo { key_name: "MediaStop".to_string(), window_decimal: 178, window_hexadecimal: 0xB2, press_integer: 1178, release_integer: 2178 },
+                WowKeyInfo { key_name: "MediaPlay".to_string(), window_decimal: 179, window_hexadecimal: 0xB3, press_integer: 1179, release_integer: 2179 },
+                WowKeyInfo { key_name: "LaunchMail".to_string(), window_decimal: 180, window_hexadecimal: 0xB4, press_integer: 1180, release_integer: 2180 },
+                WowKeyInfo { key_name: "LaunchMediaSelect".to_string(), window_decimal: 181, window_hexadecimal: 0xB5, press_integer: 1181, release_integer: 2181 },
+                WowKeyInfo { key_name: "LaunchApp1".to_string(), window_decimal: 182, window_hexadecimal: 0xB6, press_integer: 1182, release_integer: 2182 },
+                WowKeyInfo { key_name: "LaunchApp2".to_string(), window_decimal: 183, window_hexadecimal: 0xB7, press_integer: 1183, release_integer: 2183 },
+                WowKeyInfo { key_name: "OEM1".to_string(), window_decimal: 186, window_hexadecimal: 0xBA, press_integer: 1186, release_integer: 2186 },
+                WowKeyInfo { key_name: "OEMPlus".to_string(), window_decimal: 187, window_hexadecimal: 0xBB, press_integer: 1187, release_integer: 2187 },
+                WowKeyInfo { key_name: "OEMComma".to_string(), window_decimal: 188, window_hexadecimal: 0xBC, press_integer: 1188, release_integer: 2188 },
+                WowKeyInfo { key_name: "OEMMinus".to_string(), window_decimal: 189, window_hexadecimal: 0xBD, press_integer: 1189, release_integer: 2189 },
+                WowKeyInfo { key_name: "OEMPeriod".to_string(), window_decimal: 190, window_hexadecimal: 0xBE, press_integer: 1190, release_integer: 2190 },
+                WowKeyInfo { key_name: "OEM2".to_string(), window_decimal: 191, window_hexadecimal: 0xBF, press_integer: 1191, release_integer: 2191 },
+                WowKeyInfo { key_name: "OEM3".to_string(), window_decimal: 192, window_hexadecimal: 0xC0, press_integer: 1192, release_integer: 2192 },
+                WowKeyInfo { key_name: "OEM4".to_string(), window_decimal: 219, window_hexadecimal: 0xDB, press_integer: 1219, release_integer: 2219 },
+                WowKeyInfo { key_name: "OEM5".to_string(), window_decimal: 220, window_hexadecimal: 0xDC, press_integer: 1220, release_integer: 2220 },
+                WowKeyInfo { key_name: "OEM6".to_string(), window_decimal: 221, window_hexadecimal: 0xDD, press_integer: 1221, release_integer: 2221 },
+                WowKeyInfo { key_name: "OEM7".to_string(), window_decimal: 222, window_hexadecimal: 0xDE, press_integer: 1222, release_integer: 2222 },
+                WowKeyInfo { key_name: "OEM8".to_string(), window_decimal: 223, window_hexadecimal: 0xDF, press_integer: 1223, release_integer: 2223 },
+                WowKeyInfo { key_name: "OEM102".to_string(), window_decimal: 226, window_hexadecimal: 0xE2, press_integer: 1226, release_integer: 2226 },
+                WowKeyInfo { key_name: "ProcessKey".to_string(), window_decimal: 229, window_hexadecimal: 0xE5, press_integer: 1229, release_integer: 2229 },
+                WowKeyInfo { key_name: "Packet".to_string(), window_decimal: 231, window_hexadecimal: 0xE7, press_integer: 1231, release_integer: 2231 },
+                WowKeyInfo { key_name: "Attn".to_string(), window_decimal: 246, window_hexadecimal: 0xF6, press_integer: 1246, release_integer: 2246 },
+                WowKeyInfo { key_name: "CrSel".to_string(), window_decimal: 247, window_hexadecimal: 0xF7, press_integer: 1247, release_integer: 2247 },
+                WowKeyInfo { key_name: "ExSel".to_string(), window_decimal: 248, window_hexadecimal: 0xF8, press_integer: 1248, release_integer: 2248 },
+                WowKeyInfo { key_name: "EraseEOF".to_string(), window_decimal: 249, window_hexadecimal: 0xF9, press_integer: 1249, release_integer: 2249 },
+                WowKeyInfo { key_name: "Play".to_string(), window_decimal: 250, window_hexadecimal: 0xFA, press_integer: 1250, release_integer: 2250 },
+                WowKeyInfo { key_name: "Zoom".to_string(), window_decimal: 251, window_hexadecimal: 0xFB, press_integer: 1251, release_integer: 2251 },
+                WowKeyInfo { key_name: "PA1".to_string(), window_decimal: 253, window_hexadecimal: 0xFD, press_integer: 1253, release_integer: 2253 }
             ],
         }
     }
 }
 
+impl Default for EnumWowKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 
 pub enum WowWindowKeyInt {
     Backspace = 8,
@@ -544,6 +988,7 @@ impl KeywordIntegerAction {
 
 
        
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub enum XboxIntegerActionEnum {
         RandomInput = 1399,
         ReleaseAll = 1390,
@@ -630,6 +1075,103 @@ impl KeywordIntegerAction {
         SetLeftTrigger025 = 1388,
         SetRightTrigger025 = 1389,
     }
+
+    impl XboxIntegerActionEnum {
+        /// Returns the action of a press integer (1300 to 1399).
+        pub fn from_integer(value: i32) -> Option<XboxIntegerActionEnum> {
+            match value {
+                1300 => Some(XboxIntegerActionEnum::PressA),
+                1301 => Some(XboxIntegerActionEnum::PressX),
+                1302 => Some(XboxIntegerActionEnum::PressB),
+                1303 => Some(XboxIntegerActionEnum::PressY),
+                1304 => Some(XboxIntegerActionEnum::PressLeftSideButton),
+                1305 => Some(XboxIntegerActionEnum::PressRightSideButton),
+                1306 => Some(XboxIntegerActionEnum::PressLeftStick),
+                1307 => Some(XboxIntegerActionEnum::PressRightStick),
+                1308 => Some(XboxIntegerActionEnum::PressMenuRight),
+                1309 => Some(XboxIntegerActionEnum::PressMenuLeft),
+                1310 => Some(XboxIntegerActionEnum::ReleaseDpad),
+                1311 => Some(XboxIntegerActionEnum::PressArrowNorth),
+                1312 => Some(XboxIntegerActionEnum::PressArrowNortheast),
+                1313 => Some(XboxIntegerActionEnum::PressArrowEast),
+                1314 => Some(XboxIntegerActionEnum::PressArrowSoutheast),
+                1315 => Some(XboxIntegerActionEnum::PressArrowSouth),
+                1316 => Some(XboxIntegerActionEnum::PressArrowSouthwest),
+                1317 => Some(XboxIntegerActionEnum::PressArrowWest),
+                1318 => Some(XboxIntegerActionEnum::PressArrowNorthwest),
+                1319 => Some(XboxIntegerActionEnum::PressXboxHomeButton),
+                1320 => Some(XboxIntegerActionEnum::RandomAxis),
+                1321 => Some(XboxIntegerActionEnum::StartRecording),
+                1330 => Some(XboxIntegerActionEnum::SetLeftStickNeutral),
+                1331 => Some(XboxIntegerActionEnum::SetLeftStickUp),
+                1332 => Some(XboxIntegerActionEnum::SetLeftStickUpRight),
+                1333 => Some(XboxIntegerActionEnum::SetLeftStickRight),
+                1334 => Some(XboxIntegerActionEnum::SetLeftStickDownRight),
+                1335 => Some(XboxIntegerActionEnum::SetLeftStickDown),
+                1336 => Some(XboxIntegerActionEnum::SetLeftStickDownLeft),
+                1337 => Some(XboxIntegerActionEnum::SetLeftStickLeft),
+                1338 => Some(XboxIntegerActionEnum::SetLeftStickUpLeft),
+                1340 => Some(XboxIntegerActionEnum::SetRightStickNeutral),
+                1341 => Some(XboxIntegerActionEnum::SetRightStickUp),
+                1342 => Some(XboxIntegerActionEnum::SetRightStickUpRight),
+                1343 => Some(XboxIntegerActionEnum::SetRightStickRight),
+                1344 => Some(XboxIntegerActionEnum::SetRightStickDownRight),
+                1345 => Some(XboxIntegerActionEnum::SetRightStickDown),
+                1346 => Some(XboxIntegerActionEnum::SetRightStickDownLeft),
+                1347 => Some(XboxIntegerActionEnum::SetRightStickLeft),
+                1348 => Some(XboxIntegerActionEnum::SetRightStickUpLeft),
+                1350 => Some(XboxIntegerActionEnum::SetLeftStickHorizontal100),
+                1351 => Some(XboxIntegerActionEnum::SetLeftStickHorizontalNeg100),
+                1352 => Some(XboxIntegerActionEnum::SetLeftStickVertical100),
+                1353 => Some(XboxIntegerActionEnum::SetLeftStickVerticalNeg100),
+                1354 => Some(XboxIntegerActionEnum::SetRightStickHorizontal100),
+                1355 => Some(XboxIntegerActionEnum::SetRightStickHorizontalNeg100),
+                1356 => Some(XboxIntegerActionEnum::SetRightStickVertical100),
+                1357 => Some(XboxIntegerActionEnum::SetRightStickVerticalNeg100),
+                1358 => Some(XboxIntegerActionEnum::SetLeftTrigger100),
+                1359 => Some(XboxIntegerActionEnum::SetRightTrigger100),
+                1360 => Some(XboxIntegerActionEnum::SetLeftStickHorizontal075),
+                1361 => Some(XboxIntegerActionEnum::SetLeftStickHorizontalNeg075),
+                1362 => Some(XboxIntegerActionEnum::SetLeftStickVertical075),
+                1363 => Some(XboxIntegerActionEnum::SetLeftStickVerticalNeg075),
+                1364 => Some(XboxIntegerActionEnum::SetRightStickHorizontal075),
+                1365 => Some(XboxIntegerActionEnum::SetRightStickHorizontalNeg075),
+                1366 => Some(XboxIntegerActionEnum::SetRightStickVertical075),
+                1367 => Some(XboxIntegerActionEnum::SetRightStickVerticalNeg075),
+                1368 => Some(XboxIntegerActionEnum::SetLeftTrigger075),
+                1369 => Some(XboxIntegerActionEnum::SetRightTrigger075),
+                1370 => Some(XboxIntegerActionEnum::SetLeftStickHorizontal050),
+                1371 => Some(XboxIntegerActionEnum::SetLeftStickHorizontalNeg050),
+                1372 => Some(XboxIntegerActionEnum::SetLeftStickVertical050),
+                1373 => Some(XboxIntegerActionEnum::SetLeftStickVerticalNeg050),
+                1374 => Some(XboxIntegerActionEnum::SetRightStickHorizontal050),
+                1375 => Some(XboxIntegerActionEnum::SetRightStickHorizontalNeg050),
+                1376 => Some(XboxIntegerActionEnum::SetRightStickVertical050),
+                1377 => Some(XboxIntegerActionEnum::SetRightStickVerticalNeg050),
+                1378 => Some(XboxIntegerActionEnum::SetLeftTrigger050),
+                1379 => Some(XboxIntegerActionEnum::SetRightTrigger050),
+                1380 => Some(XboxIntegerActionEnum::SetLeftStickHorizontal025),
+                1381 => Some(XboxIntegerActionEnum::SetLeftStickHorizontalNeg025),
+                1382 => Some(XboxIntegerActionEnum::SetLeftStickVertical025),
+                1383 => Some(XboxIntegerActionEnum::SetLeftStickVerticalNeg025),
+                1384 => Some(XboxIntegerActionEnum::SetRightStickHorizontal025),
+                1385 => Some(XboxIntegerActionEnum::SetRightStickHorizontalNeg025),
+                1386 => Some(XboxIntegerActionEnum::SetRightStickVertical025),
+                1387 => Some(XboxIntegerActionEnum::SetRightStickVerticalNeg025),
+                1388 => Some(XboxIntegerActionEnum::SetLeftTrigger025),
+                1389 => Some(XboxIntegerActionEnum::SetRightTrigger025),
+                1390 => Some(XboxIntegerActionEnum::ReleaseAll),
+                1391 => Some(XboxIntegerActionEnum::ReleaseAllButMenu),
+                1398 => Some(XboxIntegerActionEnum::ClearTimedCommand),
+                1399 => Some(XboxIntegerActionEnum::RandomInput),
+                _ => None,
+            }
+        }
+
+        pub fn integer(&self) -> i32 {
+            *self as i32
+        }
+    }
     /// Represents mapping to remote control an Xbox gamepad with events.
     pub struct XboxIntegerAction;
 
@@ -728,7 +1270,7 @@ impl WowWindowKeyInt {
     
 
     // Method to convert a key to its string name
-    fn key_name(&self) -> &'static str {
+    pub fn key_name(&self) -> &'static str {
         match self {
             WowWindowKeyInt::Backspace => "Backspace",
             WowWindowKeyInt::Tab => "Tab",
@@ -805,8 +1347,6 @@ impl WowWindowKeyInt {
             WowWindowKeyInt::RightControl => "RightControl",
             WowWindowKeyInt::LeftAlt => "LeftAlt",
             WowWindowKeyInt::RightAlt => "RightAlt",
-            WowWindowKeyInt::LeftAlt => "LeftMenu",
-            WowWindowKeyInt::RightAlt => "RightMenu",
             WowWindowKeyInt::BrowserBack => "BrowserBack",
             WowWindowKeyInt::BrowserForward => "BrowserForward",
             WowWindowKeyInt::BrowserRefresh => "BrowserRefresh",
@@ -850,3 +1390,63 @@ impl WowWindowKeyInt {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_brackets_of_ipv6_hosts_only() {
+        assert_eq!(strip_brackets("[::1]"), "::1");
+        assert_eq!(strip_brackets("::1"), "::1");
+        assert_eq!(strip_brackets("[::1"), "[::1");
+        assert_eq!(strip_brackets("example.com"), "example.com");
+    }
+
+    #[test]
+    fn formats_addresses() {
+        assert_eq!(format_address("192.168.1.37", 7073), "192.168.1.37:7073");
+        assert_eq!(format_address("::1", 7073), "[::1]:7073");
+        assert_eq!(format_address("[fe80::1]", 7073), "[fe80::1]:7073");
+        assert_eq!(format_address("player.local", 7073), "player.local:7073");
+    }
+
+    #[test]
+    fn splits_addresses() {
+        assert_eq!(split_address("[::1]:7073").unwrap(), ("::1", 7073));
+        assert_eq!(split_address("[2001:db8::37]:80").unwrap(), ("2001:db8::37", 80));
+        assert_eq!(split_address("192.168.1.37:7073").unwrap(), ("192.168.1.37", 7073));
+        assert_eq!(split_address("player.local:7073").unwrap(), ("player.local", 7073));
+        for invalid in ["::1", "2001:db8::37:7073", "[::1]", "[::1:7073", "player.local", "player.local:port", "host:70000"] {
+            assert!(
+                matches!(split_address(invalid), Err(WowIntError::InvalidAddress { .. })),
+                "{} was accepted",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn try_new_resolves_now() {
+        let bracketed = WowIntegerTarget::try_new("[::1]", 7073, 2).unwrap();
+        assert_eq!(bracketed.address(), "[::1]:7073");
+        assert_eq!(bracketed.socket_addr().unwrap(), "[::1]:7073".parse().unwrap());
+        let bare = WowIntegerTarget::try_new("::1", 7073, 2).unwrap();
+        assert_eq!(bare.address(), "[::1]:7073");
+
+        let host = WowIntegerTarget::try_new("localhost", 7073, 2).unwrap();
+        assert_eq!(host.address(), "localhost:7073");
+        assert!(host.socket_addr().unwrap().ip().is_loopback());
+
+        let from_address = WowIntegerTarget::try_from_address("[::1]:7074", 3).unwrap();
+        assert_eq!(from_address.socket_addr().unwrap(), "[::1]:7074".parse().unwrap());
+        assert!(matches!(
+            WowIntegerTarget::try_from_address("localhost", 3),
+            Err(WowIntError::InvalidAddress { .. })
+        ));
+        assert!(matches!(
+            WowIntegerTarget::try_new("not a host!", 7073, 2),
+            Err(WowIntError::InvalidAddress { .. })
+        ));
+    }
+}
@@ -0,0 +1,295 @@
+//! # MQTT
+//! Minimal MQTT 3.1.1 client (QoS 0 and 1) to receive integers from a broker such as Mosquitto,
+//! and optionally republish everything a sender emits.
+//!
+//! Topics are `<prefix>/<index>/integer`, with `wowint` as default prefix,
+//! and `<prefix>/all/integer` for all the players.
+//! The payload is the integer as text (`1032`), or 4 bytes little endian.
+//!
+//! Do not subscribe and republish on the same prefix, the integers would loop.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::error::{WowIntError, WowIntResult};
+use super::IntegerSender;
+
+pub const DEFAULT_TOPIC_PREFIX: &str = "wowint";
+/// Biggest packet read from the broker, integers do not need more.
+const MAX_PACKET_SIZE: usize = 64 * 1024;
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PUBACK: u8 = 0x40;
+const SUBSCRIBE: u8 = 0x82;
+const PINGREQ: u8 = 0xC0;
+const DISCONNECT: u8 = 0xE0;
+
+fn push_string(buf: &mut Vec<u8>, text: &str) {
+    buf.extend_from_slice(&(text.len() as u16).to_be_bytes());
+    buf.extend_from_slice(text.as_bytes());
+}
+
+/// Builds a packet: fixed header byte, remaining length, content.
+fn packet(header: u8, content: &[u8]) -> Vec<u8> {
+    let mut buf = vec![header];
+    let mut length = content.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        buf.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    buf.extend_from_slice(content);
+    buf
+}
+
+fn read_packet<R: Read>(reader: &mut R) -> WowIntResult<(u8, Vec<u8>)> {
+    let mut header = [0u8; 1];
+    reader.read_exact(&mut header)?;
+    let mut length: usize = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        length |= ((byte[0] & 0x7F) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 21 {
+            return Err(WowIntError::parse("MQTT remaining length", "more than 4 bytes"));
+        }
+    }
+    if length > MAX_PACKET_SIZE {
+        return Err(WowIntError::PayloadTooLarge { size: length, max: MAX_PACKET_SIZE });
+    }
+    let mut content = vec![0u8; length];
+    reader.read_exact(&mut content)?;
+    Ok((header[0], content))
+}
+
+/// Writes to the broker, can be shared with senders on other threads.
+#[derive(Clone)]
+pub struct MqttPublisher {
+    writer: Arc<Mutex<TcpStream>>,
+}
+
+impl MqttPublisher {
+    /// Publishes a message with QoS 0.
+    pub fn publish(&self, topic: &str, payload: &[u8]) -> WowIntResult<()> {
+        let mut content = Vec::with_capacity(2 + topic.len() + payload.len());
+        push_string(&mut content, topic);
+        content.extend_from_slice(payload);
+        self.write(&packet(PUBLISH, &content))
+    }
+
+    fn write(&self, buf: &[u8]) -> WowIntResult<()> {
+        Ok(self.writer.lock().unwrap().write_all(buf)?)
+    }
+}
+
+pub struct MqttClient {
+    reader: TcpStream,
+    publisher: MqttPublisher,
+    next_packet_id: u16,
+}
+
+impl MqttClient {
+    /// Connects to a broker (e.g. "127.0.0.1:1883") with a clean session.
+    /// A thread sends the keep alive pings until the client is dropped.
+    pub fn connect(broker: &str, client_id: &str, keep_alive: Duration) -> WowIntResult<MqttClient> {
+        let mut reader = TcpStream::connect(broker)?;
+        reader.set_nodelay(true)?;
+        let keep_alive_seconds = keep_alive.as_secs().clamp(1, u16::MAX as u64) as u16;
+        let mut content = Vec::new();
+        push_string(&mut content, "MQTT");
+        content.push(4); // protocol level 3.1.1
+        content.push(0x02); // clean session
+        content.extend_from_slice(&keep_alive_seconds.to_be_bytes());
+        push_string(&mut content, client_id);
+        reader.write_all(&packet(CONNECT, &content))?;
+
+        let (header, content) = read_packet(&mut reader)?;
+        if header & 0xF0 != CONNACK || content.len() != 2 {
+            return Err(WowIntError::parse("MQTT CONNACK", &format!("packet type {:#04x}", header)));
+        }
+        if content[1] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("MQTT broker refused the connection (code {})", content[1]),
+            )
+            .into());
+        }
+
+        let publisher = MqttPublisher { writer: Arc::new(Mutex::new(reader.try_clone()?)) };
+        let writer = Arc::downgrade(&publisher.writer);
+        let interval = Duration::from_secs(keep_alive_seconds as u64) / 2;
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            match writer.upgrade() {
+                Some(writer) => {
+                    if writer.lock().unwrap().write_all(&packet(PINGREQ, &[])).is_err() {
+                        return;
+                    }
+                }
+                None => return,
+            }
+        });
+        Ok(MqttClient { reader, publisher, next_packet_id: 1 })
+    }
+
+    /// Returns a handle to publish from other threads.
+    pub fn publisher(&self) -> MqttPublisher {
+        self.publisher.clone()
+    }
+
+    /// Subscribes to a topic filter (e.g. "wowint/+/integer") with QoS 0.
+    /// The acknowledgement is read with the next messages.
+    pub fn subscribe(&mut self, topic_filter: &str) -> WowIntResult<()> {
+        let packet_id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+        let mut content = Vec::new();
+        content.extend_from_slice(&packet_id.to_be_bytes());
+        push_string(&mut content, topic_filter);
+        content.push(0);
+        self.publisher.write(&packet(SUBSCRIBE, &content))
+    }
+
+    pub fn publish(&self, topic: &str, payload: &[u8]) -> WowIntResult<()> {
+        self.publisher.publish(topic, payload)
+    }
+
+    /// Waits for the next published message, returns its topic and payload.
+    /// Other packets (acknowledgements, pings) are skipped.
+    pub fn read_message(&mut self) -> WowIntResult<(String, Vec<u8>)> {
+        loop {
+            let (header, content) = read_packet(&mut self.reader)?;
+            if header & 0xF0 != PUBLISH {
+                continue;
+            }
+            let qos = (header >> 1) & 0x03;
+            if content.len() < 2 {
+                return Err(WowIntError::parse("MQTT PUBLISH", "truncated"));
+            }
+            let topic_length = u16::from_be_bytes([content[0], content[1]]) as usize;
+            let mut position = 2 + topic_length;
+            let topic = content
+                .get(2..position)
+                .and_then(|topic| String::from_utf8(topic.to_vec()).ok())
+                .ok_or_else(|| WowIntError::parse("MQTT topic", &String::from_utf8_lossy(&content[2..position.min(content.len())])))?;
+            if qos > 0 {
+                let packet_id = content.get(position..position + 2).ok_or_else(|| WowIntError::parse("MQTT PUBLISH", "truncated"))?;
+                self.publisher.write(&packet(PUBACK, packet_id))?;
+                position += 2;
+            }
+            return Ok((topic, content[position..].to_vec()));
+        }
+    }
+
+    pub fn disconnect(self) -> WowIntResult<()> {
+        self.publisher.write(&packet(DISCONNECT, &[]))
+    }
+}
+
+/// Player targeted by a topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MqttTopicTarget {
+    Index(i32),
+    All,
+}
+
+/// Reads `<prefix>/<index>/integer` or `<prefix>/all/integer`.
+pub fn parse_integer_topic(prefix: &str, topic: &str) -> Option<MqttTopicTarget> {
+    let rest = topic.strip_prefix(prefix)?.strip_prefix('/')?;
+    let player = rest.strip_suffix("/integer")?;
+    if player == "all" {
+        Some(MqttTopicTarget::All)
+    } else {
+        player.parse().ok().map(MqttTopicTarget::Index)
+    }
+}
+
+/// Reads an integer payload: text, or 4 bytes little endian.
+pub fn parse_integer_payload(payload: &[u8]) -> Option<i32> {
+    if let Some(value) = std::str::from_utf8(payload).ok().and_then(|text| text.trim().parse().ok()) {
+        return Some(value);
+    }
+    payload.try_into().ok().map(i32::from_le_bytes)
+}
+
+/// Subscribes to `<prefix>/+/integer` and forwards the payloads to a target.
+/// Invalid messages are passed to `on_ignored` and skipped, a connection error stops it.
+pub fn forward_mqtt_integers<S, F>(client: &mut MqttClient, prefix: &str, target: &S, on_ignored: F) -> WowIntResult<()>
+where
+    S: IntegerSender,
+    F: Fn(&WowIntError),
+{
+    client.subscribe(&format!("{}/+/integer", prefix))?;
+    loop {
+        let (topic, payload) = client.read_message()?;
+        let player = parse_integer_topic(prefix, &topic);
+        let value = parse_integer_payload(&payload);
+        match (player, value) {
+            (Some(MqttTopicTarget::Index(index)), Some(value)) => target.send_integer_to_target_at_index(index, value)?,
+            (Some(MqttTopicTarget::All), Some(value)) => target.send_integer_to_all(value)?,
+            _ => on_ignored(&WowIntError::parse("MQTT message", &format!("{} {:?}", topic, payload))),
+        }
+    }
+}
+
+/// Wraps a sender and republishes every integer it sends on `<prefix>/<index>/integer`.
+pub struct MqttPublishingSender<S: IntegerSender> {
+    sender: S,
+    publisher: MqttPublisher,
+    prefix: String,
+    // Index published for `send_integer_to_target`
+    index: i32,
+}
+
+impl<S: IntegerSender> MqttPublishingSender<S> {
+    /// `index` is the own index of the wrapped sender, used in the topic of `send_integer_to_target`.
+    pub fn new(sender: S, publisher: MqttPublisher, prefix: &str, index: i32) -> MqttPublishingSender<S> {
+        MqttPublishingSender {
+            sender,
+            publisher,
+            prefix: prefix.to_string(),
+            index,
+        }
+    }
+
+    pub fn sender(&self) -> &S {
+        &self.sender
+    }
+
+    fn republish(&self, player: &str, value: i32) -> WowIntResult<()> {
+        let topic = format!("{}/{}/integer", self.prefix, player);
+        self.publisher.publish(&topic, value.to_string().as_bytes())
+    }
+}
+
+impl<S: IntegerSender> IntegerSender for MqttPublishingSender<S> {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_target(value)?;
+        self.republish(&self.index.to_string(), value)
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_target_at_index(index, value)?;
+        self.republish(&index.to_string(), value)
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_all(value)?;
+        self.republish("all", value)
+    }
+}
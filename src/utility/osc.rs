@@ -0,0 +1,536 @@
+//! # OSC input
+//! Listens for Open Sound Control messages over UDP (music and VJ rigs)
+//! and turns them into integers with a mapping file.
+//!
+//! One rule per line: an address pattern and an action. In a pattern,
+//! `{index}` reads the player index from that part of the address and `*` matches any part.
+//! Without `{index}` the integer goes to the target own index.
+//!
+//! ```text
+//! # pattern                 action          OSC arguments
+//! /wow/{index}/tap          tap             key name, optional duration in ms (10 s at most)
+//! /wow/{index}/press        press           key name
+//! /wow/{index}/release      release         key name
+//! /wow/{index}/integer      integer         integer
+//! /xbox/{index}/leftstick   left_stick      x y, from -1.0 to 1.0
+//! /xbox/{index}/rightstick  right_stick     x y
+//! /xbox/{index}/lefttrigger left_trigger    value, from 0.0 to 1.0
+//! /jump                     tap Space       an action can fix its key or integer
+//! ```
+//!
+//! So `/wow/2/tap Space` taps Space for player 2
+//! and `/xbox/1/leftstick 0.5 -0.2` sets the left stick of player 1 to 50% right and 25% down.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::error::{ignore_errors, ErrorHandler, WowIntError, WowIntResult};
+use super::xbox::{XboxAxis, XboxAxisTracker};
+use super::{EnumWowKey, IntegerSender, DEFAULT_TAP_DURATION, MAX_TAP_DURATION};
+
+/// Mapping used when no file is given.
+pub const DEFAULT_OSC_MAPPING: &str = "\
+/wow/{index}/tap tap
+/wow/{index}/press press
+/wow/{index}/release release
+/wow/{index}/integer integer
+/xbox/{index}/leftstick left_stick
+/xbox/{index}/rightstick right_stick
+/xbox/{index}/lefttrigger left_trigger
+/xbox/{index}/righttrigger right_trigger
+";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArgument {
+    Int(i32),
+    Float(f32),
+    String(String),
+    Long(i64),
+    Double(f64),
+    True,
+    False,
+    Nil,
+}
+
+impl OscArgument {
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            OscArgument::Int(value) => Some(*value as f32),
+            OscArgument::Float(value) => Some(*value),
+            OscArgument::Long(value) => Some(*value as f32),
+            OscArgument::Double(value) => Some(*value as f32),
+            OscArgument::String(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            OscArgument::Int(value) => Some(*value),
+            OscArgument::Long(value) => i32::try_from(*value).ok(),
+            OscArgument::Float(_) | OscArgument::Double(_) => self.as_f32().map(|value| value.round() as i32),
+            OscArgument::String(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            OscArgument::String(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub arguments: Vec<OscArgument>,
+}
+
+fn invalid(message: &str) -> WowIntError {
+    WowIntError::parse("OSC packet", message)
+}
+
+/// Reads a null terminated string padded to 4 bytes, returns it and the bytes after it.
+fn read_osc_string(buf: &[u8]) -> WowIntResult<(String, &[u8])> {
+    let end = buf.iter().position(|&b| b == 0).ok_or_else(|| invalid("unterminated string"))?;
+    let text = String::from_utf8(buf[..end].to_vec()).map_err(|_| invalid("string is not UTF-8"))?;
+    let padded = (end + 4) & !3;
+    if padded > buf.len() {
+        return Err(invalid("truncated string"));
+    }
+    Ok((text, &buf[padded..]))
+}
+
+fn read_bytes<const N: usize>(buf: &[u8]) -> WowIntResult<([u8; N], &[u8])> {
+    if buf.len() < N {
+        return Err(invalid("truncated argument"));
+    }
+    Ok((buf[..N].try_into().unwrap(), &buf[N..]))
+}
+
+fn parse_message(buf: &[u8]) -> WowIntResult<OscMessage> {
+    let (address, rest) = read_osc_string(buf)?;
+    if rest.is_empty() {
+        return Ok(OscMessage { address, arguments: Vec::new() });
+    }
+    let (type_tags, mut rest) = read_osc_string(rest)?;
+    let type_tags = type_tags.strip_prefix(',').ok_or_else(|| invalid("missing type tags"))?;
+    let mut arguments = Vec::new();
+    for tag in type_tags.chars() {
+        let argument = match tag {
+            'i' => {
+                let (bytes, after) = read_bytes::<4>(rest)?;
+                rest = after;
+                OscArgument::Int(i32::from_be_bytes(bytes))
+            }
+            'f' => {
+                let (bytes, after) = read_bytes::<4>(rest)?;
+                rest = after;
+                OscArgument::Float(f32::from_be_bytes(bytes))
+            }
+            'h' => {
+                let (bytes, after) = read_bytes::<8>(rest)?;
+                rest = after;
+                OscArgument::Long(i64::from_be_bytes(bytes))
+            }
+            'd' => {
+                let (bytes, after) = read_bytes::<8>(rest)?;
+                rest = after;
+                OscArgument::Double(f64::from_be_bytes(bytes))
+            }
+            's' | 'S' => {
+                let (text, after) = read_osc_string(rest)?;
+                rest = after;
+                OscArgument::String(text)
+            }
+            'T' => OscArgument::True,
+            'F' => OscArgument::False,
+            'N' => OscArgument::Nil,
+            _ => return Err(invalid(&format!("unsupported type tag {}", tag))),
+        };
+        arguments.push(argument);
+    }
+    Ok(OscMessage { address, arguments })
+}
+
+/// Reads an OSC packet, the messages of a bundle are returned in order.
+pub fn parse_osc_packet(buf: &[u8]) -> WowIntResult<Vec<OscMessage>> {
+    if !buf.starts_with(b"#bundle\0") {
+        return Ok(vec![parse_message(buf)?]);
+    }
+    // Skip "#bundle" and the time tag, the messages are applied when received
+    let mut rest = buf.get(16..).ok_or_else(|| invalid("truncated bundle"))?;
+    let mut messages = Vec::new();
+    while !rest.is_empty() {
+        let (size, after) = read_bytes::<4>(rest)?;
+        let size = i32::from_be_bytes(size) as usize;
+        if size > after.len() {
+            return Err(invalid("truncated bundle element"));
+        }
+        messages.extend(parse_osc_packet(&after[..size])?);
+        rest = &after[size..];
+    }
+    Ok(messages)
+}
+
+fn write_osc_string(buf: &mut Vec<u8>, text: &str) {
+    buf.extend_from_slice(text.as_bytes());
+    let padding = 4 - text.len() % 4;
+    buf.extend(std::iter::repeat_n(0u8, padding));
+}
+
+/// Writes an OSC message, to send to this listener from Rust.
+pub fn encode_osc_message(message: &OscMessage) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_osc_string(&mut buf, &message.address);
+    let mut type_tags = String::from(",");
+    let mut data = Vec::new();
+    for argument in &message.arguments {
+        match argument {
+            OscArgument::Int(value) => {
+                type_tags.push('i');
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            OscArgument::Float(value) => {
+                type_tags.push('f');
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            OscArgument::Long(value) => {
+                type_tags.push('h');
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            OscArgument::Double(value) => {
+                type_tags.push('d');
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            OscArgument::String(text) => {
+                type_tags.push('s');
+                write_osc_string(&mut data, text);
+            }
+            OscArgument::True => type_tags.push('T'),
+            OscArgument::False => type_tags.push('F'),
+            OscArgument::Nil => type_tags.push('N'),
+        }
+    }
+    write_osc_string(&mut buf, &type_tags);
+    buf.extend(data);
+    buf
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OscSegment {
+    Literal(String),
+    Any,
+    Index,
+}
+
+/// What a matching OSC message does, the fixed argument replaces the OSC one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OscAction {
+    Integer(Option<i32>),
+    Press(Option<String>),
+    Release(Option<String>),
+    Tap(Option<String>),
+    LeftStick,
+    RightStick,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OscRule {
+    pattern: Vec<OscSegment>,
+    action: OscAction,
+}
+
+/// Integer to send, after a delay for the release of a tap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OscOutput {
+    /// `None` for the target own index.
+    pub index: Option<i32>,
+    pub value: i32,
+    pub delay: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OscMapping {
+    rules: Vec<OscRule>,
+}
+
+impl Default for OscMapping {
+    fn default() -> Self {
+        OscMapping::parse(DEFAULT_OSC_MAPPING).expect("default OSC mapping is valid")
+    }
+}
+
+impl OscMapping {
+    /// Parses rules written in the text format described in the module documentation.
+    pub fn parse(text: &str) -> WowIntResult<OscMapping> {
+        let mut rules = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rule = parse_rule(line).ok_or_else(|| WowIntError::parse_at_line("OSC mapping", line_number + 1, line))?;
+            rules.push(rule);
+        }
+        Ok(OscMapping { rules })
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> WowIntResult<OscMapping> {
+        OscMapping::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Turns a message into the integers to send, with the first matching rule.
+    /// Returns an empty list if no rule matches.
+    pub fn translate(&self, message: &OscMessage, keys: &EnumWowKey, axes: &mut XboxAxisTracker) -> WowIntResult<Vec<OscOutput>> {
+        let parts: Vec<&str> = message.address.trim_start_matches('/').split('/').collect();
+        for rule in &self.rules {
+            if let Some(index) = match_pattern(&rule.pattern, &parts) {
+                return translate_action(&rule.action, index, &message.arguments, keys, axes);
+            }
+        }
+        Ok(Vec::new())
+    }
+}
+
+fn parse_rule(line: &str) -> Option<OscRule> {
+    let mut words = line.split_whitespace();
+    let pattern = words
+        .next()?
+        .strip_prefix('/')?
+        .split('/')
+        .map(|part| match part {
+            "*" => OscSegment::Any,
+            "{index}" => OscSegment::Index,
+            _ => OscSegment::Literal(part.to_string()),
+        })
+        .collect();
+    let argument = |words: &mut std::str::SplitWhitespace| words.next().map(|word| word.to_string());
+    let action = match words.next()? {
+        "integer" => match words.next() {
+            Some(value) => OscAction::Integer(Some(value.parse().ok()?)),
+            None => OscAction::Integer(None),
+        },
+        "press" => OscAction::Press(argument(&mut words)),
+        "release" => OscAction::Release(argument(&mut words)),
+        "tap" => OscAction::Tap(argument(&mut words)),
+        "left_stick" => OscAction::LeftStick,
+        "right_stick" => OscAction::RightStick,
+        "left_trigger" => OscAction::LeftTrigger,
+        "right_trigger" => OscAction::RightTrigger,
+        _ => return None,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some(OscRule { pattern, action })
+}
+
+/// Returns `Some(index)` if the address matches, the index being `None` without `{index}`.
+fn match_pattern(pattern: &[OscSegment], parts: &[&str]) -> Option<Option<i32>> {
+    if pattern.len() != parts.len() {
+        return None;
+    }
+    let mut index = None;
+    for (segment, part) in pattern.iter().zip(parts) {
+        match segment {
+            OscSegment::Literal(literal) if literal != part => return None,
+            OscSegment::Index => index = Some(part.parse().ok()?),
+            _ => {}
+        }
+    }
+    Some(index)
+}
+
+fn translate_action(
+    action: &OscAction,
+    index: Option<i32>,
+    arguments: &[OscArgument],
+    keys: &EnumWowKey,
+    axes: &mut XboxAxisTracker,
+) -> WowIntResult<Vec<OscOutput>> {
+    let missing = |argument: &str| WowIntError::parse("OSC arguments", &format!("missing {}", argument));
+    let now = |value: i32| OscOutput { index, value, delay: Duration::ZERO };
+    let key_press = |fixed: &Option<String>| -> WowIntResult<i32> {
+        let name = match fixed {
+            Some(name) => name.as_str(),
+            None => arguments.first().and_then(|a| a.as_str()).ok_or_else(|| missing("key name"))?,
+        };
+        Ok(keys.find_key(name)?.press_integer() as i32)
+    };
+    let float = |position: usize| -> WowIntResult<f32> {
+        arguments.get(position).and_then(|a| a.as_f32()).ok_or_else(|| missing("float"))
+    };
+    let outputs = match action {
+        OscAction::Integer(Some(value)) => vec![now(*value)],
+        OscAction::Integer(None) => {
+            let value = arguments.first().and_then(|a| a.as_i32()).ok_or_else(|| missing("integer"))?;
+            vec![now(value)]
+        }
+        OscAction::Press(fixed) => vec![now(key_press(fixed)?)],
+        OscAction::Release(fixed) => vec![now(key_press(fixed)? + 1000)],
+        OscAction::Tap(fixed) => {
+            // Same durations as `tap`, but the release is left to the scheduler instead of sleeping
+            let press = key_press(fixed)?;
+            // The duration follows the key name, or is the only argument when the key is fixed
+            let duration_position = if fixed.is_some() { 0 } else { 1 };
+            let delay = arguments
+                .get(duration_position)
+                .and_then(|a| a.as_i32())
+                .map(|ms| Duration::from_millis(ms.max(0) as u64).min(MAX_TAP_DURATION))
+                .unwrap_or(DEFAULT_TAP_DURATION);
+            vec![now(press), OscOutput { index, value: press + 1000, delay }]
+        }
+        OscAction::LeftStick | OscAction::RightStick => {
+            let (horizontal, vertical) = if *action == OscAction::LeftStick {
+                (XboxAxis::LeftStickHorizontal, XboxAxis::LeftStickVertical)
+            } else {
+                (XboxAxis::RightStickHorizontal, XboxAxis::RightStickVertical)
+            };
+            let mut values = axes.update(index, horizontal, float(0)?);
+            values.extend(axes.update(index, vertical, float(1)?));
+            values.into_iter().map(now).collect()
+        }
+        OscAction::LeftTrigger => axes.update(index, XboxAxis::LeftTrigger, float(0)?).into_iter().map(now).collect(),
+        OscAction::RightTrigger => axes.update(index, XboxAxis::RightTrigger, float(0)?).into_iter().map(now).collect(),
+    };
+    Ok(outputs)
+}
+
+/// Receives OSC messages on a UDP port and sends the mapped integers to a target.
+/// The delayed integers (tap releases) are sent by one scheduler thread.
+pub struct WowIntegerOscListener<S: IntegerSender + Send + Sync + 'static> {
+    socket: UdpSocket,
+    target: Arc<S>,
+    mapping: OscMapping,
+    keys: EnumWowKey,
+    axes: XboxAxisTracker,
+    scheduled: Sender<(Instant, OscOutput)>,
+    // Shared with the scheduler thread, which starts before a handler can be set
+    errors: Arc<Mutex<ErrorHandler>>,
+}
+
+impl<S: IntegerSender + Send + Sync + 'static> WowIntegerOscListener<S> {
+    /// Creates a listener on the given address (e.g. "0.0.0.0:9000") with the default mapping.
+    pub fn bind(listen_address: &str, target: S) -> WowIntResult<WowIntegerOscListener<S>> {
+        let socket = UdpSocket::bind(listen_address)?;
+        let target = Arc::new(target);
+        let (scheduled, receiver) = mpsc::channel();
+        let errors = Arc::new(Mutex::new(ignore_errors()));
+        let scheduler_target = Arc::clone(&target);
+        let scheduler_errors = Arc::clone(&errors);
+        thread::spawn(move || run_scheduler(&*scheduler_target, receiver, &scheduler_errors));
+        Ok(WowIntegerOscListener {
+            socket,
+            target,
+            mapping: OscMapping::default(),
+            keys: EnumWowKey::new(),
+            axes: XboxAxisTracker::new(),
+            scheduled,
+            errors,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    pub fn set_mapping(&mut self, mapping: OscMapping) {
+        self.mapping = mapping;
+    }
+
+    /// Receives the errors of `run` and of the delayed sends, which do not stop the listener.
+    pub fn set_error_handler<F: Fn(&WowIntError) + Send + Sync + 'static>(&mut self, handler: F) {
+        *self.errors.lock().unwrap() = Arc::new(handler);
+    }
+
+    /// Receives one packet and sends its integers, returns how many were sent or scheduled.
+    pub fn receive_once(&mut self) -> WowIntResult<usize> {
+        let mut buf = [0u8; 1536];
+        let (size, _) = self.socket.recv_from(&mut buf)?;
+        let mut count = 0;
+        for message in parse_osc_packet(&buf[..size])? {
+            for output in self.mapping.translate(&message, &self.keys, &mut self.axes)? {
+                self.send(output)?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    fn send(&self, output: OscOutput) -> WowIntResult<()> {
+        if output.delay.is_zero() {
+            return send_output(&*self.target, output);
+        }
+        // The scheduler only stops once the listener is dropped
+        let _ = self.scheduled.send((Instant::now() + output.delay, output));
+        Ok(())
+    }
+
+    /// Receives messages forever, errors go to the error handler and do not stop the listener.
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            if let Err(e) = self.receive_once() {
+                let errors = Arc::clone(&self.errors.lock().unwrap());
+                errors(&e);
+            }
+        }
+    }
+}
+
+/// Sends the delayed integers when they are due, in order.
+/// Once the listener is dropped, the integers still scheduled are sent on time and the thread stops.
+fn run_scheduler<S: IntegerSender>(target: &S, receiver: Receiver<(Instant, OscOutput)>, errors: &Mutex<ErrorHandler>) {
+    // Keyed by due time then arrival, so integers due at the same time keep their order
+    let mut pending: BTreeMap<(Instant, u64), OscOutput> = BTreeMap::new();
+    let mut arrivals = 0u64;
+    let mut listening = true;
+    loop {
+        let next_due = pending.keys().next().map(|&(due, _)| due);
+        let received = match (next_due, listening) {
+            (Some(due), true) => receiver.recv_timeout(due.saturating_duration_since(Instant::now())),
+            (None, true) => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            (Some(due), false) => {
+                thread::sleep(due.saturating_duration_since(Instant::now()));
+                Err(RecvTimeoutError::Timeout)
+            }
+            (None, false) => return,
+        };
+        match received {
+            Ok((due, output)) => {
+                pending.insert((due, arrivals), output);
+                arrivals += 1;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => listening = false,
+        }
+        let now = Instant::now();
+        while let Some(entry) = pending.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            if let Err(e) = send_output(target, entry.remove()) {
+                let errors = Arc::clone(&errors.lock().unwrap());
+                errors(&e);
+            }
+        }
+    }
+}
+
+fn send_output<S: IntegerSender>(target: &S, output: OscOutput) -> WowIntResult<()> {
+    match output.index {
+        Some(index) => target.send_integer_to_target_at_index(index, output.value),
+        None => target.send_integer_to_target(output.value),
+    }
+}
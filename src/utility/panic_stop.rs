@@ -0,0 +1,56 @@
+//! # Panic stop
+//! Emergency release of everything a player could be holding:
+//! the release integer of every key of the registry,
+//! then `RELEASE_ALL` and `CLEAR_TIMED_COMMAND` for the Xbox gamepad.
+//! Can be installed on Ctrl+C so a program never leaves a player stuck on exit.
+
+use std::collections::BTreeSet;
+use std::io;
+use std::process;
+
+use super::error::{WowIntError, WowIntResult};
+use super::{EnumWowKey, IntegerSender, XboxIntegerAction};
+
+/// Exit code used after a Ctrl+C, as a shell does for SIGINT.
+const CTRL_C_EXIT_CODE: i32 = 130;
+
+/// Returns the release integers of every key of the registry, without duplicates.
+pub fn release_integers() -> Vec<i32> {
+    let keys = EnumWowKey::new();
+    let releases: BTreeSet<i32> = keys.keys().iter().map(|key| key.release_integer() as i32).collect();
+    releases.into_iter().collect()
+}
+
+/// Sends every key release and the Xbox release commands to each player index,
+/// with one `send_many` per player so a transport can batch them.
+/// The other players are still released if one fails, the first error is returned.
+pub fn emergency_release_all<S: IntegerSender + ?Sized>(sender: &S, indices: &[i32]) -> WowIntResult<()> {
+    let mut values = release_integers();
+    values.extend([XboxIntegerAction::RELEASE_ALL, XboxIntegerAction::CLEAR_TIMED_COMMAND]);
+    let integers: Vec<(i32, i32)> = indices.iter().flat_map(|&index| values.iter().map(move |&value| (index, value))).collect();
+    let mut result = Ok(());
+    for player_integers in integers.chunks(values.len()) {
+        let sent = sender.send_many(player_integers);
+        if result.is_ok() {
+            result = sent;
+        }
+    }
+    result
+}
+
+/// Installs a Ctrl+C handler that releases everything for the given player indices, then exits.
+/// A release error is passed to `on_error` before exiting.
+/// Only one handler can be installed per process.
+pub fn release_all_on_ctrl_c<S, F>(sender: S, indices: Vec<i32>, on_error: F) -> io::Result<()>
+where
+    S: IntegerSender + Send + 'static,
+    F: Fn(&WowIntError) + Send + 'static,
+{
+    ctrlc::set_handler(move || {
+        if let Err(e) = emergency_release_all(&sender, &indices) {
+            on_error(&e);
+        }
+        process::exit(CTRL_C_EXIT_CODE);
+    })
+    .map_err(io::Error::other)
+}
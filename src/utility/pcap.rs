@@ -0,0 +1,426 @@
+//! # pcap export and import
+//! Writes the integer datagrams to a pcap file that Wireshark opens,
+//! and reads captures back into decoded integers.
+//!
+//! Written files use the raw IP link type with synthetic IPv4 or IPv6 and UDP headers.
+//! Reading also supports Ethernet (with VLAN tags), BSD loopback and Linux cooked captures,
+//! in microsecond or nanosecond precision. Only UDP payloads of 4 or 8 bytes are decoded,
+//! so signed or encrypted traffic is skipped.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::error::{WowIntError, WowIntResult};
+use super::{decode_index_integer, encode_index_integer, EnumWowKey, IntegerSender};
+
+const MAGIC_MICROSECONDS: u32 = 0xA1B2_C3D4;
+const MAGIC_NANOSECONDS: u32 = 0xA1B2_3C4D;
+const SNAPSHOT_LENGTH: u32 = 65535;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const PROTOCOL_UDP: u8 = 17;
+
+fn invalid(message: &str) -> WowIntError {
+    WowIntError::parse("pcap", message)
+}
+
+/// Sums 16 bits words for the Internet checksum.
+fn checksum_add(mut sum: u32, bytes: &[u8]) -> u32 {
+    for pair in bytes.chunks(2) {
+        let word = match pair {
+            [high, low] => u16::from_be_bytes([*high, *low]),
+            [high] => u16::from_be_bytes([*high, 0]),
+            _ => 0,
+        };
+        sum += word as u32;
+    }
+    sum
+}
+
+fn checksum_finish(mut sum: u32) -> u16 {
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Builds an IP packet holding an UDP datagram.
+pub fn build_udp_packet(source: SocketAddr, destination: SocketAddr, payload: &[u8]) -> io::Result<Vec<u8>> {
+    let udp_length = 8 + payload.len();
+    if udp_length > u16::MAX as usize - 40 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Datagram too big"));
+    }
+    let mut udp = Vec::with_capacity(udp_length);
+    udp.extend_from_slice(&source.port().to_be_bytes());
+    udp.extend_from_slice(&destination.port().to_be_bytes());
+    udp.extend_from_slice(&(udp_length as u16).to_be_bytes());
+    udp.extend_from_slice(&[0, 0]);
+    udp.extend_from_slice(payload);
+
+    match (source.ip(), destination.ip()) {
+        (IpAddr::V4(source_ip), IpAddr::V4(destination_ip)) => {
+            let mut packet = Vec::with_capacity(20 + udp_length);
+            packet.extend_from_slice(&[0x45, 0]);
+            packet.extend_from_slice(&((20 + udp_length) as u16).to_be_bytes());
+            packet.extend_from_slice(&[0, 0, 0x40, 0, 64, PROTOCOL_UDP, 0, 0]);
+            packet.extend_from_slice(&source_ip.octets());
+            packet.extend_from_slice(&destination_ip.octets());
+            let checksum = checksum_finish(checksum_add(0, &packet));
+            packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+            // The UDP checksum is optional on IPv4, zero means none.
+            packet.extend_from_slice(&udp);
+            Ok(packet)
+        }
+        (IpAddr::V6(source_ip), IpAddr::V6(destination_ip)) => {
+            let mut pseudo_header = checksum_add(0, &source_ip.octets());
+            pseudo_header = checksum_add(pseudo_header, &destination_ip.octets());
+            pseudo_header += udp_length as u32 + PROTOCOL_UDP as u32;
+            let checksum = match checksum_finish(checksum_add(pseudo_header, &udp)) {
+                0 => 0xFFFF,
+                checksum => checksum,
+            };
+            udp[6..8].copy_from_slice(&checksum.to_be_bytes());
+            let mut packet = Vec::with_capacity(40 + udp_length);
+            packet.extend_from_slice(&[0x60, 0, 0, 0]);
+            packet.extend_from_slice(&(udp_length as u16).to_be_bytes());
+            packet.extend_from_slice(&[PROTOCOL_UDP, 64]);
+            packet.extend_from_slice(&source_ip.octets());
+            packet.extend_from_slice(&destination_ip.octets());
+            packet.extend_from_slice(&udp);
+            Ok(packet)
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Source and destination must both be IPv4 or IPv6",
+        )),
+    }
+}
+
+/// Writes datagrams in the pcap format (raw IP link type, microseconds).
+pub struct PcapWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Starts a capture, the file header is written now.
+    pub fn new(mut writer: W) -> io::Result<PcapWriter<W>> {
+        writer.write_all(&MAGIC_MICROSECONDS.to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?;
+        writer.write_all(&4u16.to_le_bytes())?;
+        writer.write_all(&0i32.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(&SNAPSHOT_LENGTH.to_le_bytes())?;
+        writer.write_all(&LINKTYPE_RAW.to_le_bytes())?;
+        Ok(PcapWriter { writer })
+    }
+
+    /// Writes an UDP datagram, `time` is the time since the Unix epoch.
+    pub fn write_datagram(&mut self, time: Duration, source: SocketAddr, destination: SocketAddr, payload: &[u8]) -> io::Result<()> {
+        let packet = build_udp_packet(source, destination, payload)?;
+        self.writer.write_all(&(time.as_secs() as u32).to_le_bytes())?;
+        self.writer.write_all(&time.subsec_micros().to_le_bytes())?;
+        self.writer.write_all(&(packet.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&(packet.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&packet)
+    }
+
+    /// Writes the 8 bytes datagram of an index and an integer.
+    pub fn write_integer(&mut self, time: Duration, source: SocketAddr, destination: SocketAddr, index: i32, value: i32) -> io::Result<()> {
+        self.write_datagram(time, source, destination, &encode_index_integer(index, value))
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl PcapWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<PcapWriter<BufWriter<File>>> {
+        PcapWriter::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Writes the integers into a capture with the current time, from any thread.
+pub struct PcapRecorder {
+    writer: Mutex<PcapWriter<Box<dyn Write + Send>>>,
+}
+
+impl PcapRecorder {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> io::Result<PcapRecorder> {
+        let writer: Box<dyn Write + Send> = Box::new(writer);
+        Ok(PcapRecorder {
+            writer: Mutex::new(PcapWriter::new(writer)?),
+        })
+    }
+
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<PcapRecorder> {
+        PcapRecorder::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Writes an integer datagram, flushed so a stopped program keeps its capture.
+    pub fn record(&self, source: SocketAddr, destination: SocketAddr, index: i32, value: i32) -> io::Result<()> {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_integer(time, source, destination, index, value)?;
+        writer.flush()
+    }
+}
+
+/// Wraps a sender and captures every integer it sends successfully, as plain datagrams.
+pub struct PcapRecordingSender<S: IntegerSender> {
+    sender: S,
+    recorder: Arc<PcapRecorder>,
+    source: SocketAddr,
+    destination: SocketAddr,
+    // Index captured for `send_integer_to_target` and `send_integer_to_all`
+    index: i32,
+}
+
+impl<S: IntegerSender> PcapRecordingSender<S> {
+    /// `source` and `destination` are the addresses written in the synthetic headers,
+    /// `index` is the own index of the wrapped sender.
+    pub fn new(sender: S, recorder: Arc<PcapRecorder>, source: SocketAddr, destination: SocketAddr, index: i32) -> PcapRecordingSender<S> {
+        PcapRecordingSender {
+            sender,
+            recorder,
+            source,
+            destination,
+            index,
+        }
+    }
+
+    pub fn sender(&self) -> &S {
+        &self.sender
+    }
+}
+
+impl<S: IntegerSender> IntegerSender for PcapRecordingSender<S> {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_target(value)?;
+        Ok(self.recorder.record(self.source, self.destination, self.index, value)?)
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_target_at_index(index, value)?;
+        Ok(self.recorder.record(self.source, self.destination, index, value)?)
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_all(value)?;
+        Ok(self.recorder.record(self.source, self.destination, self.index, value)?)
+    }
+}
+
+/// Integer decoded from a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapturedInteger {
+    /// Time since the Unix epoch.
+    pub time: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub index: i32,
+    pub value: i32,
+}
+
+impl CapturedInteger {
+    /// Name of the key pressed or released by the value, e.g. "press Space".
+    pub fn key_annotation(&self, keys: &EnumWowKey) -> Option<String> {
+        let integer = u16::try_from(self.value).ok()?;
+        if let Some(key) = keys.get_key_info_by_press(integer) {
+            return Some(format!("press {}", key.key_name()));
+        }
+        keys.get_key_info_by_release(integer)
+            .map(|key| format!("release {}", key.key_name()))
+    }
+}
+
+/// Reads the integers of a pcap capture one at a time, as an iterator.
+pub struct PcapReader<R: Read> {
+    reader: R,
+    big_endian: bool,
+    nanoseconds: bool,
+    link_type: u32,
+    port: Option<u16>,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Reads the file header.
+    pub fn new(mut reader: R) -> WowIntResult<PcapReader<R>> {
+        let mut header = [0u8; 24];
+        reader.read_exact(&mut header)?;
+        let magic = [header[0], header[1], header[2], header[3]];
+        let (big_endian, nanoseconds) = if u32::from_le_bytes(magic) == MAGIC_MICROSECONDS {
+            (false, false)
+        } else if u32::from_le_bytes(magic) == MAGIC_NANOSECONDS {
+            (false, true)
+        } else if u32::from_be_bytes(magic) == MAGIC_MICROSECONDS {
+            (true, false)
+        } else if u32::from_be_bytes(magic) == MAGIC_NANOSECONDS {
+            (true, true)
+        } else {
+            return Err(invalid("missing pcap magic, pcapng is not supported"));
+        };
+        let mut pcap = PcapReader {
+            reader,
+            big_endian,
+            nanoseconds,
+            link_type: 0,
+            port: None,
+        };
+        // The upper bits can hold the FCS length.
+        pcap.link_type = pcap.u32_at(&header, 20) & 0x0FFF_FFFF;
+        match pcap.link_type {
+            LINKTYPE_NULL | LINKTYPE_ETHERNET | LINKTYPE_RAW | LINKTYPE_LINUX_SLL | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Ok(pcap),
+            link_type => Err(invalid(&format!("unsupported link type {}", link_type))),
+        }
+    }
+
+    /// Only keeps the datagrams sent from or to this UDP port.
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    fn u32_at(&self, buf: &[u8], offset: usize) -> u32 {
+        let bytes = [buf[offset], buf[offset + 1], buf[offset + 2], buf[offset + 3]];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    /// Reads the next integer, `None` at the end of the capture.
+    /// Packets that are not integer datagrams are skipped.
+    pub fn read(&mut self) -> WowIntResult<Option<CapturedInteger>> {
+        loop {
+            let mut header = [0u8; 16];
+            match self.reader.read_exact(&mut header) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+            let seconds = self.u32_at(&header, 0) as u64;
+            let fraction = self.u32_at(&header, 4) as u64;
+            let captured_length = self.u32_at(&header, 8) as usize;
+            if captured_length > SNAPSHOT_LENGTH as usize * 4 {
+                return Err(invalid("packet too big"));
+            }
+            let mut packet = vec![0u8; captured_length];
+            self.reader.read_exact(&mut packet)?;
+            let time = Duration::from_secs(seconds)
+                + if self.nanoseconds {
+                    Duration::from_nanos(fraction)
+                } else {
+                    Duration::from_micros(fraction)
+                };
+            let datagram = self.ip_packet(&packet).and_then(parse_udp_datagram);
+            if let Some((source, destination, payload)) = datagram {
+                if self.port.is_some_and(|port| source.port() != port && destination.port() != port) {
+                    continue;
+                }
+                if let Some((index, value)) = decode_index_integer(payload) {
+                    return Ok(Some(CapturedInteger {
+                        time,
+                        source,
+                        destination,
+                        index,
+                        value,
+                    }));
+                }
+            }
+        }
+    }
+
+    /// Removes the link layer header.
+    fn ip_packet<'a>(&self, packet: &'a [u8]) -> Option<&'a [u8]> {
+        match self.link_type {
+            LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Some(packet),
+            // The address family is in the byte order of the capturing host, only the IP version matters.
+            LINKTYPE_NULL => packet.get(4..),
+            LINKTYPE_LINUX_SLL => packet.get(16..),
+            LINKTYPE_ETHERNET => {
+                let mut offset = 12;
+                let mut ether_type = u16::from_be_bytes([*packet.get(offset)?, *packet.get(offset + 1)?]);
+                while ether_type == ETHERTYPE_VLAN {
+                    offset += 4;
+                    ether_type = u16::from_be_bytes([*packet.get(offset)?, *packet.get(offset + 1)?]);
+                }
+                match ether_type {
+                    ETHERTYPE_IPV4 | ETHERTYPE_IPV6 => packet.get(offset + 2..),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl PcapReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> WowIntResult<PcapReader<BufReader<File>>> {
+        PcapReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = WowIntResult<CapturedInteger>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+/// Returns the addresses and payload of an IPv4 or IPv6 packet holding an UDP datagram.
+/// Fragments and IPv6 extension headers are not supported.
+fn parse_udp_datagram(packet: &[u8]) -> Option<(SocketAddr, SocketAddr, &[u8])> {
+    let (source_ip, destination_ip, udp): (IpAddr, IpAddr, &[u8]) = match packet.first()? >> 4 {
+        4 => {
+            let header_length = ((packet[0] & 0x0F) as usize) * 4;
+            let total_length = u16::from_be_bytes([*packet.get(2)?, *packet.get(3)?]) as usize;
+            let fragmented = u16::from_be_bytes([*packet.get(6)?, *packet.get(7)?]) & 0x3FFF != 0;
+            if *packet.get(9)? != PROTOCOL_UDP || fragmented || header_length < 20 {
+                return None;
+            }
+            let source: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+            let end = total_length.min(packet.len());
+            (Ipv4Addr::from(source).into(), Ipv4Addr::from(destination).into(), packet.get(header_length..end)?)
+        }
+        6 => {
+            if *packet.get(6)? != PROTOCOL_UDP {
+                return None;
+            }
+            let source: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+            (Ipv6Addr::from(source).into(), Ipv6Addr::from(destination).into(), packet.get(40..)?)
+        }
+        _ => return None,
+    };
+    let source_port = u16::from_be_bytes([*udp.first()?, *udp.get(1)?]);
+    let destination_port = u16::from_be_bytes([*udp.get(2)?, *udp.get(3)?]);
+    let udp_length = u16::from_be_bytes([*udp.get(4)?, *udp.get(5)?]) as usize;
+    let payload = udp.get(8..udp_length.max(8).min(udp.len()))?;
+    Some((
+        SocketAddr::new(source_ip, source_port),
+        SocketAddr::new(destination_ip, destination_port),
+        payload,
+    ))
+}
+
+/// Reads every integer of a capture file.
+pub fn load_pcap_integers<P: AsRef<Path>>(path: P) -> WowIntResult<Vec<CapturedInteger>> {
+    PcapReader::open(path)?.collect()
+}
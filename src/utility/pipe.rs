@@ -0,0 +1,109 @@
+//! # Pipe commands
+//! Reads newline-delimited commands (stdin, `nc`, other languages) and forwards them.
+//!
+//! ```text
+//! 42                 send 42 to the target index
+//! 2 1032             send 1032 to player 2
+//! tap Space 200ms    press then release Space (100 ms by default, `1.5s` also works)
+//! 2 tap Space        tap on player 2
+//! # comment
+//! ```
+//!
+//! Commands run in order, so a tap delays the next lines.
+//! Malformed lines are reported and skipped.
+
+use std::io::BufRead;
+use std::time::Duration;
+
+use super::error::{WowIntError, WowIntResult};
+use super::{tap, EnumWowKey, IntegerSender, DEFAULT_TAP_DURATION, MAX_TAP_DURATION};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeCommand {
+    /// Integer for the index of the target.
+    Integer(i32),
+    IntegerAt { index: i32, value: i32 },
+    /// Press integer held for the duration, on the target index if `index` is `None`.
+    Tap { index: Option<i32>, press: i32, duration: Duration },
+}
+
+impl PipeCommand {
+    pub fn execute<S: IntegerSender>(&self, target: &S) -> WowIntResult<()> {
+        match *self {
+            PipeCommand::Integer(value) => target.send_integer_to_target(value),
+            PipeCommand::IntegerAt { index, value } => target.send_integer_to_target_at_index(index, value),
+            PipeCommand::Tap { index, press, duration } => tap(target, index, press, duration),
+        }
+    }
+}
+
+/// Reads "200ms", "1.5s" or "200" (milliseconds), capped to 10 seconds.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let duration = if let Some(milliseconds) = text.strip_suffix("ms") {
+        Duration::from_millis(milliseconds.parse().ok()?)
+    } else if let Some(seconds) = text.strip_suffix('s') {
+        Duration::try_from_secs_f64(seconds.parse().ok()?).ok()?
+    } else {
+        Duration::from_millis(text.parse().ok()?)
+    };
+    Some(duration.min(MAX_TAP_DURATION))
+}
+
+/// Parses a line, `None` for empty lines and comments.
+pub fn parse_pipe_command(line: &str, keys: &EnumWowKey) -> WowIntResult<Option<PipeCommand>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let parse_integer = |text: &str| text.parse::<i32>().map_err(|_| WowIntError::parse("integer", text));
+    let (index, rest) = match parts.as_slice() {
+        [value] => return Ok(Some(PipeCommand::Integer(parse_integer(value)?))),
+        [index, value] if *value != "tap" && *index != "tap" => {
+            return Ok(Some(PipeCommand::IntegerAt {
+                index: parse_integer(index)?,
+                value: parse_integer(value)?,
+            }))
+        }
+        ["tap", ..] => (None, &parts[1..]),
+        [index, "tap", ..] => (Some(parse_integer(index)?), &parts[2..]),
+        _ => return Err(WowIntError::parse("command", line)),
+    };
+    let (key, duration) = match rest {
+        [key] => (*key, DEFAULT_TAP_DURATION),
+        [key, duration] => (
+            *key,
+            parse_duration(duration).ok_or_else(|| WowIntError::parse("duration", duration))?,
+        ),
+        _ => return Err(WowIntError::parse("tap command", line)),
+    };
+    let key = keys.find_key(key)?;
+    Ok(Some(PipeCommand::Tap {
+        index,
+        press: key.press_integer() as i32,
+        duration,
+    }))
+}
+
+/// Runs every line of the reader on the target until the end of the stream.
+/// Malformed lines and send errors are passed to `on_error` with their line number (from 1),
+/// reading errors stop it.
+pub fn run_pipe<R, S, F>(reader: R, target: &S, mut on_error: F) -> WowIntResult<()>
+where
+    R: BufRead,
+    S: IntegerSender,
+    F: FnMut(usize, &WowIntError),
+{
+    let keys = EnumWowKey::new();
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let result = parse_pipe_command(&line, &keys).and_then(|command| match command {
+            Some(command) => command.execute(target),
+            None => Ok(()),
+        });
+        if let Err(e) = result {
+            on_error(line_number + 1, &e);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,299 @@
+//! # Policy
+//! Filters incoming integers before they reach a game:
+//! only integers from allowed ranges are accepted, specific integers can be rejected,
+//! and token bucket rate limits are enforced per source and per player index.
+//! Releases are never rate limited so a limit can not leave a key pressed.
+//! Every dropped integer is counted and the most recent ones are kept for reporting.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+pub use super::error::DropReason;
+use super::error::{WowIntError, WowIntResult};
+use super::watchdog::is_release_integer;
+use super::{EnumWowKey, IntegerSender, XboxIntegerAction};
+
+/// Press and release integers of the keyboard.
+pub const KEYBOARD_BAND: (i32, i32) = (1000, 2255);
+/// Press integers of the Xbox gamepad actions.
+pub const XBOX_BAND: (i32, i32) = (1300, 1399);
+
+/// Number of dropped integers kept in the report.
+const RECENT_DROPS_KEPT: usize = 100;
+/// Number of sources or indexes with a rate limit bucket before the idle ones are evicted.
+const MAX_TRACKED_BUCKETS: usize = 4096;
+
+/// Allows bursts of `capacity` events, refilled at `refill_per_second`.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, refill_per_second: f64) -> TokenBucket {
+        TokenBucket {
+            capacity: capacity as f64,
+            refill_per_second,
+            tokens: capacity as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Takes a token if one is available.
+    pub fn try_take(&mut self) -> bool {
+        self.try_take_at(Instant::now())
+    }
+
+    /// Takes a token if one is available at the given time.
+    pub fn try_take_at(&mut self, now: Instant) -> bool {
+        self.refill_at(now);
+        if self.has_token() {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn refill_at(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    fn has_token(&self) -> bool {
+        self.tokens >= 1.0
+    }
+
+    // A full bucket behaves like a new one, it can be evicted without losing anything
+    fn is_full(&self) -> bool {
+        self.tokens >= self.capacity
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedInteger {
+    pub source: Option<IpAddr>,
+    pub index: i32,
+    pub value: i32,
+    pub reason: DropReason,
+}
+
+/// What the policy accepted and dropped since it was created or reset.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyReport {
+    accepted: u64,
+    dropped: HashMap<DropReason, u64>,
+    recent_drops: VecDeque<DroppedInteger>,
+}
+
+impl PolicyReport {
+    pub fn accepted(&self) -> u64 {
+        self.accepted
+    }
+
+    pub fn dropped(&self, reason: DropReason) -> u64 {
+        self.dropped.get(&reason).copied().unwrap_or(0)
+    }
+
+    pub fn dropped_total(&self) -> u64 {
+        self.dropped.values().sum()
+    }
+
+    /// Most recent dropped integers, oldest first.
+    pub fn recent_drops(&self) -> impl Iterator<Item = &DroppedInteger> {
+        self.recent_drops.iter()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RateLimit {
+    capacity: u32,
+    refill_per_second: f64,
+}
+
+/// Refilled bucket of a key, evicts the full buckets first when too many keys are tracked.
+fn refilled_bucket<K: Hash + Eq>(
+    buckets: &mut HashMap<K, TokenBucket>,
+    key: K,
+    limit: RateLimit,
+    now: Instant,
+) -> &mut TokenBucket {
+    if buckets.len() >= MAX_TRACKED_BUCKETS && !buckets.contains_key(&key) {
+        buckets.retain(|_, bucket| {
+            bucket.refill_at(now);
+            !bucket.is_full()
+        });
+        if buckets.len() >= MAX_TRACKED_BUCKETS {
+            buckets.clear();
+        }
+    }
+    let bucket = buckets
+        .entry(key)
+        .or_insert_with(|| TokenBucket::new(limit.capacity, limit.refill_per_second));
+    bucket.refill_at(now);
+    bucket
+}
+
+/// Decides which incoming integers are forwarded.
+/// Without allowed ranges every integer is allowed.
+#[derive(Debug, Clone, Default)]
+pub struct IntegerPolicy {
+    allowed_ranges: Vec<(i32, i32)>,
+    rejected: HashSet<i32>,
+    source_limit: Option<RateLimit>,
+    index_limit: Option<RateLimit>,
+    source_buckets: HashMap<Option<IpAddr>, TokenBucket>,
+    index_buckets: HashMap<i32, TokenBucket>,
+    report: PolicyReport,
+}
+
+impl IntegerPolicy {
+    pub fn new() -> IntegerPolicy {
+        IntegerPolicy::default()
+    }
+
+    /// Allows the integers between min and max included.
+    pub fn allow_range(&mut self, min: i32, max: i32) {
+        self.allowed_ranges.push((min, max));
+    }
+
+    /// Rejects an integer even if it is in an allowed range.
+    pub fn reject(&mut self, value: i32) {
+        self.rejected.insert(value);
+    }
+
+    /// Rejects the press integer of a key by its name (e.g. "F4").
+    /// The release stays accepted so a key can not be left pressed.
+    pub fn reject_key(&mut self, key_name: &str) -> WowIntResult<()> {
+        let press = EnumWowKey::new().find_key(key_name)?.press_integer();
+        self.reject(press as i32);
+        Ok(())
+    }
+
+    /// Limits each source to bursts of `capacity` integers, refilled at `per_second`.
+    pub fn rate_limit_per_source(&mut self, capacity: u32, per_second: f64) {
+        self.source_limit = Some(RateLimit { capacity, refill_per_second: per_second });
+        self.source_buckets.clear();
+    }
+
+    /// Limits each player index to bursts of `capacity` integers, refilled at `per_second`.
+    pub fn rate_limit_per_index(&mut self, capacity: u32, per_second: f64) {
+        self.index_limit = Some(RateLimit { capacity, refill_per_second: per_second });
+        self.index_buckets.clear();
+    }
+
+    /// Checks an integer, records the result in the report.
+    pub fn check(&mut self, source: Option<IpAddr>, index: i32, value: i32) -> Result<(), DropReason> {
+        let result = self.evaluate(source, index, value);
+        match result {
+            Ok(()) => self.report.accepted += 1,
+            Err(reason) => {
+                *self.report.dropped.entry(reason).or_insert(0) += 1;
+                if self.report.recent_drops.len() == RECENT_DROPS_KEPT {
+                    self.report.recent_drops.pop_front();
+                }
+                self.report.recent_drops.push_back(DroppedInteger { source, index, value, reason });
+            }
+        }
+        result
+    }
+
+    fn evaluate(&mut self, source: Option<IpAddr>, index: i32, value: i32) -> Result<(), DropReason> {
+        if !self.allowed_ranges.is_empty()
+            && !self.allowed_ranges.iter().any(|&(min, max)| value >= min && value <= max)
+        {
+            return Err(DropReason::NotAllowed);
+        }
+        if self.rejected.contains(&value) {
+            return Err(DropReason::Rejected);
+        }
+        if is_release_integer(value)
+            || value == XboxIntegerAction::RELEASE_ALL
+            || value == XboxIntegerAction::RELEASE_ALL_BUT_MENU
+        {
+            return Ok(());
+        }
+        // Both buckets are checked before a token is taken from either
+        let now = Instant::now();
+        let source_bucket = self
+            .source_limit
+            .map(|limit| refilled_bucket(&mut self.source_buckets, source, limit, now));
+        let index_bucket = self
+            .index_limit
+            .map(|limit| refilled_bucket(&mut self.index_buckets, index, limit, now));
+        if source_bucket.as_ref().is_some_and(|bucket| !bucket.has_token()) {
+            return Err(DropReason::SourceRateLimited);
+        }
+        if index_bucket.as_ref().is_some_and(|bucket| !bucket.has_token()) {
+            return Err(DropReason::IndexRateLimited);
+        }
+        for bucket in source_bucket.into_iter().chain(index_bucket) {
+            bucket.tokens -= 1.0;
+        }
+        Ok(())
+    }
+
+    pub fn report(&self) -> &PolicyReport {
+        &self.report
+    }
+
+    pub fn reset_report(&mut self) {
+        self.report = PolicyReport::default();
+    }
+}
+
+/// Wraps a sender and checks every integer against a policy before sending it.
+/// Dropped integers return `WowIntError::Rejected` with the reason.
+pub struct PolicySender<S: IntegerSender> {
+    sender: S,
+    policy: Mutex<IntegerPolicy>,
+    // Index checked for `send_integer_to_target` and `send_integer_to_all`
+    index: i32,
+}
+
+impl<S: IntegerSender> PolicySender<S> {
+    pub fn new(sender: S, policy: IntegerPolicy, index: i32) -> PolicySender<S> {
+        PolicySender { sender, policy: Mutex::new(policy), index }
+    }
+
+    pub fn sender(&self) -> &S {
+        &self.sender
+    }
+
+    /// Copy of what the policy accepted and dropped so far.
+    pub fn report(&self) -> PolicyReport {
+        self.policy.lock().unwrap().report().clone()
+    }
+
+    fn check(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.policy
+            .lock()
+            .unwrap()
+            .check(None, index, value)
+            .map_err(|reason| WowIntError::Rejected { index, value, reason })
+    }
+}
+
+impl<S: IntegerSender> IntegerSender for PolicySender<S> {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.check(self.index, value)?;
+        self.sender.send_integer_to_target(value)
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.check(index, value)?;
+        self.sender.send_integer_to_target_at_index(index, value)
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        self.check(self.index, value)?;
+        self.sender.send_integer_to_all(value)
+    }
+}
@@ -0,0 +1,184 @@
+//! # Record and replay
+//! Captures the integers going through a sender into a session file,
+//! then replays them with their original timing against any target.
+//!
+//! ```text
+//! let recorder = Arc::new(SessionRecorder::create("bot.jsonl")?);
+//! let target = RecordingSender::new(WowIntegerTarget::new("127.0.0.1", 7073, 2), recorder, 2);
+//! // ... later
+//! replay_stream(SessionReader::open("bot.jsonl")?, &other_target, &ReplayOptions::new().with_speed(2.0))?;
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::error::WowIntResult;
+use super::session::{RecordedInteger, SessionFormat, SessionWriter};
+use super::IntegerSender;
+
+/// Writes timestamped integers into a session, from any thread.
+/// Each integer is flushed so a stopped program keeps its recording.
+pub struct SessionRecorder {
+    start: Instant,
+    writer: Mutex<SessionWriter<Box<dyn Write + Send>>>,
+}
+
+impl SessionRecorder {
+    /// Records into any writer, the time starts now.
+    pub fn new<W: Write + Send + 'static>(writer: W, format: SessionFormat) -> io::Result<SessionRecorder> {
+        let writer: Box<dyn Write + Send> = Box::new(writer);
+        Ok(SessionRecorder {
+            start: Instant::now(),
+            writer: Mutex::new(SessionWriter::new(writer, format)?),
+        })
+    }
+
+    /// Creates (or truncates) the session file, the format comes from the extension.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<SessionRecorder> {
+        let format = SessionFormat::from_path(&path);
+        SessionRecorder::new(BufWriter::new(File::create(path)?), format)
+    }
+
+    /// Writes an integer with the time elapsed since the start.
+    pub fn record(&self, index: i32, value: i32) -> io::Result<()> {
+        let integer = RecordedInteger::new(self.start.elapsed(), index, value);
+        let mut writer = self.writer.lock().unwrap();
+        writer.write(&integer)?;
+        writer.flush()
+    }
+}
+
+/// Wraps a sender and records every integer it sends successfully.
+pub struct RecordingSender<S: IntegerSender> {
+    sender: S,
+    recorder: Arc<SessionRecorder>,
+    // Index recorded for `send_integer_to_target` and `send_integer_to_all`
+    index: i32,
+}
+
+impl<S: IntegerSender> RecordingSender<S> {
+    /// `index` is the own index of the wrapped sender.
+    pub fn new(sender: S, recorder: Arc<SessionRecorder>, index: i32) -> RecordingSender<S> {
+        RecordingSender { sender, recorder, index }
+    }
+
+    pub fn sender(&self) -> &S {
+        &self.sender
+    }
+}
+
+impl<S: IntegerSender> IntegerSender for RecordingSender<S> {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_target(value)?;
+        Ok(self.recorder.record(self.index, value)?)
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_target_at_index(index, value)?;
+        Ok(self.recorder.record(index, value)?)
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_all(value)?;
+        Ok(self.recorder.record(self.index, value)?)
+    }
+}
+
+/// How a session is replayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayOptions {
+    speed: f64,
+    max_pause: Option<Duration>,
+    index: Option<i32>,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        ReplayOptions::new()
+    }
+}
+
+impl ReplayOptions {
+    /// Original timing, original indices.
+    pub fn new() -> ReplayOptions {
+        ReplayOptions {
+            speed: 1.0,
+            max_pause: None,
+            index: None,
+        }
+    }
+
+    /// Plays faster (2.0) or slower (0.5) than recorded.
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Compresses the time: pauses longer than `max_pause` (before speed) are shortened to it.
+    pub fn with_max_pause(mut self, max_pause: Duration) -> Self {
+        self.max_pause = Some(max_pause);
+        self
+    }
+
+    /// Sends every integer to this index instead of the recorded one.
+    pub fn with_index(mut self, index: i32) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Times at which each integer is sent, from the start of the replay.
+    pub fn schedule(&self, integers: &[RecordedInteger]) -> Vec<Duration> {
+        let mut timeline = ReplayTimeline::default();
+        integers.iter().map(|integer| timeline.next(integer.time, self)).collect()
+    }
+}
+
+/// Replay time of the integers, computed one at a time.
+#[derive(Default)]
+struct ReplayTimeline {
+    elapsed: Duration,
+    previous: Option<Duration>,
+}
+
+impl ReplayTimeline {
+    fn next(&mut self, time: Duration, options: &ReplayOptions) -> Duration {
+        let mut pause = time.saturating_sub(self.previous.unwrap_or(time));
+        if let Some(max_pause) = options.max_pause {
+            pause = pause.min(max_pause);
+        }
+        self.elapsed += pause;
+        self.previous = Some(time);
+        self.elapsed.div_f64(options.speed)
+    }
+}
+
+/// Sends the integers to the target with the recorded timing, blocks until the end.
+/// The replay starts with the first integer, without waiting for its recorded time.
+pub fn replay_session<S: IntegerSender>(integers: &[RecordedInteger], target: &S, options: &ReplayOptions) -> WowIntResult<()> {
+    replay_stream(integers.iter().copied().map(Ok), target, options)
+}
+
+/// Same as `replay_session`, reading the integers while playing (e.g. from a `SessionReader`).
+pub fn replay_stream<I, S>(integers: I, target: &S, options: &ReplayOptions) -> WowIntResult<()>
+where
+    I: IntoIterator<Item = WowIntResult<RecordedInteger>>,
+    S: IntegerSender,
+{
+    if !(options.speed > 0.0 && options.speed.is_finite()) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Replay speed must be positive").into());
+    }
+    let start = Instant::now();
+    let mut timeline = ReplayTimeline::default();
+    for integer in integers {
+        let integer = integer?;
+        if let Some(wait) = timeline.next(integer.time, options).checked_sub(start.elapsed()) {
+            thread::sleep(wait);
+        }
+        target.send_integer_to_target_at_index(options.index.unwrap_or(integer.index), integer.value)?;
+    }
+    Ok(())
+}
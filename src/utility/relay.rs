@@ -0,0 +1,448 @@
+//! # Relay
+//! Listens for index + integer packets, rewrites them with a rule table
+//! and forwards them to one or more targets.
+//!
+//! Rules can be loaded from a text file and are reloaded when the file changes.
+//! One rule per line: an index pattern, an integer pattern and an action.
+//! Patterns are `*`, a number or an inclusive range `min..max`.
+//! Lines starting with `#` are comments.
+//!
+//! ```text
+//! # index  value       action
+//! *        1390        drop
+//! 2        *           index 3
+//! *        1032        value 1033
+//! *        1000..2255  to 0 1
+//! ```
+//!
+//! Rules are applied in order and every matching rule is applied,
+//! so a rule sees the packet as rewritten by the rules above it.
+//! - `drop`: the packet is not forwarded.
+//! - `index N`: the packet is forwarded to player index N.
+//! - `value N`: the packet integer is replaced by N.
+//! - `to A B ...`: the packet is only forwarded to the targets at these positions.
+//!
+//! Without a `to` action, the packet is forwarded to every target.
+
+use std::fs;
+use std::io;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::batch::MAX_BATCH_DATAGRAM_SIZE;
+use super::clock::answer_ping;
+use super::metrics::IntegerMetrics;
+use super::error::{ignore_errors, ErrorHandler, WowIntError, WowIntResult};
+use super::policy::IntegerPolicy;
+use super::reliable::{ReliableReceiver, RELIABLE_HEADER_SIZE, RELIABLE_MAGIC};
+use super::signed::SignedPacketValidator;
+use super::{IntegerSender, PacketDecoder};
+
+/// Time between two checks of the rules file modification date.
+const RULES_RELOAD_CHECK: Duration = Duration::from_millis(500);
+
+/// Matches an index or an integer of a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayPattern {
+    Any,
+    Exact(i32),
+    /// Inclusive range.
+    Range(i32, i32),
+}
+
+impl RelayPattern {
+    pub fn matches(&self, value: i32) -> bool {
+        match *self {
+            RelayPattern::Any => true,
+            RelayPattern::Exact(expected) => value == expected,
+            RelayPattern::Range(min, max) => value >= min && value <= max,
+        }
+    }
+
+    fn parse(text: &str) -> Option<RelayPattern> {
+        if text == "*" {
+            return Some(RelayPattern::Any);
+        }
+        if let Some((min, max)) = text.split_once("..") {
+            let min = min.parse().ok()?;
+            let max = max.parse().ok()?;
+            return Some(RelayPattern::Range(min, max));
+        }
+        text.parse().ok().map(RelayPattern::Exact)
+    }
+}
+
+/// What to do with a packet matching a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayAction {
+    Drop,
+    RemapIndex(i32),
+    RemapValue(i32),
+    /// Positions of the targets to forward to.
+    Targets(Vec<usize>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayRule {
+    pub index: RelayPattern,
+    pub value: RelayPattern,
+    pub action: RelayAction,
+}
+
+impl RelayRule {
+    pub fn new(index: RelayPattern, value: RelayPattern, action: RelayAction) -> RelayRule {
+        RelayRule { index, value, action }
+    }
+}
+
+/// Packet as it must be forwarded after the rules are applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayRoute {
+    pub index: i32,
+    pub value: i32,
+    /// Positions of the targets to forward to, `None` for all of them.
+    pub targets: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayRuleTable {
+    rules: Vec<RelayRule>,
+}
+
+impl RelayRuleTable {
+    /// Creates an empty table that forwards everything unchanged.
+    pub fn new() -> RelayRuleTable {
+        RelayRuleTable { rules: Vec::new() }
+    }
+
+    pub fn add_rule(&mut self, rule: RelayRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> &[RelayRule] {
+        &self.rules
+    }
+
+    /// Parses rules written in the text format described in the module documentation.
+    pub fn parse(text: &str) -> WowIntResult<RelayRuleTable> {
+        let mut table = RelayRuleTable::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rule = parse_rule(line).ok_or_else(|| WowIntError::parse_at_line("relay rule", line_number + 1, line))?;
+            table.add_rule(rule);
+        }
+        Ok(table)
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> WowIntResult<RelayRuleTable> {
+        RelayRuleTable::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Applies the rules to a packet, returns `None` if the packet is dropped.
+    pub fn apply(&self, index: i32, value: i32) -> Option<RelayRoute> {
+        let mut route = RelayRoute { index, value, targets: None };
+        for rule in &self.rules {
+            if !rule.index.matches(route.index) || !rule.value.matches(route.value) {
+                continue;
+            }
+            match &rule.action {
+                RelayAction::Drop => return None,
+                RelayAction::RemapIndex(new_index) => route.index = *new_index,
+                RelayAction::RemapValue(new_value) => route.value = *new_value,
+                RelayAction::Targets(targets) => route.targets = Some(targets.clone()),
+            }
+        }
+        Some(route)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<RelayRule> {
+    let mut words = line.split_whitespace();
+    let index = RelayPattern::parse(words.next()?)?;
+    let value = RelayPattern::parse(words.next()?)?;
+    let action = match words.next()? {
+        "drop" => RelayAction::Drop,
+        "index" => RelayAction::RemapIndex(words.next()?.parse().ok()?),
+        "value" => RelayAction::RemapValue(words.next()?.parse().ok()?),
+        "to" => {
+            let targets: Vec<usize> = words.by_ref().map(|w| w.parse().ok()).collect::<Option<_>>()?;
+            if targets.is_empty() {
+                return None;
+            }
+            RelayAction::Targets(targets)
+        }
+        _ => return None,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some(RelayRule::new(index, value, action))
+}
+
+/// Called with the source, index and value of every received integer, see `set_packet_log`.
+type PacketLog = Box<dyn Fn(SocketAddr, i32, i32) + Send>;
+
+/// Receives integers on a UDP port and forwards them to targets through a rule table.
+pub struct WowIntegerRelay {
+    socket: UdpSocket,
+    targets: Vec<Box<dyn IntegerSender + Send>>,
+    rules: RelayRuleTable,
+    rules_file: Option<PathBuf>,
+    rules_modified: Option<SystemTime>,
+    last_reload_check: Instant,
+    policy: Option<IntegerPolicy>,
+    decoder: PacketDecoder,
+    reliable: ReliableReceiver,
+    packet_log: Option<PacketLog>,
+    malformed_datagrams: u64,
+    errors: ErrorHandler,
+    // Counts what is received under the listening address, see `metrics`
+    metrics: Option<(Arc<IntegerMetrics>, String)>,
+}
+
+impl WowIntegerRelay {
+    /// Creates a relay listening on the given address (e.g. "0.0.0.0:7073").
+    pub fn bind(listen_address: &str) -> WowIntResult<WowIntegerRelay> {
+        let socket = UdpSocket::bind(listen_address)?;
+        socket.set_read_timeout(Some(RULES_RELOAD_CHECK))?;
+        Ok(WowIntegerRelay {
+            socket,
+            targets: Vec::new(),
+            rules: RelayRuleTable::new(),
+            rules_file: None,
+            rules_modified: None,
+            last_reload_check: Instant::now(),
+            policy: None,
+            decoder: PacketDecoder::Plain,
+            reliable: ReliableReceiver::new(),
+            packet_log: None,
+            malformed_datagrams: 0,
+            errors: ignore_errors(),
+            metrics: None,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Adds a target, its position is the one used by the `to` action.
+    pub fn add_target<T: IntegerSender + Send + 'static>(&mut self, target: T) -> usize {
+        self.targets.push(Box::new(target));
+        self.targets.len() - 1
+    }
+
+    pub fn rules(&self) -> &RelayRuleTable {
+        &self.rules
+    }
+
+    pub fn set_rules(&mut self, rules: RelayRuleTable) {
+        self.rules = rules;
+    }
+
+    /// Filters the received integers with a policy before the rules are applied.
+    pub fn set_policy(&mut self, policy: IntegerPolicy) {
+        self.policy = Some(policy);
+    }
+
+    /// Policy in use, to read what it dropped.
+    pub fn policy(&self) -> Option<&IntegerPolicy> {
+        self.policy.as_ref()
+    }
+
+    /// Only accepts packets signed with the validator key, see `signed`.
+    pub fn set_validator(&mut self, validator: SignedPacketValidator) {
+        self.decoder = PacketDecoder::Signed(validator);
+    }
+
+    /// Sets how the received datagrams are read, plain by default.
+    pub fn set_decoder(&mut self, decoder: PacketDecoder) {
+        self.decoder = decoder;
+    }
+
+    /// Replaces the state acknowledging the reliable datagrams (see `reliable`),
+    /// e.g. to change the gap timeout or simulate lost acknowledgements.
+    pub fn set_reliable_receiver(&mut self, reliable: ReliableReceiver) {
+        self.reliable = reliable;
+    }
+
+    /// Counts the datagrams and integers received, and the ones dropped by the policy.
+    pub fn set_metrics(&mut self, metrics: Arc<IntegerMetrics>) -> io::Result<()> {
+        self.metrics = Some((metrics, self.socket.local_addr()?.to_string()));
+        Ok(())
+    }
+
+    /// Calls `log` with the source, index and value of every received integer,
+    /// e.g. to print them with `describe`.
+    pub fn set_packet_log<F: Fn(SocketAddr, i32, i32) + Send + 'static>(&mut self, log: F) {
+        self.packet_log = Some(Box::new(log));
+    }
+
+    /// Receives the forward, receive and rules reload errors, which do not stop the relay.
+    pub fn set_error_handler<F: Fn(&WowIntError) + Send + Sync + 'static>(&mut self, handler: F) {
+        self.errors = Arc::new(handler);
+    }
+
+    /// Number of datagrams dropped because they could not be decoded.
+    pub fn malformed_datagrams(&self) -> u64 {
+        self.malformed_datagrams
+    }
+
+    /// Loads the rules from a file and watches it for changes.
+    pub fn load_rules_from_file<P: AsRef<Path>>(&mut self, path: P) -> WowIntResult<()> {
+        let path = path.as_ref().to_path_buf();
+        let modified = fs::metadata(&path)?.modified().ok();
+        self.rules = RelayRuleTable::load_from_file(&path)?;
+        self.rules_modified = modified;
+        self.rules_file = Some(path);
+        Ok(())
+    }
+
+    /// Reloads the rules file if it changed since the last load.
+    /// Returns true if the rules were replaced.
+    /// On error the previous rules are kept and the file is read again on the next call.
+    pub fn reload_rules_if_changed(&mut self) -> WowIntResult<bool> {
+        let path = match &self.rules_file {
+            Some(path) => path.clone(),
+            None => return Ok(false),
+        };
+        let modified = fs::metadata(&path)?.modified().ok();
+        if modified == self.rules_modified {
+            return Ok(false);
+        }
+        self.rules = RelayRuleTable::load_from_file(&path)?;
+        self.rules_modified = modified;
+        Ok(true)
+    }
+
+    /// Applies the rules to a packet and forwards it.
+    /// Every target is tried, the first error is returned.
+    pub fn handle_packet(&self, index: i32, value: i32) -> WowIntResult<()> {
+        let route = match self.rules.apply(index, value) {
+            Some(route) => route,
+            None => return Ok(()),
+        };
+        let mut result = Ok(());
+        for (position, target) in self.targets.iter().enumerate() {
+            if let Some(targets) = &route.targets {
+                if !targets.contains(&position) {
+                    continue;
+                }
+            }
+            let sent = target.send_integer_to_target_at_index(route.index, route.value);
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+
+    /// Checks a packet against the policy, then forwards it.
+    /// Returns false if the policy dropped it.
+    pub fn handle_packet_from(&mut self, source: Option<IpAddr>, index: i32, value: i32) -> WowIntResult<bool> {
+        if let Some(policy) = &mut self.policy {
+            if policy.check(source, index, value).is_err() {
+                if let Some((metrics, listener)) = &self.metrics {
+                    metrics.record_dropped(listener, index);
+                }
+                return Ok(false);
+            }
+        }
+        if let Some((metrics, listener)) = &self.metrics {
+            metrics.record_received(listener, index);
+        }
+        self.handle_packet(index, value)?;
+        Ok(true)
+    }
+
+    /// Waits for one datagram and forwards its integers, several for a batch datagram.
+    /// Reliable packets held behind an expired gap (see `reliable`) are forwarded first.
+    /// Returns false if nothing valid was received before the read timeout or if all were dropped.
+    /// Malformed datagrams are counted and dropped, a failed forward is printed and the
+    /// remaining integers of the datagram are still forwarded.
+    pub fn receive_once(&mut self) -> WowIntResult<bool> {
+        let mut forwarded = false;
+        for (source, packet) in self.reliable.expire_gaps() {
+            forwarded |= self.forward_packet(source, &packet);
+        }
+        let mut buf = [0u8; RELIABLE_HEADER_SIZE + MAX_BATCH_DATAGRAM_SIZE];
+        let (size, source) = match self.socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+                return Ok(forwarded)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if let Some((metrics, listener)) = &self.metrics {
+            metrics.record_datagram(listener, size);
+        }
+        let datagram = &buf[..size];
+        // Pings are not authenticated, only a plain relay answers them
+        if matches!(self.decoder, PacketDecoder::Plain) && answer_ping(&self.socket, source, datagram, SystemTime::now())? {
+            return Ok(forwarded);
+        }
+        if !datagram.starts_with(&RELIABLE_MAGIC) {
+            return Ok(self.forward_packet(source, datagram) || forwarded);
+        }
+        // Reliable datagrams only wrap plain packets, they are not acknowledged otherwise
+        if !matches!(self.decoder, PacketDecoder::Plain) {
+            self.malformed_datagrams += 1;
+            return Ok(forwarded);
+        }
+        let packets = match self.reliable.receive(&self.socket, source, datagram) {
+            Ok(packets) => packets,
+            Err(WowIntError::Parse { .. }) => {
+                self.malformed_datagrams += 1;
+                return Ok(forwarded);
+            }
+            Err(e) => return Err(e),
+        };
+        for packet in packets {
+            forwarded |= self.forward_packet(source, &packet);
+        }
+        Ok(forwarded)
+    }
+
+    /// Decodes a packet and forwards its integers, returns true if one was forwarded.
+    fn forward_packet(&mut self, source: SocketAddr, packet: &[u8]) -> bool {
+        let integers = match self.decoder.decode_many(packet) {
+            Ok(integers) => integers,
+            Err(_) => {
+                self.malformed_datagrams += 1;
+                return false;
+            }
+        };
+        let mut forwarded = false;
+        for (index, value) in integers {
+            if let Some(log) = &self.packet_log {
+                log(source, index, value);
+            }
+            match self.handle_packet_from(Some(source.ip()), index, value) {
+                Ok(sent) => forwarded |= sent,
+                Err(e) => (self.errors)(&e),
+            }
+        }
+        forwarded
+    }
+
+    /// Forwards packets forever, reloading the rules file when it changes.
+    /// Send and reload errors go to the error handler and do not stop the relay.
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            if self.last_reload_check.elapsed() >= RULES_RELOAD_CHECK {
+                self.last_reload_check = Instant::now();
+                if let Err(e) = self.reload_rules_if_changed() {
+                    (self.errors)(&e);
+                }
+            }
+            if let Err(e) = self.receive_once() {
+                (self.errors)(&e);
+            }
+        }
+    }
+}
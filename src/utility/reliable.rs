@@ -0,0 +1,450 @@
+//! # Reliable delivery
+//! Optional mode where the receiver acknowledges every packet and the sender retransmits
+//! the ones not acknowledged, so a lost release does not leave a key pressed.
+//!
+//! A reliable datagram wraps a plain packet, little endian:
+//! - magic `WOWR` (4 bytes)
+//! - sender id (u32), random per sender
+//! - sequence (u32), starting at 0 and increased at every packet
+//! - the packet (8 bytes, or a batch)
+//!
+//! The acknowledgement is `WOWA` + sender id + sequence, sent back to the source address.
+//! The receiver delivers the packets of each sender in sequence order: a packet arriving
+//! after a gap is held until the missing ones are retransmitted, so a release never reaches
+//! the game before its press. Retransmitted packets are acknowledged again but delivered once.
+//! A gap the sender gave up on is skipped after the gap timeout (1 s by default).
+//! `WowIntegerRelay` reads reliable datagrams.
+//!
+//! Signed and encrypted framings are not supported: their validators reject the counters
+//! that do not increase, so a retransmitted packet would be acknowledged then dropped as a replay.
+//!
+//! Packets still not acknowledged after the last attempt are reported by `take_failures`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::error::{WowIntError, WowIntResult};
+use super::{bind_udp_socket, IntegerSender, PacketFraming, WowIntegerTarget};
+
+pub const RELIABLE_MAGIC: [u8; 4] = *b"WOWR";
+pub const ACK_MAGIC: [u8; 4] = *b"WOWA";
+pub const RELIABLE_HEADER_SIZE: usize = 12;
+const ACK_SIZE: usize = 12;
+const DEFAULT_RETRANSMIT_TIMEOUT: Duration = Duration::from_millis(100);
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+/// Period of the thread reading the acknowledgements and retransmitting.
+const TICK: Duration = Duration::from_millis(5);
+/// Packets a receiver holds per sender while waiting for a missing one.
+/// A sequence further ahead is taken as a restarted sender.
+const REORDER_WINDOW: u32 = 1024;
+const DEFAULT_GAP_TIMEOUT: Duration = Duration::from_secs(1);
+/// Senders remembered by a receiver before all of them are forgotten.
+const MAX_TRACKED_SENDERS: usize = 1024;
+
+/// Wraps a packet in a reliable datagram.
+pub fn encode_reliable(sender_id: u32, sequence: u32, packet: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(RELIABLE_HEADER_SIZE + packet.len());
+    buf.extend_from_slice(&RELIABLE_MAGIC);
+    buf.extend_from_slice(&sender_id.to_le_bytes());
+    buf.extend_from_slice(&sequence.to_le_bytes());
+    buf.extend_from_slice(packet);
+    buf
+}
+
+/// Returns the sender id, sequence and packet of a reliable datagram.
+pub fn decode_reliable(buf: &[u8]) -> Option<(u32, u32, &[u8])> {
+    let rest = buf.strip_prefix(&RELIABLE_MAGIC)?;
+    if rest.len() < 8 {
+        return None;
+    }
+    let sender_id = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+    let sequence = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]);
+    Some((sender_id, sequence, &rest[8..]))
+}
+
+fn encode_ack(sender_id: u32, sequence: u32) -> [u8; ACK_SIZE] {
+    let mut buf = [0u8; ACK_SIZE];
+    buf[..4].copy_from_slice(&ACK_MAGIC);
+    buf[4..8].copy_from_slice(&sender_id.to_le_bytes());
+    buf[8..].copy_from_slice(&sequence.to_le_bytes());
+    buf
+}
+
+fn decode_ack(buf: &[u8]) -> Option<(u32, u32)> {
+    if buf.len() != ACK_SIZE || buf[..4] != ACK_MAGIC {
+        return None;
+    }
+    Some((
+        u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]),
+        u32::from_le_bytes([buf[8], buf[9], buf[10], buf[11]]),
+    ))
+}
+
+fn simulate_loss(probability: f64) -> bool {
+    probability > 0.0 && rand::random::<f64>() < probability
+}
+
+/// Integer never acknowledged by the receiver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeliveryFailure {
+    pub index: i32,
+    pub value: i32,
+    pub attempts: u32,
+}
+
+struct PendingPacket {
+    datagram: Vec<u8>,
+    index: i32,
+    value: i32,
+    attempts: u32,
+    last_sent: Instant,
+}
+
+struct ReliableState {
+    socket: UdpSocket,
+    sender_id: u32,
+    pending: Mutex<BTreeMap<u32, PendingPacket>>,
+    failures: Mutex<Vec<DeliveryFailure>>,
+    retransmit_timeout: Duration,
+    max_attempts: u32,
+    loss: f64,
+}
+
+impl ReliableState {
+    fn transmit(&self, datagram: &[u8]) -> io::Result<()> {
+        if simulate_loss(self.loss) {
+            return Ok(());
+        }
+        self.socket.send(datagram).map(|_| ())
+    }
+
+    fn receive_acks(&self) {
+        let mut buf = [0u8; 64];
+        while let Ok(size) = self.socket.recv(&mut buf) {
+            if let Some((sender_id, sequence)) = decode_ack(&buf[..size]) {
+                if sender_id == self.sender_id {
+                    self.pending.lock().unwrap().remove(&sequence);
+                }
+            }
+        }
+    }
+
+    fn retransmit_expired(&self) {
+        let mut pending = self.pending.lock().unwrap();
+        let mut failed = Vec::new();
+        for (&sequence, packet) in pending.iter_mut() {
+            if packet.last_sent.elapsed() < self.retransmit_timeout {
+                continue;
+            }
+            if packet.attempts >= self.max_attempts {
+                failed.push(sequence);
+                continue;
+            }
+            // A failed retransmit counts as a lost one, `take_failures` reports it after the last attempt
+            let _ = self.transmit(&packet.datagram);
+            packet.attempts += 1;
+            packet.last_sent = Instant::now();
+        }
+        let mut failures = self.failures.lock().unwrap();
+        for sequence in failed {
+            if let Some(packet) = pending.remove(&sequence) {
+                failures.push(DeliveryFailure {
+                    index: packet.index,
+                    value: packet.value,
+                    attempts: packet.attempts,
+                });
+            }
+        }
+    }
+}
+
+/// Retransmission settings of a `ReliableSender`.
+#[derive(Debug, Clone, Copy)]
+pub struct ReliableOptions {
+    retransmit_timeout: Duration,
+    max_attempts: u32,
+    loss: f64,
+}
+
+impl Default for ReliableOptions {
+    fn default() -> Self {
+        ReliableOptions {
+            retransmit_timeout: DEFAULT_RETRANSMIT_TIMEOUT,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            loss: 0.0,
+        }
+    }
+}
+
+impl ReliableOptions {
+    pub fn new() -> ReliableOptions {
+        ReliableOptions::default()
+    }
+
+    /// Waiting time for an acknowledgement before sending again, 100 ms by default.
+    pub fn with_retransmit_timeout(mut self, retransmit_timeout: Duration) -> Self {
+        self.retransmit_timeout = retransmit_timeout;
+        self
+    }
+
+    /// Sends of a packet, the first one included, before it is given up (5 by default).
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Drops each datagram sent with this probability, to test with simulated loss.
+    pub fn with_simulated_loss(mut self, loss: f64) -> Self {
+        self.loss = loss;
+        self
+    }
+}
+
+/// Sends integers to a player and retransmits them until they are acknowledged.
+/// A thread reads the acknowledgements until the sender is dropped.
+pub struct ReliableSender {
+    state: Arc<ReliableState>,
+    framing: PacketFraming,
+    address: String,
+    // Player index to control
+    index: i32,
+    next_sequence: Mutex<u32>,
+}
+
+impl ReliableSender {
+    /// Sends to the address, framing and index of the target.
+    pub fn new(target: WowIntegerTarget) -> WowIntResult<ReliableSender> {
+        ReliableSender::with_options(target, ReliableOptions::default())
+    }
+
+    /// Fails with `InvalidInput` if the target does not use the plain framing.
+    pub fn with_options(target: WowIntegerTarget, options: ReliableOptions) -> WowIntResult<ReliableSender> {
+        if !matches!(target.framing, PacketFraming::Plain) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Reliable delivery only supports the plain framing",
+            )
+            .into());
+        }
+        let socket_address = target.socket_addr()?;
+        let socket = bind_udp_socket(socket_address)?;
+        socket.connect(socket_address)?;
+        socket.set_read_timeout(Some(TICK))?;
+        let state = Arc::new(ReliableState {
+            socket,
+            sender_id: rand::random(),
+            pending: Mutex::new(BTreeMap::new()),
+            failures: Mutex::new(Vec::new()),
+            retransmit_timeout: options.retransmit_timeout,
+            max_attempts: options.max_attempts,
+            loss: options.loss,
+        });
+        let weak = Arc::downgrade(&state);
+        thread::spawn(move || run_acknowledgements(weak));
+        let address = target.address();
+        Ok(ReliableSender {
+            state,
+            framing: target.framing,
+            address,
+            index: target.index,
+            next_sequence: Mutex::new(0),
+        })
+    }
+
+    /// Number of integers sent and not acknowledged yet.
+    pub fn pending(&self) -> usize {
+        self.state.pending.lock().unwrap().len()
+    }
+
+    /// Returns and forgets the integers given up since the last call.
+    pub fn take_failures(&self) -> Vec<DeliveryFailure> {
+        std::mem::take(&mut *self.state.failures.lock().unwrap())
+    }
+
+    /// Waits until every integer is acknowledged or given up, returns false on timeout.
+    pub fn wait_for_delivery(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while self.pending() > 0 {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+
+    fn send_packet(&self, index: i32, value: i32) -> WowIntResult<()> {
+        let sequence = {
+            let mut next_sequence = self.next_sequence.lock().unwrap();
+            let sequence = *next_sequence;
+            *next_sequence = next_sequence.wrapping_add(1);
+            sequence
+        };
+        let datagram = encode_reliable(self.state.sender_id, sequence, &self.framing.encode(index, value));
+        // Pending before it is sent, the acknowledgement can come back before `transmit` returns
+        self.state.pending.lock().unwrap().insert(
+            sequence,
+            PendingPacket { datagram: datagram.clone(), index, value, attempts: 1, last_sent: Instant::now() },
+        );
+        self.state.transmit(&datagram).map_err(|source| {
+            self.state.pending.lock().unwrap().remove(&sequence);
+            WowIntError::Send { target: self.address.clone(), index, value, source }
+        })
+    }
+}
+
+fn run_acknowledgements(state: Weak<ReliableState>) {
+    while let Some(state) = state.upgrade() {
+        state.receive_acks();
+        state.retransmit_expired();
+    }
+}
+
+impl IntegerSender for ReliableSender {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.send_packet(self.index, value)
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.send_packet(index, value)
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        self.send_integer_to_target(value)
+    }
+}
+
+/// Packets of one sender: the next sequence to deliver and the ones received after a gap.
+struct SenderStream {
+    next: u32,
+    held: BTreeMap<u32, Vec<u8>>,
+    // Since when the first held packet waits for a missing one
+    waiting_since: Option<Instant>,
+}
+
+impl SenderStream {
+    /// A sender starts at 0, a receiver joining later starts at the first sequence it sees.
+    fn new(sequence: u32) -> SenderStream {
+        SenderStream {
+            next: if sequence < REORDER_WINDOW { 0 } else { sequence },
+            held: BTreeMap::new(),
+            waiting_since: None,
+        }
+    }
+
+    /// Holds a packet, returns false if it was already delivered or held.
+    fn hold(&mut self, sequence: u32, packet: &[u8]) -> bool {
+        let ahead = sequence.wrapping_sub(self.next);
+        if ahead > u32::MAX / 2 || self.held.contains_key(&sequence) {
+            return false;
+        }
+        if ahead >= REORDER_WINDOW {
+            // Restarted sender, what was held is delivered first
+            self.next = self.held.keys().next().copied().unwrap_or(sequence);
+            self.held.insert(sequence, packet.to_vec());
+            return true;
+        }
+        self.held.insert(sequence, packet.to_vec());
+        true
+    }
+
+    /// Removes the packets that follow the last delivered one.
+    fn release(&mut self, now: Instant, packets: &mut Vec<Vec<u8>>) {
+        let before = packets.len();
+        while let Some(packet) = self.held.remove(&self.next) {
+            packets.push(packet);
+            self.next = self.next.wrapping_add(1);
+        }
+        self.waiting_since = match self.waiting_since {
+            _ if self.held.is_empty() => None,
+            Some(since) if packets.len() == before => Some(since),
+            _ => Some(now),
+        };
+    }
+
+    /// Skips the missing packets if the first held one waited longer than the timeout.
+    fn skip_gap(&mut self, now: Instant, timeout: Duration, packets: &mut Vec<Vec<u8>>) {
+        if self.waiting_since.is_some_and(|since| now.duration_since(since) >= timeout) {
+            if let Some(&first) = self.held.keys().next() {
+                self.next = first;
+            }
+            self.release(now, packets);
+        }
+    }
+}
+
+/// Receiving side: acknowledges reliable datagrams and delivers them once, in order.
+pub struct ReliableReceiver {
+    streams: HashMap<(SocketAddr, u32), SenderStream>,
+    gap_timeout: Duration,
+    loss: f64,
+}
+
+impl Default for ReliableReceiver {
+    fn default() -> Self {
+        ReliableReceiver {
+            streams: HashMap::new(),
+            gap_timeout: DEFAULT_GAP_TIMEOUT,
+            loss: 0.0,
+        }
+    }
+}
+
+impl ReliableReceiver {
+    pub fn new() -> ReliableReceiver {
+        ReliableReceiver::default()
+    }
+
+    /// Waiting time for a missing packet before the ones after it are delivered, 1 s by default.
+    /// Keep it longer than the time a sender retransmits.
+    pub fn with_gap_timeout(mut self, gap_timeout: Duration) -> Self {
+        self.gap_timeout = gap_timeout;
+        self
+    }
+
+    /// Drops each acknowledgement with this probability, to test with simulated loss.
+    pub fn with_simulated_loss(mut self, loss: f64) -> Self {
+        self.loss = loss;
+        self
+    }
+
+    /// Acknowledges a reliable datagram received on the socket.
+    /// Returns the packets of its sender now deliverable in order: none if it is a duplicate
+    /// or follows a missing packet, several if it fills a gap.
+    pub fn receive(&mut self, socket: &UdpSocket, source: SocketAddr, datagram: &[u8]) -> WowIntResult<Vec<Vec<u8>>> {
+        let (sender_id, sequence, packet) =
+            decode_reliable(datagram).ok_or_else(|| WowIntError::parse("reliable datagram", "header too short"))?;
+        if !simulate_loss(self.loss) {
+            socket.send_to(&encode_ack(sender_id, sequence), source)?;
+        }
+        if self.streams.len() >= MAX_TRACKED_SENDERS && !self.streams.contains_key(&(source, sender_id)) {
+            self.streams.clear();
+        }
+        let stream = self
+            .streams
+            .entry((source, sender_id))
+            .or_insert_with(|| SenderStream::new(sequence));
+        let mut packets = Vec::new();
+        if stream.hold(sequence, packet) {
+            stream.release(Instant::now(), &mut packets);
+        }
+        Ok(packets)
+    }
+
+    /// Returns the packets held behind a gap older than the gap timeout, in order, with their source.
+    /// Called regularly by the relay, also when nothing is received.
+    pub fn expire_gaps(&mut self) -> Vec<(SocketAddr, Vec<u8>)> {
+        let now = Instant::now();
+        let mut expired = Vec::new();
+        for (&(source, _), stream) in self.streams.iter_mut() {
+            let mut packets = Vec::new();
+            stream.skip_gap(now, self.gap_timeout, &mut packets);
+            expired.extend(packets.into_iter().map(|packet| (source, packet)));
+        }
+        expired
+    }
+}
@@ -0,0 +1,299 @@
+//! # Integer sessions
+//! Integers captured with the time they were sent, in two on-disk formats.
+//!
+//! ## JSON Lines (`.jsonl`)
+//! Human-editable, one JSON object per line, empty lines are skipped:
+//!
+//! ```text
+//! {"t":0,"index":2,"value":1032}
+//! {"t":200154,"index":2,"value":2032}
+//! ```
+//!
+//! `t` is the time in microseconds since the start of the recording.
+//!
+//! ## Binary (`.wowint`)
+//! Compact form, about 6 bytes per integer:
+//!
+//! - header: the magic `WOWS` then the version byte `1`
+//! - each integer: the time since the previous integer in microseconds (the first one since the start),
+//!   the index and the value, as LEB128 varints. The delta is unsigned, the index and value are zigzag encoded
+//!   (0, -1, 1, -2... become 0, 1, 2, 3...).
+//!
+//! `SessionReader` detects the format from the first bytes and reads one integer at a time,
+//! so large files are never loaded fully.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use super::error::{WowIntError, WowIntResult};
+
+pub const BINARY_MAGIC: &[u8; 4] = b"WOWS";
+pub const BINARY_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedInteger {
+    /// Time since the start of the recording.
+    pub time: Duration,
+    pub index: i32,
+    pub value: i32,
+}
+
+impl RecordedInteger {
+    pub fn new(time: Duration, index: i32, value: i32) -> RecordedInteger {
+        RecordedInteger { time, index, value }
+    }
+
+    /// Writes the integer as a JSON line, without the line break.
+    pub fn to_json_line(&self) -> String {
+        format!("{{\"t\":{},\"index\":{},\"value\":{}}}", self.time.as_micros(), self.index, self.value)
+    }
+
+    /// Reads a JSON line such as `{"t":1234,"index":2,"value":1032}`, the fields can be in any order.
+    pub fn from_json_line(line: &str) -> Option<RecordedInteger> {
+        let fields = line.trim().strip_prefix('{')?.strip_suffix('}')?;
+        let (mut time, mut index, mut value) = (None, None, None);
+        for field in fields.split(',') {
+            let (name, number) = field.split_once(':')?;
+            match name.trim().trim_matches('"') {
+                "t" => time = Some(Duration::from_micros(number.trim().parse().ok()?)),
+                "index" => index = Some(number.trim().parse().ok()?),
+                "value" => value = Some(number.trim().parse().ok()?),
+                _ => return None,
+            }
+        }
+        Some(RecordedInteger::new(time?, index?, value?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionFormat {
+    JsonLines,
+    Binary,
+}
+
+impl SessionFormat {
+    /// `.jsonl` and `.json` files are JSON Lines, the others are binary.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> SessionFormat {
+        match path.as_ref().extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl") | Some("json") => SessionFormat::JsonLines,
+            _ => SessionFormat::Binary,
+        }
+    }
+}
+
+fn invalid(message: &str) -> WowIntError {
+    WowIntError::parse("session", message)
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    let mut buf = [0u8; 10];
+    let mut length = 0;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[length] = byte;
+            length += 1;
+            break;
+        }
+        buf[length] = byte | 0x80;
+        length += 1;
+    }
+    writer.write_all(&buf[..length])
+}
+
+/// Reads a varint, `None` at the end of the stream before its first byte.
+fn read_varint<R: Read>(reader: &mut R) -> WowIntResult<Option<u64>> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        if reader.read(&mut byte)? == 0 {
+            if shift == 0 {
+                return Ok(None);
+            }
+            return Err(invalid("truncated integer"));
+        }
+        // The tenth byte holds the last bit of a u64 and can not continue
+        if shift == 63 && byte[0] > 1 {
+            return Err(invalid("varint longer than 64 bits"));
+        }
+        value |= ((byte[0] & 0x7F) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+        shift += 7;
+    }
+}
+
+fn zigzag(value: i32) -> u64 {
+    ((value << 1) ^ (value >> 31)) as u32 as u64
+}
+
+fn unzigzag(value: u64) -> WowIntResult<i32> {
+    let value = u32::try_from(value).map_err(|_| invalid("integer out of range"))?;
+    Ok(((value >> 1) as i32) ^ -((value & 1) as i32))
+}
+
+/// Writes integers in either format.
+pub struct SessionWriter<W: Write> {
+    writer: W,
+    format: SessionFormat,
+    previous_time: Duration,
+}
+
+impl<W: Write> SessionWriter<W> {
+    /// Starts a session, the binary header is written now.
+    pub fn new(mut writer: W, format: SessionFormat) -> io::Result<SessionWriter<W>> {
+        if format == SessionFormat::Binary {
+            writer.write_all(BINARY_MAGIC)?;
+            writer.write_all(&[BINARY_VERSION])?;
+        }
+        Ok(SessionWriter {
+            writer,
+            format,
+            previous_time: Duration::ZERO,
+        })
+    }
+
+    pub fn format(&self) -> SessionFormat {
+        self.format
+    }
+
+    /// Writes an integer, the times must not go backward in the binary format.
+    pub fn write(&mut self, integer: &RecordedInteger) -> io::Result<()> {
+        match self.format {
+            SessionFormat::JsonLines => writeln!(self.writer, "{}", integer.to_json_line()),
+            SessionFormat::Binary => {
+                let delta = integer.time.checked_sub(self.previous_time).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "Session times must not go backward")
+                })?;
+                self.previous_time = integer.time;
+                write_varint(&mut self.writer, delta.as_micros() as u64)?;
+                write_varint(&mut self.writer, zigzag(integer.index))?;
+                write_varint(&mut self.writer, zigzag(integer.value))
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl SessionWriter<BufWriter<File>> {
+    /// Creates (or truncates) a session file, the format comes from the extension.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<SessionWriter<BufWriter<File>>> {
+        let format = SessionFormat::from_path(&path);
+        SessionWriter::new(BufWriter::new(File::create(path)?), format)
+    }
+}
+
+/// Reads the integers of a session one at a time, as an iterator.
+pub struct SessionReader<R: BufRead> {
+    reader: R,
+    format: SessionFormat,
+    time: Duration,
+    line_number: usize,
+}
+
+impl<R: BufRead> SessionReader<R> {
+    /// Detects the format from the first bytes.
+    pub fn new(mut reader: R) -> WowIntResult<SessionReader<R>> {
+        // A short read may buffer only the start of the magic, no JSON line starts like it
+        let start = reader.fill_buf()?;
+        let binary = !start.is_empty() && BINARY_MAGIC.starts_with(&start[..start.len().min(BINARY_MAGIC.len())]);
+        let format = if binary {
+            let mut header = [0u8; 5];
+            reader.read_exact(&mut header)?;
+            if &header[..4] != BINARY_MAGIC {
+                return Err(invalid("wrong magic"));
+            }
+            if header[4] != BINARY_VERSION {
+                return Err(invalid(&format!("unsupported version {}", header[4])));
+            }
+            SessionFormat::Binary
+        } else {
+            SessionFormat::JsonLines
+        };
+        Ok(SessionReader {
+            reader,
+            format,
+            time: Duration::ZERO,
+            line_number: 0,
+        })
+    }
+
+    pub fn format(&self) -> SessionFormat {
+        self.format
+    }
+
+    /// Reads the next integer, `None` at the end of the session.
+    pub fn read(&mut self) -> WowIntResult<Option<RecordedInteger>> {
+        match self.format {
+            SessionFormat::JsonLines => loop {
+                let mut line = String::new();
+                if self.reader.read_line(&mut line)? == 0 {
+                    return Ok(None);
+                }
+                self.line_number += 1;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                return RecordedInteger::from_json_line(&line)
+                    .map(Some)
+                    .ok_or_else(|| WowIntError::parse_at_line("session line", self.line_number, line.trim()));
+            },
+            SessionFormat::Binary => {
+                let delta = match read_varint(&mut self.reader)? {
+                    Some(delta) => delta,
+                    None => return Ok(None),
+                };
+                let mut next = || read_varint(&mut self.reader)?.ok_or_else(|| invalid("truncated integer"));
+                let index = unzigzag(next()?)?;
+                let value = unzigzag(next()?)?;
+                self.time += Duration::from_micros(delta);
+                Ok(Some(RecordedInteger::new(self.time, index, value)))
+            }
+        }
+    }
+}
+
+impl SessionReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> WowIntResult<SessionReader<BufReader<File>>> {
+        SessionReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: BufRead> Iterator for SessionReader<R> {
+    type Item = WowIntResult<RecordedInteger>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read().transpose()
+    }
+}
+
+/// Reads every integer of a session file, in either format.
+pub fn load_session<P: AsRef<Path>>(path: P) -> WowIntResult<Vec<RecordedInteger>> {
+    SessionReader::open(path)?.collect()
+}
+
+/// Copies a session into the given format, one integer at a time. Returns the number of integers.
+pub fn convert_session<R: BufRead, W: Write>(reader: R, writer: W, format: SessionFormat) -> WowIntResult<usize> {
+    let mut writer = SessionWriter::new(writer, format)?;
+    let mut count = 0;
+    for integer in SessionReader::new(reader)? {
+        writer.write(&integer?)?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Converts a session file, the output format comes from its extension.
+pub fn convert_session_file<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q) -> WowIntResult<usize> {
+    let format = SessionFormat::from_path(&output);
+    convert_session(BufReader::new(File::open(input)?), BufWriter::new(File::create(output)?), format)
+}
@@ -0,0 +1,230 @@
+//! # Signed packets
+//! Optional framing that lets a receiver check an integer comes from someone knowing a shared key.
+//!
+//! A signed packet is 36 bytes, little endian:
+//! - index (i32)
+//! - value (i32)
+//! - sender id (u32), random per signer
+//! - counter (u64), increased at every packet
+//! - first 16 bytes of the HMAC-SHA256 of the 20 bytes above
+//!
+//! The validator remembers the counters seen for each sender id, in a window of the last 64,
+//! and rejects a counter seen twice or older than the window, so captured packets can not be replayed.
+//! Packets reordered by the network are accepted as long as they are within the window.
+//! The counter starts from the current time so it keeps increasing when the sender restarts.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::error::{WowIntError, WowIntResult};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SIGNED_PACKET_SIZE: usize = 36;
+const SIGNED_CONTENT_SIZE: usize = 20;
+const SIGNATURE_SIZE: usize = 16;
+
+fn new_mac(key: &[u8]) -> HmacSha256 {
+    HmacSha256::new_from_slice(key).expect("HMAC accepts any key size")
+}
+
+/// Signs the integers sent with a shared key.
+pub struct PacketSigner {
+    key: Vec<u8>,
+    sender_id: u32,
+    counter: AtomicU64,
+}
+
+impl PacketSigner {
+    /// Creates a signer with a random sender id.
+    pub fn new(key: &[u8]) -> PacketSigner {
+        PacketSigner::with_sender_id(key, rand::random())
+    }
+
+    pub fn with_sender_id(key: &[u8], sender_id: u32) -> PacketSigner {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_micros() as u64)
+            .unwrap_or(0);
+        PacketSigner {
+            key: key.to_vec(),
+            sender_id,
+            counter: AtomicU64::new(now),
+        }
+    }
+
+    pub fn sender_id(&self) -> u32 {
+        self.sender_id
+    }
+
+    /// Builds the signed packet of an index and an integer.
+    pub fn encode(&self, index: i32, value: i32) -> [u8; SIGNED_PACKET_SIZE] {
+        let counter = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut buf = [0u8; SIGNED_PACKET_SIZE];
+        buf[0..4].copy_from_slice(&index.to_le_bytes());
+        buf[4..8].copy_from_slice(&value.to_le_bytes());
+        buf[8..12].copy_from_slice(&self.sender_id.to_le_bytes());
+        buf[12..20].copy_from_slice(&counter.to_le_bytes());
+        let mut mac = new_mac(&self.key);
+        mac.update(&buf[..SIGNED_CONTENT_SIZE]);
+        let signature = mac.finalize().into_bytes();
+        buf[SIGNED_CONTENT_SIZE..].copy_from_slice(&signature[..SIGNATURE_SIZE]);
+        buf
+    }
+}
+
+/// Number of counters below the highest one that are still accepted once.
+pub const REPLAY_WINDOW_SIZE: u64 = 64;
+
+/// Counters seen from one sender: the highest one and a bitmap of the ones below it,
+/// bit `n` being the counter `highest - n`.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayWindow {
+    highest: u64,
+    seen: u64,
+}
+
+impl Default for ReplayWindow {
+    fn default() -> Self {
+        ReplayWindow::new()
+    }
+}
+
+impl ReplayWindow {
+    /// Window where only the counter 0 was seen.
+    pub fn new() -> ReplayWindow {
+        ReplayWindow { highest: 0, seen: 1 }
+    }
+
+    /// Returns true and records the counter if it was not seen and is not older than the window.
+    pub fn accept(&mut self, counter: u64) -> bool {
+        if counter > self.highest {
+            let shift = counter - self.highest;
+            self.seen = if shift >= REPLAY_WINDOW_SIZE { 0 } else { self.seen << shift };
+            self.seen |= 1;
+            self.highest = counter;
+            return true;
+        }
+        let offset = self.highest - counter;
+        if offset >= REPLAY_WINDOW_SIZE || self.seen & (1 << offset) != 0 {
+            return false;
+        }
+        self.seen |= 1 << offset;
+        true
+    }
+}
+
+/// Checks signed packets and rejects replayed ones.
+pub struct SignedPacketValidator {
+    key: Vec<u8>,
+    windows: HashMap<u32, ReplayWindow>,
+}
+
+impl SignedPacketValidator {
+    pub fn new(key: &[u8]) -> SignedPacketValidator {
+        SignedPacketValidator {
+            key: key.to_vec(),
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Returns the index and integer of a valid packet.
+    /// Fails with `WowIntError::Parse` if the size or the signature is wrong,
+    /// and with `WowIntError::Replayed` if the packet was replayed.
+    pub fn validate(&mut self, buf: &[u8]) -> WowIntResult<(i32, i32)> {
+        if buf.len() != SIGNED_PACKET_SIZE {
+            return Err(WowIntError::parse(
+                "signed packet",
+                &format!("must be {} bytes, received {}", SIGNED_PACKET_SIZE, buf.len()),
+            ));
+        }
+        let mut mac = new_mac(&self.key);
+        mac.update(&buf[..SIGNED_CONTENT_SIZE]);
+        if mac.verify_truncated_left(&buf[SIGNED_CONTENT_SIZE..]).is_err() {
+            return Err(WowIntError::parse("signed packet", "wrong signature"));
+        }
+        let index = i32::from_le_bytes(buf[0..4].try_into().unwrap());
+        let value = i32::from_le_bytes(buf[4..8].try_into().unwrap());
+        let sender_id = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+        let counter = u64::from_le_bytes(buf[12..20].try_into().unwrap());
+        if !self.windows.entry(sender_id).or_default().accept(counter) {
+            return Err(WowIntError::Replayed { sender_id, counter });
+        }
+        Ok((index, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let signer = PacketSigner::new(b"key");
+        let mut validator = SignedPacketValidator::new(b"key");
+        assert_eq!(validator.validate(&signer.encode(3, 1032)).unwrap(), (3, 1032));
+        assert_eq!(validator.validate(&signer.encode(-1, 2032)).unwrap(), (-1, 2032));
+    }
+
+    #[test]
+    fn rejects_tampered_packets_and_wrong_keys() {
+        let signer = PacketSigner::new(b"key");
+        let mut validator = SignedPacketValidator::new(b"key");
+        let mut tampered = signer.encode(3, 1032);
+        tampered[4] ^= 1;
+        assert!(matches!(validator.validate(&tampered), Err(WowIntError::Parse { .. })));
+        let mut tampered = signer.encode(3, 1032);
+        tampered[SIGNED_PACKET_SIZE - 1] ^= 1;
+        assert!(matches!(validator.validate(&tampered), Err(WowIntError::Parse { .. })));
+        let other = PacketSigner::new(b"other key");
+        assert!(matches!(validator.validate(&other.encode(3, 1032)), Err(WowIntError::Parse { .. })));
+        assert!(validator.validate(&signer.encode(3, 1032)[..20]).is_err());
+    }
+
+    #[test]
+    fn rejects_replayed_packets() {
+        let signer = PacketSigner::new(b"key");
+        let mut validator = SignedPacketValidator::new(b"key");
+        let packet = signer.encode(3, 1032);
+        validator.validate(&packet).unwrap();
+        assert!(matches!(
+            validator.validate(&packet),
+            Err(WowIntError::Replayed { sender_id, .. }) if sender_id == signer.sender_id()
+        ));
+    }
+
+    #[test]
+    fn accepts_reordered_packets_within_the_window() {
+        let signer = PacketSigner::new(b"key");
+        let mut validator = SignedPacketValidator::new(b"key");
+        let packets: Vec<_> = (0..70).map(|value| signer.encode(0, value)).collect();
+        validator.validate(&packets[1]).unwrap();
+        validator.validate(&packets[0]).unwrap();
+        assert!(validator.validate(&packets[0]).is_err());
+        validator.validate(&packets[65]).unwrap();
+        validator.validate(&packets[2]).unwrap();
+        // Older than the 64 counters below the highest one
+        assert!(matches!(validator.validate(&packets[1]), Err(WowIntError::Replayed { .. })));
+        validator.validate(&packets[69]).unwrap();
+        assert!(validator.validate(&packets[3]).is_err());
+        validator.validate(&packets[68]).unwrap();
+    }
+
+    #[test]
+    fn window_tracks_the_last_64_counters() {
+        let mut window = ReplayWindow::new();
+        assert!(!window.accept(0));
+        assert!(window.accept(100));
+        assert!(window.accept(37));
+        assert!(!window.accept(36));
+        assert!(window.accept(99));
+        assert!(!window.accept(99));
+        assert!(window.accept(1000));
+        assert!(!window.accept(100));
+        assert!(window.accept(999));
+    }
+}
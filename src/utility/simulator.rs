@@ -0,0 +1,434 @@
+//! # Virtual player
+//! Consumes the integers like the game would, to test bots without it.
+//! Tracks the held keys and the Xbox pad, keeps a timeline of the changes,
+//! and moves a character on a 2D plane:
+//!
+//! - forward / backward: W, S, Up, Down, Numpad8, Numpad2, left stick vertical
+//! - strafe left / right: Q, E, Numpad1, Numpad3, left stick horizontal
+//! - turn left / right: A, D, Left, Right, Numpad4, Numpad6, right stick horizontal
+//!
+//! The heading is in degrees, 0 looks to +y and turning right increases it.
+//! By default the character moves 1 unit and turns 180 degrees per second.
+//!
+//! ```text
+//! let mut player = VirtualPlayer::new();
+//! player.apply(1104);                     // press Numpad8
+//! player.advance(Duration::from_secs(3));
+//! player.apply(2104);                     // release Numpad8
+//! // player.position() is now (0.0, 3.0)
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::error::WowIntResult;
+use super::xbox::XboxAxis;
+use super::{IntegerSender, XboxIntegerActionEnum};
+
+const FORWARD_KEYS: [u8; 3] = [0x57, 0x26, 0x68];
+const BACKWARD_KEYS: [u8; 3] = [0x53, 0x28, 0x62];
+const STRAFE_LEFT_KEYS: [u8; 2] = [0x51, 0x61];
+const STRAFE_RIGHT_KEYS: [u8; 2] = [0x45, 0x63];
+const TURN_LEFT_KEYS: [u8; 3] = [0x41, 0x25, 0x64];
+const TURN_RIGHT_KEYS: [u8; 3] = [0x44, 0x27, 0x66];
+/// Longest step of the movement integration, so turning while moving draws a curve.
+const MOVEMENT_STEP: Duration = Duration::from_millis(10);
+const DIAGONAL: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// State change reported in the timeline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerEvent {
+    KeyDown(u8),
+    KeyUp(u8),
+    ButtonDown(XboxIntegerActionEnum),
+    ButtonUp(XboxIntegerActionEnum),
+    /// Arrow of the directional pad, `None` when released.
+    Dpad(Option<XboxIntegerActionEnum>),
+    Axis(XboxAxis, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedPlayerEvent {
+    pub time: Duration,
+    pub event: PlayerEvent,
+}
+
+/// Buttons, directional pad, sticks and triggers of the virtual gamepad.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XboxPadState {
+    buttons: BTreeSet<i32>,
+    dpad: Option<XboxIntegerActionEnum>,
+    axes: HashMap<XboxAxis, f32>,
+}
+
+impl XboxPadState {
+    pub fn is_pressed(&self, button: XboxIntegerActionEnum) -> bool {
+        self.buttons.contains(&button.integer())
+    }
+
+    /// Buttons held, sorted by integer.
+    pub fn buttons(&self) -> Vec<XboxIntegerActionEnum> {
+        self.buttons.iter().filter_map(|&button| XboxIntegerActionEnum::from_integer(button)).collect()
+    }
+
+    pub fn dpad(&self) -> Option<XboxIntegerActionEnum> {
+        self.dpad
+    }
+
+    /// Value of an axis, -1.0 to 1.0 for the sticks and 0.0 to 1.0 for the triggers.
+    pub fn axis(&self, axis: XboxAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    pub fn is_neutral(&self) -> bool {
+        self.buttons.is_empty() && self.dpad.is_none() && self.axes.values().all(|&value| value == 0.0)
+    }
+}
+
+const AXES: [XboxAxis; 6] = [
+    XboxAxis::LeftStickHorizontal,
+    XboxAxis::LeftStickVertical,
+    XboxAxis::RightStickHorizontal,
+    XboxAxis::RightStickVertical,
+    XboxAxis::LeftTrigger,
+    XboxAxis::RightTrigger,
+];
+
+/// Horizontal and vertical values of the stick directions, from neutral to up left.
+const STICK_DIRECTIONS: [(f32, f32); 9] = [
+    (0.0, 0.0),
+    (0.0, 1.0),
+    (DIAGONAL, DIAGONAL),
+    (1.0, 0.0),
+    (DIAGONAL, -DIAGONAL),
+    (0.0, -1.0),
+    (-DIAGONAL, -DIAGONAL),
+    (-1.0, 0.0),
+    (-DIAGONAL, DIAGONAL),
+];
+
+/// Axis and value set by an action from 1350 to 1389.
+fn axis_level(action: i32) -> Option<(XboxAxis, f32)> {
+    let level = match (action - 1350) / 10 {
+        0 => 1.0,
+        1 => 0.75,
+        2 => 0.5,
+        3 => 0.25,
+        _ => return None,
+    };
+    let (axis, sign) = match (action - 1350) % 10 {
+        0 => (XboxAxis::LeftStickHorizontal, 1.0),
+        1 => (XboxAxis::LeftStickHorizontal, -1.0),
+        2 => (XboxAxis::LeftStickVertical, 1.0),
+        3 => (XboxAxis::LeftStickVertical, -1.0),
+        4 => (XboxAxis::RightStickHorizontal, 1.0),
+        5 => (XboxAxis::RightStickHorizontal, -1.0),
+        6 => (XboxAxis::RightStickVertical, 1.0),
+        7 => (XboxAxis::RightStickVertical, -1.0),
+        8 => (XboxAxis::LeftTrigger, 1.0),
+        _ => (XboxAxis::RightTrigger, 1.0),
+    };
+    Some((axis, sign * level))
+}
+
+/// Simulated player of one index.
+#[derive(Debug, Clone)]
+pub struct VirtualPlayer {
+    time: Duration,
+    held_keys: BTreeSet<u8>,
+    pad: XboxPadState,
+    timeline: Vec<TimedPlayerEvent>,
+    position: (f32, f32),
+    heading: f32,
+    speed: f32,
+    turn_speed: f32,
+}
+
+impl Default for VirtualPlayer {
+    fn default() -> Self {
+        VirtualPlayer::new()
+    }
+}
+
+impl VirtualPlayer {
+    /// Player at (0, 0) looking to +y, at time zero.
+    pub fn new() -> VirtualPlayer {
+        VirtualPlayer {
+            time: Duration::ZERO,
+            held_keys: BTreeSet::new(),
+            pad: XboxPadState::default(),
+            timeline: Vec::new(),
+            position: (0.0, 0.0),
+            heading: 0.0,
+            speed: 1.0,
+            turn_speed: 180.0,
+        }
+    }
+
+    /// Units moved per second at full speed.
+    pub fn with_speed(mut self, units_per_second: f32) -> Self {
+        self.speed = units_per_second;
+        self
+    }
+
+    /// Degrees turned per second at full speed.
+    pub fn with_turn_speed(mut self, degrees_per_second: f32) -> Self {
+        self.turn_speed = degrees_per_second;
+        self
+    }
+
+    /// Time of the simulation, since the creation of the player.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    pub fn position(&self) -> (f32, f32) {
+        self.position
+    }
+
+    /// Heading in degrees, from 0 to 360.
+    pub fn heading(&self) -> f32 {
+        self.heading
+    }
+
+    /// Virtual key codes held, sorted.
+    pub fn held_keys(&self) -> Vec<u8> {
+        self.held_keys.iter().copied().collect()
+    }
+
+    pub fn is_key_held(&self, virtual_key: u8) -> bool {
+        self.held_keys.contains(&virtual_key)
+    }
+
+    pub fn pad(&self) -> &XboxPadState {
+        &self.pad
+    }
+
+    pub fn timeline(&self) -> &[TimedPlayerEvent] {
+        &self.timeline
+    }
+
+    /// True when no key, button or axis is held.
+    pub fn is_idle(&self) -> bool {
+        self.held_keys.is_empty() && self.pad.is_neutral()
+    }
+
+    /// Moves the simulation forward, the held inputs move the character.
+    pub fn advance(&mut self, duration: Duration) {
+        let mut remaining = duration;
+        while !remaining.is_zero() {
+            let step = remaining.min(MOVEMENT_STEP);
+            self.step(step.as_secs_f32());
+            remaining -= step;
+        }
+        self.time += duration;
+    }
+
+    /// Advances to a time of the simulation, does nothing if it is in the past.
+    pub fn advance_to(&mut self, time: Duration) {
+        if let Some(duration) = time.checked_sub(self.time) {
+            self.advance(duration);
+        }
+    }
+
+    fn any_held(&self, keys: &[u8]) -> f32 {
+        if keys.iter().any(|key| self.held_keys.contains(key)) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn step(&mut self, seconds: f32) {
+        let forward = (self.any_held(&FORWARD_KEYS) - self.any_held(&BACKWARD_KEYS)
+            + self.pad.axis(XboxAxis::LeftStickVertical))
+        .clamp(-1.0, 1.0);
+        let strafe = (self.any_held(&STRAFE_RIGHT_KEYS) - self.any_held(&STRAFE_LEFT_KEYS)
+            + self.pad.axis(XboxAxis::LeftStickHorizontal))
+        .clamp(-1.0, 1.0);
+        let turn = (self.any_held(&TURN_RIGHT_KEYS) - self.any_held(&TURN_LEFT_KEYS)
+            + self.pad.axis(XboxAxis::RightStickHorizontal))
+        .clamp(-1.0, 1.0);
+        self.heading = (self.heading + turn * self.turn_speed * seconds).rem_euclid(360.0);
+        let (sin, cos) = self.heading.to_radians().sin_cos();
+        let distance = self.speed * seconds;
+        self.position.0 += (forward * sin + strafe * cos) * distance;
+        self.position.1 += (forward * cos - strafe * sin) * distance;
+    }
+
+    fn push(&mut self, event: PlayerEvent) {
+        self.timeline.push(TimedPlayerEvent { time: self.time, event });
+    }
+
+    fn set_axis(&mut self, axis: XboxAxis, value: f32) {
+        if self.pad.axis(axis) != value {
+            self.pad.axes.insert(axis, value);
+            self.push(PlayerEvent::Axis(axis, value));
+        }
+    }
+
+    fn press_button(&mut self, action: XboxIntegerActionEnum) {
+        if self.pad.buttons.insert(action.integer()) {
+            self.push(PlayerEvent::ButtonDown(action));
+        }
+    }
+
+    fn release_button(&mut self, action: XboxIntegerActionEnum) {
+        if self.pad.buttons.remove(&action.integer()) {
+            self.push(PlayerEvent::ButtonUp(action));
+        }
+    }
+
+    fn set_dpad(&mut self, dpad: Option<XboxIntegerActionEnum>) {
+        if self.pad.dpad != dpad {
+            self.pad.dpad = dpad;
+            self.push(PlayerEvent::Dpad(dpad));
+        }
+    }
+
+    /// Releases the pad, the menu buttons too unless `keep_menu`.
+    fn release_pad(&mut self, keep_menu: bool) {
+        for button in self.pad.buttons() {
+            let menu = matches!(button, XboxIntegerActionEnum::PressMenuLeft | XboxIntegerActionEnum::PressMenuRight);
+            if !(keep_menu && menu) {
+                self.release_button(button);
+            }
+        }
+        self.set_dpad(None);
+        for axis in AXES {
+            self.set_axis(axis, 0.0);
+        }
+    }
+
+    /// Applies an integer at the current time of the simulation.
+    /// Integers that do not change the state (random, recording, unknown) are ignored.
+    pub fn apply(&mut self, value: i32) {
+        match value {
+            1000..=1255 => {
+                let key = (value - 1000) as u8;
+                if self.held_keys.insert(key) {
+                    self.push(PlayerEvent::KeyDown(key));
+                }
+            }
+            2000..=2255 => {
+                let key = (value - 2000) as u8;
+                if self.held_keys.remove(&key) {
+                    self.push(PlayerEvent::KeyUp(key));
+                }
+            }
+            1300..=1399 => self.apply_xbox(value, true),
+            2300..=2399 => self.apply_xbox(value - 1000, false),
+            _ => {}
+        }
+    }
+
+    /// Advances to a time of the simulation then applies the integer.
+    pub fn apply_at(&mut self, time: Duration, value: i32) {
+        self.advance_to(time);
+        self.apply(value);
+    }
+
+    fn apply_xbox(&mut self, action: i32, press: bool) {
+        let action_enum = match XboxIntegerActionEnum::from_integer(action) {
+            Some(action_enum) => action_enum,
+            None => return,
+        };
+        match action {
+            1310 => self.set_dpad(None),
+            1311..=1318 if press => self.set_dpad(Some(action_enum)),
+            1311..=1318 if self.pad.dpad == Some(action_enum) => self.set_dpad(None),
+            1311..=1318 => {}
+            1300..=1319 if press => self.press_button(action_enum),
+            1300..=1319 => self.release_button(action_enum),
+            1330..=1338 | 1340..=1348 => {
+                let (horizontal, vertical) = if action < 1340 {
+                    (XboxAxis::LeftStickHorizontal, XboxAxis::LeftStickVertical)
+                } else {
+                    (XboxAxis::RightStickHorizontal, XboxAxis::RightStickVertical)
+                };
+                let (x, y) = if press {
+                    STICK_DIRECTIONS[(action % 10) as usize]
+                } else {
+                    (0.0, 0.0)
+                };
+                self.set_axis(horizontal, x);
+                self.set_axis(vertical, y);
+            }
+            1350..=1389 => {
+                if let Some((axis, value)) = axis_level(action) {
+                    self.set_axis(axis, if press { value } else { 0.0 });
+                }
+            }
+            1390 if press => self.release_pad(false),
+            1391 if press => self.release_pad(true),
+            _ => {}
+        }
+    }
+}
+
+/// Virtual players of every index, usable as a sender by the bot under test.
+/// The time of the simulation is the real time since the creation.
+pub struct VirtualGame {
+    start: Instant,
+    index: i32,
+    players: Mutex<HashMap<i32, VirtualPlayer>>,
+}
+
+impl VirtualGame {
+    /// `index` is the player of `send_integer_to_target`.
+    pub fn new(index: i32) -> VirtualGame {
+        VirtualGame {
+            start: Instant::now(),
+            index,
+            players: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Copy of a player, advanced to now. `None` if it never received an integer.
+    pub fn player(&self, index: i32) -> Option<VirtualPlayer> {
+        let mut players = self.players.lock().unwrap();
+        let player = players.get_mut(&index)?;
+        player.advance_to(self.start.elapsed());
+        Some(player.clone())
+    }
+
+    /// Indices of the players that received an integer, sorted.
+    pub fn indices(&self) -> Vec<i32> {
+        let mut indices: Vec<i32> = self.players.lock().unwrap().keys().copied().collect();
+        indices.sort();
+        indices
+    }
+
+    fn apply(&self, index: i32, value: i32) {
+        let now = self.start.elapsed();
+        self.players
+            .lock()
+            .unwrap()
+            .entry(index)
+            .or_default()
+            .apply_at(now, value);
+    }
+}
+
+impl IntegerSender for VirtualGame {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.apply(self.index, value);
+        Ok(())
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.apply(index, value);
+        Ok(())
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        let now = self.start.elapsed();
+        let mut players = self.players.lock().unwrap();
+        players.entry(self.index).or_default();
+        for player in players.values_mut() {
+            player.apply_at(now, value);
+        }
+        Ok(())
+    }
+}
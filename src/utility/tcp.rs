@@ -0,0 +1,248 @@
+//! # TCP transport
+//! Sends integers over TCP so a lost release can not go unnoticed as with UDP.
+//! Every frame is a length (u32, little endian) followed by the packet,
+//! written with the same `PacketFraming` as the UDP target (8 bytes when plain).
+//!
+//! The target reconnects automatically when the connection is lost,
+//! waiting longer between each failed attempt.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::encrypted::PacketEncrypter;
+use super::error::{ignore_errors, ErrorHandler, WowIntError, WowIntResult};
+use super::signed::PacketSigner;
+use super::{format_address, IntegerSender, PacketDecoder, PacketFraming};
+
+/// Biggest frame accepted by the listener, a bigger length means a broken stream.
+pub const MAX_FRAME_SIZE: usize = 1024;
+
+const DEFAULT_FIRST_BACKOFF: Duration = Duration::from_millis(50);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(2);
+const DEFAULT_CONNECT_ATTEMPTS: u32 = 5;
+
+/// Writes a length-prefixed frame.
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)
+}
+
+/// Reads a length-prefixed frame, returns `None` when the stream is closed between two frames.
+/// A stream closed in the middle of a frame fails with `UnexpectedEof`.
+pub fn read_frame<R: Read>(reader: &mut R) -> WowIntResult<Option<Vec<u8>>> {
+    let mut length = [0u8; 4];
+    let mut filled = 0;
+    while filled < length.len() {
+        match reader.read(&mut length[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stream closed in the frame length").into())
+            }
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_FRAME_SIZE {
+        return Err(WowIntError::PayloadTooLarge { size: length, max: MAX_FRAME_SIZE });
+    }
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+/// Sends integers to a player over a TCP connection.
+pub struct WowIntegerTcpTarget {
+    // Player address as "ip:port"
+    address: String,
+    // Player index to control
+    index: i32,
+    framing: PacketFraming,
+    stream: Mutex<Option<TcpStream>>,
+    first_backoff: Duration,
+    max_backoff: Duration,
+    connect_attempts: u32,
+}
+
+impl WowIntegerTcpTarget {
+    /// Creates a target, the connection is opened at the first send.
+    pub fn new(ip: &str, port: u16, index: i32) -> WowIntegerTcpTarget {
+        WowIntegerTcpTarget {
+            address: format_address(ip, port),
+            index,
+            framing: PacketFraming::Plain,
+            stream: Mutex::new(None),
+            first_backoff: DEFAULT_FIRST_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            connect_attempts: DEFAULT_CONNECT_ATTEMPTS,
+        }
+    }
+
+    /// Uses the given framing for the packets sent.
+    pub fn with_framing(mut self, framing: PacketFraming) -> WowIntegerTcpTarget {
+        self.framing = framing;
+        self
+    }
+
+    /// Signs every packet sent with the shared key (see `signed`).
+    pub fn with_signing_key(self, key: &[u8]) -> WowIntegerTcpTarget {
+        self.with_framing(PacketFraming::Signed(PacketSigner::new(key)))
+    }
+
+    /// Encrypts every packet sent with the pre-shared key (see `encrypted`).
+    pub fn with_encryption_key(self, pre_shared_key: &[u8]) -> WowIntegerTcpTarget {
+        self.with_framing(PacketFraming::Encrypted(PacketEncrypter::new(pre_shared_key)))
+    }
+
+    /// Sets how many connections are tried before a send fails,
+    /// and the waiting time after the first failure, doubled up to `max_backoff`.
+    pub fn with_reconnect(mut self, connect_attempts: u32, first_backoff: Duration, max_backoff: Duration) -> WowIntegerTcpTarget {
+        self.connect_attempts = connect_attempts.max(1);
+        self.first_backoff = first_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Returns true if a connection is currently open.
+    pub fn is_connected(&self) -> bool {
+        self.stream.lock().unwrap().is_some()
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let mut backoff = self.first_backoff;
+        let mut attempt = 1;
+        loop {
+            match TcpStream::connect(&self.address) {
+                Ok(stream) => {
+                    stream.set_nodelay(true)?;
+                    return Ok(stream);
+                }
+                Err(e) if attempt >= self.connect_attempts => return Err(e),
+                Err(_) => {
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Writes the frame, reconnecting once if the connection was lost.
+    /// The connection is not locked while reconnecting, so the backoff does not block other sends.
+    fn send_frame(&self, payload: &[u8]) -> io::Result<()> {
+        {
+            let mut stream = self.stream.lock().unwrap();
+            if let Some(connected) = stream.as_mut() {
+                if write_frame(connected, payload).is_ok() {
+                    return Ok(());
+                }
+                *stream = None;
+            }
+        }
+        let mut connected = self.connect()?;
+        write_frame(&mut connected, payload)?;
+        *self.stream.lock().unwrap() = Some(connected);
+        Ok(())
+    }
+}
+
+impl IntegerSender for WowIntegerTcpTarget {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.send_integer_to_target_at_index(self.index, value)
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.send_frame(&self.framing.encode(index, value)).map_err(|source| WowIntError::Send {
+            target: self.address.clone(),
+            index,
+            value,
+            source,
+        })
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        self.send_integer_to_target(value)
+    }
+}
+
+/// Accepts TCP connections and reads the integers sent by `WowIntegerTcpTarget`.
+pub struct WowIntegerTcpListener {
+    listener: TcpListener,
+    decoder: Arc<Mutex<PacketDecoder>>,
+    errors: ErrorHandler,
+}
+
+impl WowIntegerTcpListener {
+    /// Creates a listener on the given address (e.g. "0.0.0.0:7074").
+    pub fn bind(listen_address: &str) -> WowIntResult<WowIntegerTcpListener> {
+        Ok(WowIntegerTcpListener {
+            listener: TcpListener::bind(listen_address)?,
+            decoder: Arc::new(Mutex::new(PacketDecoder::Plain)),
+            errors: ignore_errors(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Sets how the received frames are read, plain by default.
+    /// The decoder is shared by all the connections.
+    pub fn set_decoder(&mut self, decoder: PacketDecoder) {
+        self.decoder = Arc::new(Mutex::new(decoder));
+    }
+
+    /// Receives the accept errors and the errors closing a connection.
+    pub fn set_error_handler<F: Fn(&WowIntError) + Send + Sync + 'static>(&mut self, handler: F) {
+        self.errors = Arc::new(handler);
+    }
+
+    /// Accepts connections forever, each one read on its own thread.
+    /// The handler is called with the sender address, the index and the integer.
+    /// A connection sending an invalid frame is closed.
+    pub fn run<F>(&self, handler: F) -> io::Result<()>
+    where
+        F: Fn(SocketAddr, i32, i32) + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    (self.errors)(&e.into());
+                    continue;
+                }
+            };
+            let handler = Arc::clone(&handler);
+            let decoder = Arc::clone(&self.decoder);
+            let errors = Arc::clone(&self.errors);
+            thread::spawn(move || {
+                if let Err(e) = read_connection(stream, &decoder, &*handler) {
+                    errors(&e);
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+fn read_connection<F>(mut stream: TcpStream, decoder: &Mutex<PacketDecoder>, handler: &F) -> WowIntResult<()>
+where
+    F: Fn(SocketAddr, i32, i32),
+{
+    let peer = stream.peer_addr()?;
+    while let Some(payload) = read_frame(&mut stream)? {
+        let packet = decoder.lock().unwrap().decode(&payload)?;
+        match packet {
+            Some((index, value)) => handler(peer, index, value),
+            None => return Err(WowIntError::parse("TCP frame", &format!("{} bytes", payload.len()))),
+        }
+    }
+    Ok(())
+}
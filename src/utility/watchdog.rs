@@ -0,0 +1,232 @@
+//! # Watchdog
+//! Wraps a sender and remembers every press integer until its release (+1000) is sent.
+//! Keys held longer than a maximum time are released automatically,
+//! so a client that crashes between a press and its release does not leave a key stuck.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::error::{WowIntError, WowIntResult};
+use super::{IntegerSender, XboxIntegerAction};
+
+/// Player the integer was sent to, as seen by the wrapped sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum KeyScope {
+    /// Sent to one player, with `send_integer_to_target_at_index`
+    /// or with `send_integer_to_target` and the sender own index.
+    Index(i32),
+    /// Sent with `send_integer_to_all`.
+    All,
+}
+
+/// Returns true for integers that press a key or a gamepad button.
+/// The Xbox commands (1390 to 1399) are not presses.
+pub fn is_press_integer(value: i32) -> bool {
+    (1000..2000).contains(&value) && !(1390..1400).contains(&value)
+}
+
+/// Returns true for integers that release a key or a gamepad button.
+pub fn is_release_integer(value: i32) -> bool {
+    (2000..3000).contains(&value) && !(2390..2400).contains(&value)
+}
+
+/// Returns true for the press integers of the gamepad, the ones released by `RELEASE_ALL`.
+pub fn is_xbox_press(value: i32) -> bool {
+    (1300..1390).contains(&value)
+}
+
+/// Presses sent and not released yet, with the player they were sent to (`K`),
+/// in the order they were first pressed.
+/// `RELEASE_ALL` and `RELEASE_ALL_BUT_MENU` release the gamepad presses of their player.
+#[derive(Debug, Clone)]
+pub struct HeldKeys<K> {
+    // Player, press integer and time of the last press
+    presses: Vec<(K, i32, Instant)>,
+}
+
+impl<K> Default for HeldKeys<K> {
+    fn default() -> Self {
+        HeldKeys { presses: Vec::new() }
+    }
+}
+
+impl<K: Copy + PartialEq> HeldKeys<K> {
+    pub fn new() -> HeldKeys<K> {
+        HeldKeys::default()
+    }
+
+    /// Updates the held presses with an integer sent to a player.
+    /// Pressing a held key again restarts its hold time.
+    pub fn track(&mut self, scope: K, value: i32) {
+        if is_press_integer(value) {
+            let now = Instant::now();
+            match self.presses.iter_mut().find(|(held_scope, press, _)| *held_scope == scope && *press == value) {
+                Some(held) => held.2 = now,
+                None => self.presses.push((scope, value, now)),
+            }
+        } else if is_release_integer(value) {
+            self.presses.retain(|&(held_scope, press, _)| held_scope != scope || press != value - 1000);
+        } else if value == XboxIntegerAction::RELEASE_ALL || value == XboxIntegerAction::RELEASE_ALL_BUT_MENU {
+            self.presses.retain(|&(held_scope, press, _)| held_scope != scope || !is_xbox_press(press));
+        }
+    }
+
+    /// Presses held, with their player.
+    pub fn keys(&self) -> Vec<(K, i32)> {
+        self.presses.iter().map(|&(scope, press, _)| (scope, press)).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.presses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presses.is_empty()
+    }
+
+    /// Forgets and returns the presses held for `max_hold` or longer.
+    pub fn take_expired(&mut self, max_hold: Duration) -> Vec<(K, i32)> {
+        let mut expired = Vec::new();
+        self.presses.retain(|&(scope, press, pressed_at)| {
+            let keep = pressed_at.elapsed() < max_hold;
+            if !keep {
+                expired.push((scope, press));
+            }
+            keep
+        });
+        expired
+    }
+
+    /// Forgets and returns every held press.
+    pub fn take_all(&mut self) -> Vec<(K, i32)> {
+        self.presses.drain(..).map(|(scope, press, _)| (scope, press)).collect()
+    }
+}
+
+pub struct StuckKeyWatchdog<S: IntegerSender> {
+    sender: S,
+    max_hold: Duration,
+    held: Mutex<HeldKeys<KeyScope>>,
+    // Own index of the sender, a press sent to the target is released at this index
+    index: i32,
+}
+
+impl<S: IntegerSender> StuckKeyWatchdog<S> {
+    /// Wraps a sender, keys held longer than `max_hold` are released by `release_expired`.
+    /// `index` is the index the sender uses for `send_integer_to_target`.
+    pub fn new(sender: S, max_hold: Duration, index: i32) -> StuckKeyWatchdog<S> {
+        StuckKeyWatchdog {
+            sender,
+            max_hold,
+            held: Mutex::new(HeldKeys::new()),
+            index,
+        }
+    }
+
+    pub fn sender(&self) -> &S {
+        &self.sender
+    }
+
+    pub fn max_hold(&self) -> Duration {
+        self.max_hold
+    }
+
+    /// Press integers currently held, with the player they were sent to.
+    pub fn held_keys(&self) -> Vec<(KeyScope, i32)> {
+        self.held.lock().unwrap().keys()
+    }
+
+    fn track(&self, scope: KeyScope, value: i32) {
+        self.held.lock().unwrap().track(scope, value);
+    }
+
+    fn send_in_scope(&self, scope: KeyScope, value: i32) -> WowIntResult<()> {
+        match scope {
+            KeyScope::Index(index) => self.sender.send_integer_to_target_at_index(index, value),
+            KeyScope::All => self.sender.send_integer_to_all(value),
+        }
+    }
+
+    /// Sends the release of every key held longer than the maximum hold time.
+    /// Returns how many keys were released, or the first send error.
+    pub fn release_expired(&self) -> WowIntResult<usize> {
+        let expired = self.held.lock().unwrap().take_expired(self.max_hold);
+        let mut result = Ok(expired.len());
+        for (scope, press) in expired {
+            if let Err(e) = self.send_in_scope(scope, press + 1000) {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Sends the release of every held key, and `RELEASE_ALL` to players with a held gamepad button.
+    /// Every release is tried, the first send error is returned.
+    pub fn release_all(&self) -> WowIntResult<()> {
+        let held = self.held.lock().unwrap().take_all();
+        let mut pads = HashSet::new();
+        let mut result = Ok(());
+        for (scope, press) in held {
+            if is_xbox_press(press) {
+                pads.insert(scope);
+            }
+            let sent = self.send_in_scope(scope, press + 1000);
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        for scope in pads {
+            let sent = self.send_in_scope(scope, XboxIntegerAction::RELEASE_ALL);
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+}
+
+impl<S: IntegerSender + Send + Sync + 'static> StuckKeyWatchdog<S> {
+    /// Starts a thread calling `release_expired` at every interval, its errors are passed to `on_error`.
+    /// The thread stops when the last `Arc` of the watchdog is dropped.
+    pub fn spawn_release_thread<F>(watchdog: &Arc<Self>, interval: Duration, on_error: F) -> JoinHandle<()>
+    where
+        F: Fn(&WowIntError) + Send + 'static,
+    {
+        let watchdog = Arc::downgrade(watchdog);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            match watchdog.upgrade() {
+                Some(watchdog) => {
+                    if let Err(e) = watchdog.release_expired() {
+                        on_error(&e);
+                    }
+                }
+                None => return,
+            }
+        })
+    }
+}
+
+impl<S: IntegerSender> IntegerSender for StuckKeyWatchdog<S> {
+    fn send_integer_to_target(&self, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_target(value)?;
+        self.track(KeyScope::Index(self.index), value);
+        Ok(())
+    }
+
+    fn send_integer_to_target_at_index(&self, index: i32, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_target_at_index(index, value)?;
+        self.track(KeyScope::Index(index), value);
+        Ok(())
+    }
+
+    fn send_integer_to_all(&self, value: i32) -> WowIntResult<()> {
+        self.sender.send_integer_to_all(value)?;
+        self.track(KeyScope::All, value);
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+//! # WebSocket bridge
+//! Lets browsers (Scratch, web pages) send integers, as they can not emit raw UDP.
+//! Each WebSocket message is one integer, forwarded through a sender:
+//! - binary 8 bytes: index + integer, little endian as a UDP packet
+//! - binary 4 bytes: integer for the sender own index
+//! - text `"1032"`: integer for the sender own index
+//! - text `"2:1032"`: index 2, integer 1032
+//!
+//! Browsers always send an `Origin` header, it can be checked against an allow-list.
+//! Connections without `Origin` do not come from a browser and are accepted.
+
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::Message;
+
+use super::error::{ignore_errors, ErrorHandler, WowIntError, WowIntResult};
+use super::IntegerSender;
+
+/// Reads a text message, returns the optional index and the integer.
+pub fn parse_text_message(text: &str) -> Option<(Option<i32>, i32)> {
+    let text = text.trim();
+    match text.split_once(':') {
+        Some((index, value)) => Some((Some(index.trim().parse().ok()?), value.trim().parse().ok()?)),
+        None => Some((None, text.parse().ok()?)),
+    }
+}
+
+/// Reads a binary message, returns the optional index and the integer.
+pub fn parse_binary_message(bytes: &[u8]) -> Option<(Option<i32>, i32)> {
+    match bytes.len() {
+        4 => Some((None, i32::from_le_bytes(bytes.try_into().ok()?))),
+        8 => Some((
+            Some(i32::from_le_bytes(bytes[0..4].try_into().ok()?)),
+            i32::from_le_bytes(bytes[4..8].try_into().ok()?),
+        )),
+        _ => None,
+    }
+}
+
+/// Local WebSocket server forwarding the received integers to a sender.
+pub struct WowIntegerWebSocketBridge<S: IntegerSender + Send + Sync + 'static> {
+    listener: TcpListener,
+    target: Arc<S>,
+    allowed_origins: Arc<Vec<String>>,
+    errors: ErrorHandler,
+}
+
+impl<S: IntegerSender + Send + Sync + 'static> WowIntegerWebSocketBridge<S> {
+    /// Creates a bridge listening on the given address (e.g. "127.0.0.1:7075").
+    pub fn bind(listen_address: &str, target: S) -> WowIntResult<WowIntegerWebSocketBridge<S>> {
+        Ok(WowIntegerWebSocketBridge {
+            listener: TcpListener::bind(listen_address)?,
+            target: Arc::new(target),
+            allowed_origins: Arc::new(Vec::new()),
+            errors: ignore_errors(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Only accepts browsers from this origin (e.g. "http://localhost:8601").
+    /// Without any allowed origin, every origin is accepted.
+    pub fn allow_origin(&mut self, origin: &str) {
+        Arc::make_mut(&mut self.allowed_origins).push(origin.to_string());
+    }
+
+    /// Receives the accept and connection errors, and the messages that are not integers.
+    pub fn set_error_handler<F: Fn(&WowIntError) + Send + Sync + 'static>(&mut self, handler: F) {
+        self.errors = Arc::new(handler);
+    }
+
+    /// Accepts connections forever, each one read on its own thread.
+    pub fn run(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    (self.errors)(&e.into());
+                    continue;
+                }
+            };
+            let target = Arc::clone(&self.target);
+            let allowed_origins = Arc::clone(&self.allowed_origins);
+            let errors = Arc::clone(&self.errors);
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, &*target, &allowed_origins, &*errors) {
+                    errors(&e);
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+fn check_origin(request: &Request, allowed_origins: &[String]) -> Result<(), String> {
+    if allowed_origins.is_empty() {
+        return Ok(());
+    }
+    let origin = match request.headers().get("Origin") {
+        Some(origin) => origin.to_str().unwrap_or(""),
+        None => return Ok(()),
+    };
+    if allowed_origins.iter().any(|allowed| allowed == origin) {
+        Ok(())
+    } else {
+        Err(format!("Origin not allowed: {}", origin))
+    }
+}
+
+// The handshake callback signature is imposed by tungstenite
+#[allow(clippy::result_large_err)]
+fn handle_connection<S: IntegerSender>(
+    stream: TcpStream,
+    target: &S,
+    allowed_origins: &[String],
+    errors: &(dyn Fn(&WowIntError) + Send + Sync),
+) -> WowIntResult<()> {
+    let callback = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        check_origin(request, allowed_origins).map(|()| response).map_err(|reason| {
+            let mut error = ErrorResponse::new(Some(reason));
+            *error.status_mut() = StatusCode::FORBIDDEN;
+            error
+        })
+    };
+    let mut socket = tungstenite::accept_hdr(stream, callback).map_err(|e| io::Error::other(e.to_string()))?;
+    loop {
+        let message = match socket.read() {
+            Ok(message) => message,
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(e) => return Err(io::Error::other(e).into()),
+        };
+        let packet = match &message {
+            Message::Text(text) => parse_text_message(text),
+            Message::Binary(bytes) => parse_binary_message(bytes),
+            Message::Close(_) => return Ok(()),
+            _ => continue,
+        };
+        match packet {
+            Some((Some(index), value)) => target.send_integer_to_target_at_index(index, value)?,
+            Some((None, value)) => target.send_integer_to_target(value)?,
+            None => errors(&WowIntError::parse("WebSocket message", &format!("{:?}", message))),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! # Xbox axes
+//! Turns analog values (sticks from -1.0 to 1.0, triggers from 0.0 to 1.0)
+//! into the `XboxIntegerAction` levels: 25%, 50%, 75% and 100%.
+//! Values closer to zero than 12.5% are in the dead zone.
+
+use std::collections::HashMap;
+
+use super::XboxIntegerAction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XboxAxis {
+    LeftStickHorizontal,
+    LeftStickVertical,
+    RightStickHorizontal,
+    RightStickVertical,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl XboxAxis {
+    /// Offset of the positive action from the level base (e.g. 1350 for 100%).
+    fn offset(&self) -> i32 {
+        match self {
+            XboxAxis::LeftStickHorizontal => 0,
+            XboxAxis::LeftStickVertical => 2,
+            XboxAxis::RightStickHorizontal => 4,
+            XboxAxis::RightStickVertical => 6,
+            XboxAxis::LeftTrigger => 8,
+            XboxAxis::RightTrigger => 9,
+        }
+    }
+
+    /// Reads a name such as "left_stick_horizontal" or "right_trigger".
+    pub fn from_name(name: &str) -> Option<XboxAxis> {
+        match name {
+            "left_stick_horizontal" => Some(XboxAxis::LeftStickHorizontal),
+            "left_stick_vertical" => Some(XboxAxis::LeftStickVertical),
+            "right_stick_horizontal" => Some(XboxAxis::RightStickHorizontal),
+            "right_stick_vertical" => Some(XboxAxis::RightStickVertical),
+            "left_trigger" => Some(XboxAxis::LeftTrigger),
+            "right_trigger" => Some(XboxAxis::RightTrigger),
+            _ => None,
+        }
+    }
+
+    pub fn is_trigger(&self) -> bool {
+        matches!(self, XboxAxis::LeftTrigger | XboxAxis::RightTrigger)
+    }
+}
+
+/// Returns the action integer for an axis value, `None` in the dead zone.
+/// Trigger values below zero are in the dead zone.
+pub fn quantize_axis(axis: XboxAxis, value: f32) -> Option<i32> {
+    if value.is_nan() || (axis.is_trigger() && value < 0.0) {
+        return None;
+    }
+    let magnitude = value.abs();
+    let base = if magnitude >= 0.875 {
+        XboxIntegerAction::SET_LEFT_STICK_HORIZONTAL_100
+    } else if magnitude >= 0.625 {
+        XboxIntegerAction::SET_LEFT_STICK_HORIZONTAL_075
+    } else if magnitude >= 0.375 {
+        XboxIntegerAction::SET_LEFT_STICK_HORIZONTAL_050
+    } else if magnitude >= 0.125 {
+        XboxIntegerAction::SET_LEFT_STICK_HORIZONTAL_025
+    } else {
+        return None;
+    };
+    let negative = if value < 0.0 { 1 } else { 0 };
+    Some(base + axis.offset() + negative)
+}
+
+/// Remembers the level sent for each axis of each player,
+/// so a new value releases the previous level and the dead zone releases it all.
+/// The player is `None` for the own index of the sender, kept apart from an explicit index.
+#[derive(Debug, Clone, Default)]
+pub struct XboxAxisTracker {
+    levels: HashMap<(Option<i32>, XboxAxis), i32>,
+}
+
+impl XboxAxisTracker {
+    pub fn new() -> XboxAxisTracker {
+        XboxAxisTracker::default()
+    }
+
+    /// Returns the integers to send for a new axis value, in order.
+    /// Nothing is returned if the level did not change.
+    pub fn update(&mut self, index: Option<i32>, axis: XboxAxis, value: f32) -> Vec<i32> {
+        let level = quantize_axis(axis, value);
+        let previous = self.levels.get(&(index, axis)).copied();
+        if level == previous {
+            return Vec::new();
+        }
+        let mut integers = Vec::new();
+        if let Some(previous) = previous {
+            integers.push(previous + 1000);
+        }
+        match level {
+            Some(level) => {
+                integers.push(level);
+                self.levels.insert((index, axis), level);
+            }
+            None => {
+                self.levels.remove(&(index, axis));
+            }
+        }
+        integers
+    }
+}
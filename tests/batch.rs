@@ -0,0 +1,89 @@
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::time::Duration;
+
+use wowint::utility::batch::{
+    decode_index_integers, encode_index_integers, send_datagrams, BATCH_MAGIC, MAX_BATCH_INTEGERS,
+    MAX_BATCH_DATAGRAM_SIZE,
+};
+use wowint::utility::metrics::IntegerMetrics;
+use wowint::utility::{decode_index_integer, encode_index_integer, IntegerSender, WowIntegerTarget};
+
+fn receiver() -> UdpSocket {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    socket
+}
+
+fn receive(socket: &UdpSocket, count: usize) -> Vec<Vec<u8>> {
+    let mut buf = [0u8; MAX_BATCH_DATAGRAM_SIZE];
+    (0..count)
+        .map(|_| {
+            let size = socket.recv(&mut buf).unwrap();
+            buf[..size].to_vec()
+        })
+        .collect()
+}
+
+#[test]
+fn encodes_and_decodes_batches() {
+    let integers = vec![(2, 1032), (-1, 2032), (7, 1390)];
+    let datagram = encode_index_integers(&integers);
+    assert_eq!(&datagram[..4], &BATCH_MAGIC);
+    assert_eq!(datagram.len(), 4 + 3 * 8);
+    assert_eq!(decode_index_integers(&datagram), Some(integers));
+
+    // Single datagrams are decoded too
+    assert_eq!(decode_index_integers(&encode_index_integer(2, 1032)), Some(vec![(2, 1032)]));
+    assert_eq!(decode_index_integers(&1032i32.to_le_bytes()), Some(vec![(0, 1032)]));
+
+    // The magic alone is a 4 bytes datagram, not an empty batch
+    assert_eq!(decode_index_integers(&BATCH_MAGIC), Some(vec![(0, i32::from_le_bytes(BATCH_MAGIC))]));
+    assert_eq!(decode_index_integers(&datagram[..datagram.len() - 1]), None);
+    let full: Vec<(i32, i32)> = (0..MAX_BATCH_INTEGERS as i32 + 1).map(|value| (0, value)).collect();
+    let mut oversized = encode_index_integers(&full[..MAX_BATCH_INTEGERS]);
+    assert_eq!(decode_index_integers(&oversized).unwrap().len(), MAX_BATCH_INTEGERS);
+    oversized.extend_from_slice(&encode_index_integer(0, 0));
+    assert_eq!(decode_index_integers(&oversized), None);
+}
+
+#[test]
+fn sends_datagrams_in_order() {
+    let receiver = receiver();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let datagrams: Vec<Vec<u8>> = (0..5).map(|value| encode_index_integer(0, 1000 + value).to_vec()).collect();
+    send_datagrams(&socket, &datagrams, receiver.local_addr().unwrap()).unwrap();
+    assert_eq!(receive(&receiver, 5), datagrams);
+}
+
+#[test]
+fn send_many_sends_one_datagram_per_integer() {
+    let receiver = receiver();
+    let metrics = Arc::new(IntegerMetrics::new());
+    let target = WowIntegerTarget::from_socket_addr(receiver.local_addr().unwrap(), 2).with_metrics(Arc::clone(&metrics));
+    let integers = [(2, 1032), (3, 1033), (2, 2032), (3, 2033)];
+    target.send_many(&integers).unwrap();
+    let received: Vec<(i32, i32)> = receive(&receiver, 4).iter().map(|datagram| decode_index_integer(datagram).unwrap()).collect();
+    assert_eq!(received, integers);
+
+    let exposition = metrics.to_prometheus();
+    let target = receiver.local_addr().unwrap().to_string();
+    assert!(exposition.contains(&format!("wowint_sent_integers_total{{target=\"{}\",index=\"2\"}} 2", target)));
+    assert!(exposition.contains(&format!("wowint_sent_bytes_total{{target=\"{}\",index=\"3\"}} 16", target)));
+    // One latency sample for the whole call
+    assert!(exposition.contains(&format!("wowint_send_latency_seconds_count{{target=\"{}\",index=\"2\"}} 1", target)));
+    assert!(exposition.contains(&format!("wowint_send_latency_seconds_count{{target=\"{}\",index=\"3\"}} 0", target)));
+    assert!(exposition.contains(&format!("wowint_send_errors_total{{target=\"{}\",index=\"3\"}} 0", target)));
+}
+
+#[test]
+fn send_many_packs_batch_datagrams() {
+    let receiver = receiver();
+    let target = WowIntegerTarget::from_socket_addr(receiver.local_addr().unwrap(), 0).with_batch_datagrams(true);
+    let integers: Vec<(i32, i32)> = (0..MAX_BATCH_INTEGERS as i32 + 2).map(|value| (1, 1000 + value)).collect();
+    target.send_many(&integers).unwrap();
+    let datagrams = receive(&receiver, 2);
+    assert_eq!(datagrams[0].len(), MAX_BATCH_DATAGRAM_SIZE);
+    let decoded: Vec<(i32, i32)> = datagrams.iter().flat_map(|datagram| decode_index_integers(datagram).unwrap()).collect();
+    assert_eq!(decoded, integers);
+}
@@ -0,0 +1,66 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use wowint::utility::mock::MockSender;
+use wowint::utility::relay::WowIntegerRelay;
+use wowint::utility::signed::SignedPacketValidator;
+use wowint::utility::{PacketDecoder, WowIntegerTarget};
+
+/// Runs a relay until the returned flag is set, the relay is returned by the thread.
+fn spawn_relay(decoder: PacketDecoder) -> (SocketAddr, Arc<AtomicBool>, thread::JoinHandle<WowIntegerRelay>) {
+    let mut relay = WowIntegerRelay::bind("127.0.0.1:0").unwrap();
+    relay.add_target(MockSender::new());
+    relay.set_decoder(decoder);
+    let address = relay.local_addr().unwrap();
+    let stop = Arc::new(AtomicBool::new(false));
+    let relay_stop = Arc::clone(&stop);
+    let relay_thread = thread::spawn(move || {
+        while !relay_stop.load(Ordering::Relaxed) {
+            relay.receive_once().unwrap();
+        }
+        relay
+    });
+    (address, stop, relay_thread)
+}
+
+#[test]
+fn plain_relay_answers_pings() {
+    let (address, stop, relay_thread) = spawn_relay(PacketDecoder::Plain);
+    let target = WowIntegerTarget::from_socket_addr(address, 0);
+    let sample = target.ping(Duration::from_secs(2)).unwrap();
+    assert!(sample.round_trip < Duration::from_secs(2));
+    // Same machine, same clock
+    assert!(sample.offset_micros.abs() < 100_000);
+
+    let estimate = target.measure_clock(5, Duration::from_secs(2)).unwrap();
+    assert_eq!(estimate.samples, 5);
+    assert!(estimate.min_round_trip <= estimate.mean_round_trip);
+    stop.store(true, Ordering::Relaxed);
+    assert_eq!(relay_thread.join().unwrap().malformed_datagrams(), 0);
+}
+
+#[test]
+fn signed_relay_does_not_answer_pings() {
+    let (address, stop, relay_thread) = spawn_relay(PacketDecoder::Signed(SignedPacketValidator::new(b"key")));
+    let target = WowIntegerTarget::from_socket_addr(address, 0);
+    assert!(target.ping(Duration::from_millis(200)).is_err());
+    stop.store(true, Ordering::Relaxed);
+    // The ping is not a valid signed packet
+    assert_eq!(relay_thread.join().unwrap().malformed_datagrams(), 1);
+}
+
+#[test]
+fn tracking_with_a_zero_interval_is_clamped() {
+    let (address, stop, relay_thread) = spawn_relay(PacketDecoder::Plain);
+    let tracker = WowIntegerTarget::from_socket_addr(address, 0).track_clock(Duration::ZERO).unwrap();
+    thread::sleep(Duration::from_millis(200));
+    let estimate = tracker.estimate().expect("a pong was received");
+    assert!(estimate.samples <= 8);
+    assert!(tracker.last_sample().is_some());
+    drop(tracker);
+    stop.store(true, Ordering::Relaxed);
+    relay_thread.join().unwrap();
+}
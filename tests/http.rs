@@ -0,0 +1,109 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use wowint::utility::http::{handle_request, HttpResponse, WowIntegerHttpServer};
+use wowint::utility::mock::MockSender;
+use wowint::utility::policy::{IntegerPolicy, PolicySender};
+use wowint::utility::watchdog::KeyScope;
+use wowint::utility::EnumWowKey;
+
+fn request(target: &MockSender, method: &str, path: &str, body: &str) -> HttpResponse {
+    handle_request(target, &EnumWowKey::new(), method, path, body)
+}
+
+#[test]
+fn sends_integers_to_a_player() {
+    let mock = MockSender::new();
+    let response = request(&mock, "POST", "/players/2/integer", "1032\n");
+    assert_eq!(response, HttpResponse { status: 200, body: "{\"ok\":true}".to_string() });
+    let sent: Vec<(KeyScope, i32)> = mock.sent().iter().map(|sent| (sent.scope, sent.value)).collect();
+    assert_eq!(sent, vec![(KeyScope::Index(2), 1032)]);
+
+    assert_eq!(request(&mock, "POST", "/players/x/integer", "1032").status, 400);
+    assert_eq!(request(&mock, "POST", "/players/2/integer", "abc").status, 400);
+    assert_eq!(mock.values(), vec![1032]);
+}
+
+#[test]
+fn taps_keys_by_name() {
+    let mock = MockSender::new();
+    assert_eq!(request(&mock, "POST", "/players/3/tap/Space?duration_ms=0", "").status, 200);
+    mock.assert_tapped("Space");
+    assert_eq!(mock.values(), vec![1032, 2032]);
+
+    assert_eq!(request(&mock, "POST", "/players/3/tap/Space?duration_ms=soon", "").status, 400);
+    let unknown = request(&mock, "POST", "/players/3/tap/No\u{1}\"pe", "");
+    assert_eq!(unknown.status, 404);
+    // Control characters and quotes of the key name are escaped in the JSON error
+    assert!(unknown.body.contains("No\\u0001\\\"pe"), "{}", unknown.body);
+    assert!(!unknown.body.contains('\u{1}'));
+}
+
+#[test]
+fn lists_keys() {
+    let response = request(&MockSender::new(), "GET", "/keys", "");
+    assert_eq!(response.status, 200);
+    assert!(response.body.starts_with('[') && response.body.ends_with(']'));
+    assert!(response.body.contains("{\"name\":\"Space\",\"decimal\":32,\"press\":1032,\"release\":2032}"));
+}
+
+#[test]
+fn answers_unknown_paths_and_methods() {
+    let mock = MockSender::new();
+    assert_eq!(request(&mock, "GET", "/players/2/integer", "").status, 405);
+    assert_eq!(request(&mock, "DELETE", "/keys", "").status, 405);
+    assert_eq!(request(&mock, "GET", "/nothing", "").status, 404);
+    assert!(mock.sent().is_empty());
+}
+
+#[test]
+fn policy_drops_are_forbidden_or_rate_limited() {
+    let mut policy = IntegerPolicy::new();
+    policy.allow_range(1000, 2999);
+    policy.reject(1040);
+    policy.rate_limit_per_index(1, 0.0);
+    let target = PolicySender::new(MockSender::new(), policy, 0);
+    let keys = EnumWowKey::new();
+    let status = |value: &str| handle_request(&target, &keys, "POST", "/players/2/integer", value).status;
+    assert_eq!(status("5000"), 403);
+    assert_eq!(status("1040"), 403);
+    assert_eq!(status("1032"), 200);
+    assert_eq!(status("1033"), 429);
+}
+
+fn raw_request(text: &str) -> String {
+    let server = WowIntegerHttpServer::bind("127.0.0.1:0", MockSender::new()).unwrap();
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(text.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn rejects_invalid_content_length() {
+    let response = raw_request("POST /players/2/integer HTTP/1.1\r\nContent-Length: four\r\n\r\n1032");
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{}", response);
+    let response = raw_request("POST /players/2/integer HTTP/1.1\r\nContent-Length: 4\r\n\r\n1032");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.ends_with("{\"ok\":true}"));
+}
+
+#[test]
+fn reports_truncated_requests_to_the_error_handler() {
+    let mut server = WowIntegerHttpServer::bind("127.0.0.1:0", MockSender::new()).unwrap();
+    let (errors, reported) = mpsc::channel();
+    let errors = Mutex::new(errors);
+    server.set_error_handler(move |error| errors.lock().unwrap().send(error.to_string()).unwrap());
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(b"POST /players/2/integer HTTP/1.1\r\nContent-Length: 10\r\n\r\n1032").unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+    assert!(reported.recv_timeout(Duration::from_secs(2)).is_ok());
+}
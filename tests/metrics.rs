@@ -0,0 +1,124 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::Duration;
+
+use wowint::utility::metrics::{IntegerMetrics, MetricsSender, WowIntegerMetricsServer};
+use wowint::utility::mock::MockSender;
+use wowint::utility::watchdog::KeyScope;
+use wowint::utility::{IntegerSender, XboxIntegerAction};
+
+const EXPOSITION: &str = r#"# HELP wowint_sent_integers_total Integers sent.
+# TYPE wowint_sent_integers_total counter
+wowint_sent_integers_total{target="t",index="2"} 3
+# HELP wowint_sent_bytes_total Bytes sent, when the transport knows them.
+# TYPE wowint_sent_bytes_total counter
+wowint_sent_bytes_total{target="t",index="2"} 16
+# HELP wowint_send_errors_total Integers that could not be sent.
+# TYPE wowint_send_errors_total counter
+wowint_send_errors_total{target="t",index="2"} 1
+# HELP wowint_key_presses_total Key and gamepad presses sent, by key.
+# TYPE wowint_key_presses_total counter
+wowint_key_presses_total{target="t",index="2",key="Space"} 1
+# HELP wowint_key_releases_total Key and gamepad releases sent.
+# TYPE wowint_key_releases_total counter
+wowint_key_releases_total{target="t",index="2"} 1
+# HELP wowint_held_keys Keys and gamepad buttons pressed and not released.
+# TYPE wowint_held_keys gauge
+wowint_held_keys{target="t",index="2"} 0
+# HELP wowint_send_latency_seconds Time spent sending an integer.
+# TYPE wowint_send_latency_seconds histogram
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.0001"} 0
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.00025"} 1
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.0005"} 1
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.001"} 1
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.0025"} 2
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.005"} 2
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.01"} 2
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.025"} 2
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.05"} 2
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.1"} 2
+wowint_send_latency_seconds_bucket{target="t",index="2",le="0.25"} 2
+wowint_send_latency_seconds_bucket{target="t",index="2",le="+Inf"} 3
+wowint_send_latency_seconds_sum{target="t",index="2"} 1.0022
+wowint_send_latency_seconds_count{target="t",index="2"} 3
+# HELP wowint_received_datagrams_total Datagrams received by a listener.
+# TYPE wowint_received_datagrams_total counter
+wowint_received_datagrams_total{listener="0.0.0.0:7073"} 1
+# HELP wowint_received_bytes_total Bytes received by a listener.
+# TYPE wowint_received_bytes_total counter
+wowint_received_bytes_total{listener="0.0.0.0:7073"} 8
+# HELP wowint_received_integers_total Integers received and accepted.
+# TYPE wowint_received_integers_total counter
+wowint_received_integers_total{listener="0.0.0.0:7073",index="2"} 1
+# HELP wowint_dropped_integers_total Integers received and dropped by a policy.
+# TYPE wowint_dropped_integers_total counter
+wowint_dropped_integers_total{listener="0.0.0.0:7073",index="2"} 0
+"#;
+
+#[test]
+fn exports_the_prometheus_text_format() {
+    let metrics = IntegerMetrics::new();
+    metrics.record_sent("t", KeyScope::Index(2), 1032, 8, Duration::from_micros(200));
+    metrics.record_sent("t", KeyScope::Index(2), 2032, 8, Duration::from_millis(2));
+    // Slower than the last bucket, only counted by +Inf
+    metrics.record_sent("t", KeyScope::Index(2), 42, 0, Duration::from_secs(1));
+    metrics.record_send_error("t", KeyScope::Index(2));
+    metrics.record_datagram("0.0.0.0:7073", 8);
+    metrics.record_received("0.0.0.0:7073", 2);
+    assert_eq!(metrics.to_prometheus(), EXPOSITION);
+}
+
+#[test]
+fn tracks_held_keys() {
+    let metrics = Arc::new(IntegerMetrics::new());
+    let sender = MetricsSender::new(MockSender::new(), Arc::clone(&metrics), "mock", 1);
+    for value in [1032, 1300, 1301, 1040] {
+        sender.send_integer_to_target(value).unwrap();
+    }
+    assert_eq!(metrics.held_keys("mock", KeyScope::Index(1)), vec![1032, 1040, 1300, 1301]);
+    sender.send_integer_to_target(2040).unwrap();
+    assert_eq!(metrics.held_keys("mock", KeyScope::Index(1)), vec![1032, 1300, 1301]);
+    // Releases the gamepad buttons only
+    sender.send_integer_to_target(XboxIntegerAction::RELEASE_ALL).unwrap();
+    assert_eq!(metrics.held_keys("mock", KeyScope::Index(1)), vec![1032]);
+    assert!(metrics.held_keys("mock", KeyScope::Index(2)).is_empty());
+}
+
+#[test]
+fn counts_integers_sent_to_all_apart() {
+    let metrics = Arc::new(IntegerMetrics::new());
+    let sender = MetricsSender::new(MockSender::new(), Arc::clone(&metrics), "mock", 1);
+    sender.send_integer_to_target(1032).unwrap();
+    sender.send_integer_to_all(1040).unwrap();
+    assert_eq!(metrics.held_keys("mock", KeyScope::Index(1)), vec![1032]);
+    assert_eq!(metrics.held_keys("mock", KeyScope::All), vec![1040]);
+    let exposition = metrics.to_prometheus();
+    assert!(exposition.contains("wowint_sent_integers_total{target=\"mock\",index=\"1\"} 1\n"), "{}", exposition);
+    assert!(exposition.contains("wowint_sent_integers_total{target=\"mock\",index=\"all\"} 1\n"), "{}", exposition);
+}
+
+fn scrape(metrics: &Arc<IntegerMetrics>, request_line: &str) -> String {
+    let server = WowIntegerMetricsServer::bind("127.0.0.1:0", Arc::clone(metrics)).unwrap();
+    let address = server.local_addr().unwrap();
+    server.spawn();
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "{}\r\nHost: localhost\r\n\r\n", request_line).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn serves_metrics_over_http() {
+    let metrics = Arc::new(IntegerMetrics::new());
+    metrics.record_received("0.0.0.0:7073", 2);
+    let response = scrape(&metrics, "GET /metrics HTTP/1.1");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.contains("Content-Type: text/plain; version=0.0.4\r\n"));
+    assert!(response.ends_with(&format!("\r\n\r\n{}", metrics.to_prometheus())));
+
+    assert!(scrape(&metrics, "GET /metrics?x=1 HTTP/1.1").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(scrape(&metrics, "POST /metrics HTTP/1.1").starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    assert!(scrape(&metrics, "GET / HTTP/1.1").starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use wowint::utility::midi::{
+    play_midi_file, play_midi_stream, MidiFile, MidiMapping, MidiMessage, MidiStreamParser, MidiTranslator,
+};
+use wowint::utility::mock::MockSender;
+
+/// Format 0 file with one track, 96 ticks per quarter note.
+fn midi_file_bytes(track: &[u8]) -> Vec<u8> {
+    let mut bytes = b"MThd\0\0\0\x06\0\0\0\x01\0\x60MTrk".to_vec();
+    bytes.extend((track.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(track);
+    bytes
+}
+
+/// One second per quarter note, so 48 ticks are 500 ms.
+const TEMPO: [u8; 7] = [0, 0xFF, 0x51, 3, 0x0F, 0x42, 0x40];
+const END_OF_TRACK: [u8; 4] = [0, 0xFF, 0x2F, 0];
+
+#[test]
+fn parses_raw_midi_bytes() {
+    let mut parser = MidiStreamParser::new();
+    // Running status, a clock in the middle and a SysEx are handled
+    let messages = parser.push_bytes(&[0x90, 60, 100, 62, 0, 0xF8, 0xB0, 1, 127, 0xF0, 1, 2, 3, 0xF7, 0x80, 60, 0]);
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[1], MidiMessage::NoteOff { channel: 0, note: 62, velocity: 0 });
+
+    let mapping = MidiMapping::parse("note 60 Space\nnote 62 1104\ncc 1 left_stick_horizontal\n").unwrap();
+    let mut translator = MidiTranslator::new(mapping);
+    let outputs: Vec<_> = messages.iter().flat_map(|message| translator.translate(message)).collect();
+    assert_eq!(outputs, vec![(None, 1032), (None, 2104), (None, 1350), (None, 2032)]);
+}
+
+#[test]
+fn rejects_unknown_keys_in_mapping() {
+    assert!(MidiMapping::parse("note 60 Nope").is_err());
+}
+
+#[test]
+fn parses_and_translates_midi_file() {
+    let mut track = TEMPO.to_vec();
+    track.extend([0, 0x90, 60, 100, 48, 60, 0]);
+    track.extend(END_OF_TRACK);
+    let file = MidiFile::parse(&midi_file_bytes(&track)).unwrap();
+    assert_eq!(file.messages.len(), 2);
+    assert_eq!(file.messages[1].time, Duration::from_millis(500));
+
+    let mut translator = MidiTranslator::new(MidiMapping::parse("note 60 Space\nchannel_as_index").unwrap());
+    assert_eq!(
+        translator.translate_file(&file),
+        vec![(Duration::ZERO, (Some(1), 1032)), (Duration::from_millis(500), (Some(1), 2032))]
+    );
+    assert!(MidiFile::parse(b"MThd").is_err());
+}
+
+#[test]
+fn file_playback_releases_held_notes() {
+    let mut track = TEMPO.to_vec();
+    // Note 60 is released, note 62 is left on
+    track.extend([0, 0x90, 60, 100, 0, 0x90, 62, 100, 4, 0x80, 60, 0]);
+    track.extend(END_OF_TRACK);
+    let file = MidiFile::parse(&midi_file_bytes(&track)).unwrap();
+    let mut translator = MidiTranslator::new(MidiMapping::parse("note 60 Space\nnote 62 1104").unwrap());
+    let mock = MockSender::new();
+    play_midi_file(&file, &mut translator, &mock).unwrap();
+    assert_eq!(mock.values(), vec![1032, 1104, 2032, 2104]);
+    mock.assert_no_stuck_keys();
+}
+
+#[test]
+fn stream_playback_releases_held_notes() {
+    let mut translator = MidiTranslator::new(MidiMapping::parse("note 60 Space\nnote 62 1104").unwrap());
+    let mock = MockSender::new();
+    let bytes: &[u8] = &[0x90, 60, 100, 62, 100, 0x80, 62, 0];
+    play_midi_stream(bytes, &mut translator, &mock).unwrap();
+    assert_eq!(mock.values(), vec![1032, 1104, 2104, 2032]);
+    mock.assert_no_stuck_keys();
+}
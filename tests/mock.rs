@@ -0,0 +1,94 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use wowint::utility::mock::MockSender;
+use wowint::utility::watchdog::{KeyScope, StuckKeyWatchdog};
+use wowint::utility::IntegerSender;
+
+#[test]
+fn records_the_own_index_as_an_index() {
+    let mock = MockSender::new().with_index(3);
+    mock.send_integer_to_target(1032).unwrap();
+    mock.send_integer_to_target_at_index(3, 2032).unwrap();
+    mock.send_integer_to_all(1390).unwrap();
+    let sent: Vec<(KeyScope, i32)> = mock.sent().iter().map(|sent| (sent.scope, sent.value)).collect();
+    assert_eq!(sent, vec![(KeyScope::Index(3), 1032), (KeyScope::Index(3), 2032), (KeyScope::All, 1390)]);
+    mock.assert_no_stuck_keys();
+}
+
+#[test]
+fn finds_stuck_keys_per_player() {
+    let mock = MockSender::new();
+    mock.send_integer_to_target_at_index(1, 1032).unwrap();
+    // Released on another player, still held on player 1
+    mock.send_integer_to_target_at_index(2, 2032).unwrap();
+    mock.send_integer_to_target_at_index(1, 1300).unwrap();
+    mock.send_integer_to_target_at_index(2, 1301).unwrap();
+    assert_eq!(
+        mock.stuck_keys(),
+        vec![(KeyScope::Index(1), 1032), (KeyScope::Index(1), 1300), (KeyScope::Index(2), 1301)]
+    );
+    assert!(catch_unwind(AssertUnwindSafe(|| mock.assert_no_stuck_keys())).is_err());
+
+    // RELEASE_ALL releases the gamepad of its player only, not the keyboard
+    mock.send_integer_to_target_at_index(1, 1390).unwrap();
+    assert_eq!(mock.stuck_keys(), vec![(KeyScope::Index(1), 1032), (KeyScope::Index(2), 1301)]);
+    mock.send_integer_to_target_at_index(1, 2032).unwrap();
+    mock.send_integer_to_target_at_index(2, 2301).unwrap();
+    mock.assert_no_stuck_keys();
+}
+
+#[test]
+fn asserts_taps_and_sent_values() {
+    let mock = MockSender::new();
+    mock.send_integer_to_target_at_index(1, 1032).unwrap();
+    mock.send_integer_to_target_at_index(2, 2032).unwrap();
+    // The release went to another player
+    assert!(catch_unwind(AssertUnwindSafe(|| mock.assert_tapped("Space"))).is_err());
+    mock.send_integer_to_target_at_index(1, 2032).unwrap();
+    mock.assert_tapped("Space");
+    assert!(catch_unwind(AssertUnwindSafe(|| mock.assert_tapped("Enter"))).is_err());
+
+    mock.assert_sent(2032);
+    assert!(catch_unwind(AssertUnwindSafe(|| mock.assert_sent(1040))).is_err());
+    assert_eq!(mock.values(), vec![1032, 2032, 2032]);
+    mock.clear();
+    assert!(mock.sent().is_empty());
+}
+
+#[test]
+fn simulates_failures() {
+    let mock = MockSender::new();
+    mock.fail_next(2);
+    assert!(mock.send_integer_to_target(1032).is_err());
+    assert!(mock.send_integer_to_target(1032).is_err());
+    mock.send_integer_to_target(1032).unwrap();
+    mock.set_always_fail(true);
+    assert!(mock.send_integer_to_target(2032).is_err());
+    assert_eq!(mock.failures(), 3);
+    assert_eq!(mock.values(), vec![1032]);
+}
+
+#[test]
+fn watchdog_and_mock_agree_on_the_own_index() {
+    let watchdog = StuckKeyWatchdog::new(MockSender::new().with_index(4), Duration::from_secs(60), 4);
+    watchdog.send_integer_to_target(1032).unwrap();
+    assert_eq!(watchdog.held_keys(), vec![(KeyScope::Index(4), 1032)]);
+    assert_eq!(watchdog.sender().stuck_keys(), watchdog.held_keys());
+    watchdog.release_all().unwrap();
+    watchdog.sender().assert_no_stuck_keys();
+}
+
+#[test]
+fn watchdog_thread_passes_release_errors_to_the_handler() {
+    let watchdog = Arc::new(StuckKeyWatchdog::new(MockSender::new(), Duration::from_millis(10), 0));
+    watchdog.send_integer_to_target(1032).unwrap();
+    watchdog.sender().set_always_fail(true);
+    let (errors, received) = mpsc::channel();
+    StuckKeyWatchdog::spawn_release_thread(&watchdog, Duration::from_millis(10), move |error| {
+        let _ = errors.send(error.to_string());
+    });
+    let error = received.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(error, "Simulated send error for 2032");
+}
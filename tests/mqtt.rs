@@ -0,0 +1,137 @@
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use wowint::utility::error::WowIntError;
+use wowint::utility::mock::MockSender;
+use wowint::utility::mqtt::{
+    forward_mqtt_integers, parse_integer_payload, parse_integer_topic, MqttClient, MqttPublishingSender,
+    MqttTopicTarget, DEFAULT_TOPIC_PREFIX,
+};
+use wowint::utility::watchdog::KeyScope;
+
+/// Fixed header byte and content of an MQTT packet.
+type Packet = (u8, Vec<u8>);
+
+fn read_packet(stream: &mut TcpStream) -> Packet {
+    let mut header = [0u8; 1];
+    stream.read_exact(&mut header).unwrap();
+    let mut length = 0usize;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).unwrap();
+        length |= ((byte[0] & 0x7F) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    let mut content = vec![0u8; length];
+    stream.read_exact(&mut content).unwrap();
+    (header[0], content)
+}
+
+fn publish_packet(topic: &str, packet_id: Option<u16>, payload: &[u8]) -> Vec<u8> {
+    let mut content = (topic.len() as u16).to_be_bytes().to_vec();
+    content.extend_from_slice(topic.as_bytes());
+    let header = match packet_id {
+        Some(id) => {
+            content.extend_from_slice(&id.to_be_bytes());
+            0x32
+        }
+        None => 0x30,
+    };
+    content.extend_from_slice(payload);
+    let mut packet = vec![header, content.len() as u8];
+    packet.extend(content);
+    packet
+}
+
+/// Topic and payload of a PUBLISH sent by the client at QoS 0.
+fn published(content: &[u8]) -> (String, String) {
+    let topic_length = u16::from_be_bytes([content[0], content[1]]) as usize;
+    let topic = String::from_utf8(content[2..2 + topic_length].to_vec()).unwrap();
+    let payload = String::from_utf8(content[2 + topic_length..].to_vec()).unwrap();
+    (topic, payload)
+}
+
+/// Local broker for one client: accepts the connection and the subscription,
+/// publishes the given packets, then returns what the client sent until it saw `expected` publishes and acks.
+fn start_broker(publishes: Vec<Vec<u8>>, expected: usize) -> (String, thread::JoinHandle<Vec<Packet>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let broker = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(read_packet(&mut stream).0, 0x10);
+        stream.write_all(&[0x20, 2, 0, 0]).unwrap();
+        let (header, subscribe) = read_packet(&mut stream);
+        assert_eq!(header, 0x82);
+        stream.write_all(&[0x90, 3, subscribe[0], subscribe[1], 0]).unwrap();
+        for publish in publishes {
+            stream.write_all(&publish).unwrap();
+        }
+        let mut received = vec![(header, subscribe)];
+        while received.len() < expected + 1 {
+            received.push(read_packet(&mut stream));
+        }
+        // Closing the connection stops the client
+        received
+    });
+    (address, broker)
+}
+
+#[test]
+fn forwards_and_republishes_integers() {
+    let publishes = vec![
+        publish_packet("wowint/2/integer", None, b"1032"),
+        // Invalid payload, skipped
+        publish_packet("wowint/2/integer", None, b"abc"),
+        publish_packet("wowint/5/integer", Some(7), &2032i32.to_le_bytes()),
+        publish_packet("wowint/all/integer", None, b"1390"),
+    ];
+    // Three republished integers and the acknowledgement of the QoS 1 message
+    let (address, broker) = start_broker(publishes, 4);
+
+    let mut client = MqttClient::connect(&address, "wowint-test", Duration::from_secs(30)).unwrap();
+    let target = MqttPublishingSender::new(MockSender::new(), client.publisher(), "echo", 0);
+    let ignored = RefCell::new(Vec::new());
+    let on_ignored = |error: &WowIntError| ignored.borrow_mut().push(error.to_string());
+    assert!(forward_mqtt_integers(&mut client, DEFAULT_TOPIC_PREFIX, &target, on_ignored).is_err());
+    assert_eq!(ignored.into_inner(), vec!["Invalid MQTT message: wowint/2/integer [97, 98, 99]".to_string()]);
+
+    let received = broker.join().unwrap();
+    let subscribe = &received[0].1;
+    assert_eq!(&subscribe[4..subscribe.len() - 1], b"wowint/+/integer");
+    assert!(received.contains(&(0x40, vec![0, 7])));
+    let republished: Vec<(String, String)> = received
+        .iter()
+        .filter(|(header, _)| *header == 0x30)
+        .map(|(_, content)| published(content))
+        .collect();
+    assert_eq!(
+        republished,
+        vec![
+            ("echo/2/integer".to_string(), "1032".to_string()),
+            ("echo/5/integer".to_string(), "2032".to_string()),
+            ("echo/all/integer".to_string(), "1390".to_string()),
+        ]
+    );
+
+    let sent: Vec<(KeyScope, i32)> = target.sender().sent().iter().map(|sent| (sent.scope, sent.value)).collect();
+    assert_eq!(sent, vec![(KeyScope::Index(2), 1032), (KeyScope::Index(5), 2032), (KeyScope::All, 1390)]);
+}
+
+#[test]
+fn parses_topics_and_payloads() {
+    assert_eq!(parse_integer_topic("wowint", "wowint/3/integer"), Some(MqttTopicTarget::Index(3)));
+    assert_eq!(parse_integer_topic("wowint", "wowint/all/integer"), Some(MqttTopicTarget::All));
+    assert_eq!(parse_integer_topic("wowint", "wowint/x/integer"), None);
+    assert_eq!(parse_integer_topic("wowint", "other/3/integer"), None);
+    assert_eq!(parse_integer_payload(b" 1032\n"), Some(1032));
+    assert_eq!(parse_integer_payload(&2032i32.to_le_bytes()), Some(2032));
+    assert_eq!(parse_integer_payload(b"abc"), None);
+}
@@ -0,0 +1,44 @@
+use std::net::UdpSocket;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use wowint::utility::mock::MockSender;
+use wowint::utility::osc::{encode_osc_message, parse_osc_packet, OscArgument, OscMapping, OscMessage, WowIntegerOscListener};
+use wowint::utility::xbox::XboxAxisTracker;
+use wowint::utility::EnumWowKey;
+
+fn message(address: &str, arguments: Vec<OscArgument>) -> OscMessage {
+    let message = OscMessage { address: address.to_string(), arguments };
+    parse_osc_packet(&encode_osc_message(&message)).unwrap().remove(0)
+}
+
+#[test]
+fn own_index_and_player_0_have_their_own_sticks() {
+    let mapping = OscMapping::parse("/stick left_stick\n/xbox/{index}/leftstick left_stick").unwrap();
+    let keys = EnumWowKey::new();
+    let mut axes = XboxAxisTracker::new();
+    let mut translate = |address: &str, x: f32| -> Vec<(Option<i32>, i32)> {
+        let arguments = vec![OscArgument::Float(x), OscArgument::Float(0.0)];
+        let outputs = mapping.translate(&message(address, arguments), &keys, &mut axes).unwrap();
+        outputs.iter().map(|output| (output.index, output.value)).collect()
+    };
+    assert_eq!(translate("/stick", 1.0), vec![(None, 1350)]);
+    // Player 0 did not move its stick yet, nothing to release
+    assert_eq!(translate("/xbox/0/leftstick", 1.0), vec![(Some(0), 1350)]);
+    assert_eq!(translate("/stick", 0.0), vec![(None, 2350)]);
+    assert_eq!(translate("/xbox/0/leftstick", 0.0), vec![(Some(0), 2350)]);
+}
+
+#[test]
+fn run_reports_invalid_packets_to_the_error_handler() {
+    let mut listener = WowIntegerOscListener::bind("127.0.0.1:0", MockSender::new()).unwrap();
+    let (errors, reported) = mpsc::channel();
+    let errors = Mutex::new(errors);
+    listener.set_error_handler(move |error| errors.lock().unwrap().send(error.to_string()).unwrap());
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || listener.run());
+    UdpSocket::bind("127.0.0.1:0").unwrap().send_to(b"/a", address).unwrap();
+    let error = reported.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert!(error.starts_with("Invalid OSC packet"), "{}", error);
+}
@@ -0,0 +1,28 @@
+use wowint::utility::mock::MockSender;
+use wowint::utility::panic_stop::{emergency_release_all, release_integers};
+use wowint::utility::watchdog::KeyScope;
+use wowint::utility::XboxIntegerAction;
+
+#[test]
+fn releases_every_key_of_each_player() {
+    let mock = MockSender::new();
+    emergency_release_all(&mock, &[1, 2]).unwrap();
+    let mut expected = release_integers();
+    expected.extend([XboxIntegerAction::RELEASE_ALL, XboxIntegerAction::CLEAR_TIMED_COMMAND]);
+    let sent = mock.sent();
+    assert_eq!(sent.len(), expected.len() * 2);
+    for (player, index) in [1, 2].into_iter().enumerate() {
+        let player_sent = &sent[player * expected.len()..(player + 1) * expected.len()];
+        assert!(player_sent.iter().all(|sent| sent.scope == KeyScope::Index(index)));
+        assert_eq!(player_sent.iter().map(|sent| sent.value).collect::<Vec<_>>(), expected);
+    }
+}
+
+#[test]
+fn a_failing_player_does_not_stop_the_others() {
+    let mock = MockSender::new();
+    mock.fail_next(1);
+    assert!(emergency_release_all(&mock, &[1, 2]).is_err());
+    let last = mock.sent().last().map(|sent| (sent.scope, sent.value));
+    assert_eq!(last, Some((KeyScope::Index(2), XboxIntegerAction::CLEAR_TIMED_COMMAND)));
+}
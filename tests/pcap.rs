@@ -0,0 +1,108 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use wowint::utility::pcap::{build_udp_packet, CapturedInteger, PcapReader, PcapWriter};
+use wowint::utility::{encode_index_integer, EnumWowKey};
+
+fn address(text: &str) -> SocketAddr {
+    text.parse().unwrap()
+}
+
+fn read_all(bytes: &[u8]) -> Vec<CapturedInteger> {
+    PcapReader::new(bytes).unwrap().collect::<Result<_, _>>().unwrap()
+}
+
+#[test]
+fn reads_back_written_ipv4_and_ipv6_integers() {
+    let v4 = (address("192.168.1.10:50000"), address("192.168.1.37:7073"));
+    let v6 = (address("[fe80::1]:50001"), address("[2001:db8::37]:7073"));
+    let mut bytes = Vec::new();
+    {
+        let mut writer = PcapWriter::new(&mut bytes).unwrap();
+        writer.write_integer(Duration::from_micros(1_700_000_000_000_001), v4.0, v4.1, 2, 1032).unwrap();
+        writer.write_integer(Duration::from_micros(1_700_000_000_250_000), v6.0, v6.1, -1, 2032).unwrap();
+        // Not an integer datagram, skipped
+        writer.write_datagram(Duration::from_secs(1_700_000_001), v4.0, v4.1, b"hello").unwrap();
+        writer.write_integer(Duration::from_secs(1_700_000_002), v4.0, address("192.168.1.38:9000"), 3, 1390).unwrap();
+        writer.flush().unwrap();
+    }
+
+    let captured = read_all(&bytes);
+    assert_eq!(captured.len(), 3);
+    assert_eq!(
+        captured[0],
+        CapturedInteger {
+            time: Duration::from_micros(1_700_000_000_000_001),
+            source: v4.0,
+            destination: v4.1,
+            index: 2,
+            value: 1032,
+        }
+    );
+    assert_eq!((captured[1].source, captured[1].destination), v6);
+    assert_eq!((captured[1].index, captured[1].value), (-1, 2032));
+    assert_eq!(captured[0].key_annotation(&EnumWowKey::new()).as_deref(), Some("press Space"));
+    assert_eq!(captured[1].key_annotation(&EnumWowKey::new()).as_deref(), Some("release Space"));
+
+    let filtered: Vec<CapturedInteger> = PcapReader::new(&bytes[..]).unwrap().with_port(9000).collect::<Result<_, _>>().unwrap();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].value, 1390);
+}
+
+/// Big endian capture with nanoseconds and the given link type.
+fn capture(link_type: u32, frames: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = 0xA1B2_3C4Du32.to_be_bytes().to_vec();
+    bytes.extend(2u16.to_be_bytes());
+    bytes.extend(4u16.to_be_bytes());
+    bytes.extend([0; 8]);
+    bytes.extend(65535u32.to_be_bytes());
+    bytes.extend(link_type.to_be_bytes());
+    for (position, frame) in frames.iter().enumerate() {
+        bytes.extend(100u32.to_be_bytes());
+        bytes.extend((position as u32 * 500).to_be_bytes());
+        bytes.extend((frame.len() as u32).to_be_bytes());
+        bytes.extend((frame.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(frame);
+    }
+    bytes
+}
+
+#[test]
+fn reads_ethernet_captures_with_vlan_tags() {
+    let (source, destination) = (address("10.0.0.1:50000"), address("[::1]:7073"));
+    let ipv4 = build_udp_packet(source, address("10.0.0.2:7073"), &encode_index_integer(1, 1040)).unwrap();
+    let ipv6 = build_udp_packet(address("[::2]:50000"), destination, &1041i32.to_le_bytes()).unwrap();
+    let mut plain = vec![0; 12];
+    plain.extend([0x08, 0x00]);
+    plain.extend(&ipv4);
+    let mut tagged = vec![0; 12];
+    tagged.extend([0x81, 0x00, 0x00, 0x05, 0x86, 0xDD]);
+    tagged.extend(&ipv6);
+    let mut arp = vec![0; 12];
+    arp.extend([0x08, 0x06, 0, 0]);
+
+    let captured = read_all(&capture(1, &[plain, tagged, arp]));
+    let values: Vec<(i32, i32)> = captured.iter().map(|integer| (integer.index, integer.value)).collect();
+    assert_eq!(values, vec![(1, 1040), (0, 1041)]);
+    assert_eq!(captured[0].time, Duration::from_secs(100));
+    assert_eq!(captured[1].time, Duration::from_secs(100) + Duration::from_nanos(500));
+    assert_eq!(captured[1].destination, destination);
+}
+
+#[test]
+fn reads_linux_cooked_and_loopback_captures() {
+    let packet = build_udp_packet(address("127.0.0.1:50000"), address("127.0.0.1:7073"), &encode_index_integer(4, 1050)).unwrap();
+    let mut cooked = vec![0; 16];
+    cooked.extend(&packet);
+    assert_eq!(read_all(&capture(113, &[cooked]))[0].value, 1050);
+    let mut loopback = 2u32.to_le_bytes().to_vec();
+    loopback.extend(&packet);
+    assert_eq!(read_all(&capture(0, &[loopback]))[0].index, 4);
+}
+
+#[test]
+fn rejects_unsupported_captures() {
+    assert!(PcapReader::new(&capture(105, &[])[..]).is_err());
+    assert!(PcapReader::new(&b"\x0a\x0d\x0d\x0a"[..]).is_err());
+    assert!(build_udp_packet(address("10.0.0.1:1"), address("[::1]:2"), &[]).is_err());
+}
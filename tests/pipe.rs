@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use wowint::utility::error::WowIntError;
+use wowint::utility::mock::MockSender;
+use wowint::utility::pipe::{parse_duration, parse_pipe_command, run_pipe, PipeCommand};
+use wowint::utility::watchdog::KeyScope;
+use wowint::utility::EnumWowKey;
+
+fn parse(line: &str) -> Result<Option<PipeCommand>, WowIntError> {
+    parse_pipe_command(line, &EnumWowKey::new())
+}
+
+#[test]
+fn parses_integers() {
+    assert_eq!(parse("42").unwrap(), Some(PipeCommand::Integer(42)));
+    assert_eq!(parse("  2 1032 ").unwrap(), Some(PipeCommand::IntegerAt { index: 2, value: 1032 }));
+    assert_eq!(parse("-1 2032").unwrap(), Some(PipeCommand::IntegerAt { index: -1, value: 2032 }));
+}
+
+#[test]
+fn parses_taps() {
+    let tap = |index, duration| Some(PipeCommand::Tap { index, press: 1032, duration });
+    assert_eq!(parse("tap Space").unwrap(), tap(None, Duration::from_millis(100)));
+    assert_eq!(parse("tap Space 200ms").unwrap(), tap(None, Duration::from_millis(200)));
+    assert_eq!(parse("2 tap Space 1.5s").unwrap(), tap(Some(2), Duration::from_millis(1500)));
+    assert_eq!(parse("2 tap Space 300").unwrap(), tap(Some(2), Duration::from_millis(300)));
+}
+
+#[test]
+fn skips_empty_lines_and_comments() {
+    assert_eq!(parse("").unwrap(), None);
+    assert_eq!(parse("   ").unwrap(), None);
+    assert_eq!(parse("# tap Space").unwrap(), None);
+}
+
+#[test]
+fn rejects_malformed_lines() {
+    assert!(matches!(parse("abc"), Err(WowIntError::Parse { .. })));
+    assert!(matches!(parse("2 abc"), Err(WowIntError::Parse { .. })));
+    assert!(matches!(parse("1 2 3"), Err(WowIntError::Parse { .. })));
+    assert!(matches!(parse("tap"), Err(WowIntError::Parse { .. })));
+    assert!(matches!(parse("tap Space soon"), Err(WowIntError::Parse { .. })));
+    assert!(matches!(parse("x tap Space"), Err(WowIntError::Parse { .. })));
+    assert!(matches!(parse("tap Nope"), Err(WowIntError::UnknownKey { .. })));
+}
+
+#[test]
+fn caps_durations() {
+    assert_eq!(parse_duration("1h"), None);
+    assert_eq!(parse_duration("-1s"), None);
+    assert_eq!(parse_duration("60s"), Some(Duration::from_secs(10)));
+}
+
+#[test]
+fn runs_every_valid_line() {
+    let mock = MockSender::new().with_index(5);
+    let input: &[u8] = b"1032\n# comment\nnot a command\n2 tap Space 0\n2032\n";
+    let mut errors = Vec::new();
+    run_pipe(input, &mock, |line, error| errors.push((line, error.to_string()))).unwrap();
+    assert_eq!(errors, vec![(3, "Invalid command: not a command".to_string())]);
+    let sent: Vec<(KeyScope, i32)> = mock.sent().iter().map(|sent| (sent.scope, sent.value)).collect();
+    assert_eq!(
+        sent,
+        vec![(KeyScope::Index(5), 1032), (KeyScope::Index(2), 1032), (KeyScope::Index(2), 2032), (KeyScope::Index(5), 2032)]
+    );
+}
@@ -0,0 +1,39 @@
+use std::net::UdpSocket;
+use std::sync::{Arc, Mutex};
+
+use wowint::utility::encode_index_integer;
+use wowint::utility::mock::MockSender;
+use wowint::utility::relay::WowIntegerRelay;
+
+#[test]
+fn forward_errors_go_to_the_error_handler() {
+    let mut relay = WowIntegerRelay::bind("127.0.0.1:0").unwrap();
+    let mock = MockSender::new();
+    mock.fail_next(1);
+    relay.add_target(mock);
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let handler_errors = Arc::clone(&errors);
+    relay.set_error_handler(move |error| handler_errors.lock().unwrap().push(error.to_string()));
+
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let address = relay.local_addr().unwrap();
+    client.send_to(&encode_index_integer(2, 1032), address).unwrap();
+    assert!(!relay.receive_once().unwrap());
+    client.send_to(&encode_index_integer(2, 2032), address).unwrap();
+    assert!(relay.receive_once().unwrap());
+    assert_eq!(*errors.lock().unwrap(), vec!["Simulated send error for 1032".to_string()]);
+}
+
+#[test]
+fn logs_every_received_integer() {
+    let mut relay = WowIntegerRelay::bind("127.0.0.1:0").unwrap();
+    relay.add_target(MockSender::new());
+    let logged = Arc::new(Mutex::new(Vec::new()));
+    let log_logged = Arc::clone(&logged);
+    relay.set_packet_log(move |source, index, value| log_logged.lock().unwrap().push((source, index, value)));
+
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client.send_to(&encode_index_integer(2, 1032), relay.local_addr().unwrap()).unwrap();
+    relay.receive_once().unwrap();
+    assert_eq!(*logged.lock().unwrap(), vec![(client.local_addr().unwrap(), 2, 1032)]);
+}
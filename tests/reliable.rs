@@ -0,0 +1,107 @@
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use wowint::utility::mock::MockSender;
+use wowint::utility::relay::WowIntegerRelay;
+use wowint::utility::reliable::{encode_reliable, ReliableOptions, ReliableReceiver, ReliableSender};
+use wowint::utility::{encode_index_integer, IntegerSender, WowIntegerTarget};
+
+const LOSS: f64 = 0.3;
+
+#[test]
+fn delivers_every_release_after_its_press_despite_loss() {
+    let mock = Arc::new(MockSender::new());
+    let mut relay = WowIntegerRelay::bind("127.0.0.1:0").unwrap();
+    relay.add_target(Arc::clone(&mock));
+    relay.set_reliable_receiver(ReliableReceiver::new().with_simulated_loss(LOSS));
+    let address = relay.local_addr().unwrap();
+    let stop = Arc::new(AtomicBool::new(false));
+    let relay_stop = Arc::clone(&stop);
+    let relay_thread = thread::spawn(move || {
+        while !relay_stop.load(Ordering::Relaxed) {
+            relay.receive_once().unwrap();
+        }
+    });
+
+    let options = ReliableOptions::new()
+        .with_retransmit_timeout(Duration::from_millis(20))
+        .with_max_attempts(30)
+        .with_simulated_loss(LOSS);
+    let sender = ReliableSender::with_options(WowIntegerTarget::from_socket_addr(address, 2), options).unwrap();
+    let mut expected = Vec::new();
+    for tap in 0..100 {
+        let press = 1000 + tap % 20;
+        expected.extend([press, press + 1000]);
+        sender.send_integer_to_target(press).unwrap();
+        sender.send_integer_to_target(press + 1000).unwrap();
+    }
+    assert!(sender.wait_for_delivery(Duration::from_secs(20)));
+    assert!(sender.take_failures().is_empty());
+    stop.store(true, Ordering::Relaxed);
+    relay_thread.join().unwrap();
+
+    let values = mock.values();
+    assert_eq!(values, expected);
+    for (position, &value) in values.iter().enumerate() {
+        if value >= 2000 {
+            assert_eq!(values[position - 1], value - 1000, "release {} before its press", value);
+        }
+    }
+    mock.assert_no_stuck_keys();
+}
+
+#[test]
+fn holds_packets_until_the_missing_one_arrives() {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let source = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let mut receiver = ReliableReceiver::new();
+    let packet = |value| encode_index_integer(0, value).to_vec();
+    let datagram = |sequence, value| encode_reliable(7, sequence, &packet(value));
+
+    assert!(receiver.receive(&socket, source, &datagram(1, 2032)).unwrap().is_empty());
+    assert_eq!(receiver.receive(&socket, source, &datagram(0, 1032)).unwrap(), vec![packet(1032), packet(2032)]);
+    // Retransmitted packets are acknowledged again but delivered once
+    assert!(receiver.receive(&socket, source, &datagram(0, 1032)).unwrap().is_empty());
+    assert!(receiver.receive(&socket, source, &datagram(1, 2032)).unwrap().is_empty());
+    assert_eq!(receiver.receive(&socket, source, &datagram(2, 1033)).unwrap(), vec![packet(1033)]);
+}
+
+#[test]
+fn skips_a_gap_after_the_timeout() {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let source = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let mut receiver = ReliableReceiver::new().with_gap_timeout(Duration::from_millis(20));
+    let packet = encode_index_integer(0, 2032).to_vec();
+
+    assert!(receiver.receive(&socket, source, &encode_reliable(7, 1, &packet)).unwrap().is_empty());
+    assert!(receiver.expire_gaps().is_empty());
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(receiver.expire_gaps(), vec![(source, packet.clone())]);
+    // The skipped packet is too late once the ones after it were delivered
+    assert!(receiver.receive(&socket, source, &encode_reliable(7, 0, &packet)).unwrap().is_empty());
+}
+
+#[test]
+fn refuses_signed_and_encrypted_framings() {
+    let address = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let signed = WowIntegerTarget::from_socket_addr(address, 0).with_signing_key(b"key");
+    assert!(ReliableSender::new(signed).is_err());
+    let encrypted = WowIntegerTarget::from_socket_addr(address, 0).with_encryption_key(b"key");
+    assert!(ReliableSender::new(encrypted).is_err());
+}
+
+#[test]
+fn reports_packets_nobody_acknowledges() {
+    // Nothing listens on this port, the retransmits may fail with a refused connection
+    let address = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let options = ReliableOptions::new().with_retransmit_timeout(Duration::from_millis(10)).with_max_attempts(3);
+    let sender = ReliableSender::with_options(WowIntegerTarget::from_socket_addr(address, 2), options).unwrap();
+    sender.send_integer_to_target(1032).unwrap();
+    assert!(sender.wait_for_delivery(Duration::from_secs(2)));
+    let failures = sender.take_failures();
+    assert_eq!(failures.len(), 1);
+    assert_eq!((failures[0].index, failures[0].value, failures[0].attempts), (2, 1032, 3));
+}
@@ -0,0 +1,107 @@
+use std::io::{BufReader, Cursor};
+use std::time::Duration;
+
+use wowint::utility::session::{
+    convert_session, RecordedInteger, SessionFormat, SessionReader, SessionWriter, BINARY_MAGIC, BINARY_VERSION,
+};
+
+fn integers() -> Vec<RecordedInteger> {
+    vec![
+        RecordedInteger::new(Duration::ZERO, 2, 1032),
+        RecordedInteger::new(Duration::from_micros(200_154), 2, 2032),
+        RecordedInteger::new(Duration::from_micros(200_154), -1, i32::MIN),
+        RecordedInteger::new(Duration::from_secs(3600), i32::MAX, -7),
+    ]
+}
+
+fn write(format: SessionFormat, integers: &[RecordedInteger]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    {
+        let mut writer = SessionWriter::new(&mut bytes, format).unwrap();
+        for integer in integers {
+            writer.write(integer).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+    bytes
+}
+
+fn read(bytes: &[u8]) -> (SessionFormat, Vec<RecordedInteger>) {
+    let reader = SessionReader::new(bytes).unwrap();
+    let format = reader.format();
+    (format, reader.collect::<Result<_, _>>().unwrap())
+}
+
+#[test]
+fn json_lines_round_trip() {
+    let bytes = write(SessionFormat::JsonLines, &integers());
+    let text = String::from_utf8(bytes.clone()).unwrap();
+    assert!(text.starts_with("{\"t\":0,\"index\":2,\"value\":1032}\n{\"t\":200154,"));
+    assert_eq!(read(&bytes), (SessionFormat::JsonLines, integers()));
+}
+
+#[test]
+fn binary_round_trip() {
+    let bytes = write(SessionFormat::Binary, &integers());
+    assert_eq!(&bytes[..4], BINARY_MAGIC);
+    assert_eq!(bytes[4], BINARY_VERSION);
+    assert_eq!(read(&bytes), (SessionFormat::Binary, integers()));
+}
+
+#[test]
+fn detects_the_format_from_short_reads() {
+    for format in [SessionFormat::Binary, SessionFormat::JsonLines] {
+        let bytes = write(format, &integers());
+        // Only one byte is buffered at a time
+        let reader = SessionReader::new(BufReader::with_capacity(1, Cursor::new(bytes))).unwrap();
+        assert_eq!(reader.format(), format);
+        assert_eq!(reader.collect::<Result<Vec<_>, _>>().unwrap(), integers());
+    }
+    let (format, empty) = read(b"");
+    assert_eq!(format, SessionFormat::JsonLines);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn rejects_invalid_sessions() {
+    assert!(SessionReader::new(&b"WOWS\x09"[..]).is_err());
+    assert!(SessionReader::new(&b"WOWX\x01"[..]).unwrap().read().is_err());
+    assert!(SessionReader::new(BufReader::with_capacity(1, &b"WOWX\x01"[..])).is_err());
+
+    let mut reader = SessionReader::new(&b"{\"t\":0,\"index\":2,\"value\":1032}\n\nnot json\n"[..]).unwrap();
+    assert!(reader.read().unwrap().is_some());
+    assert!(reader.read().is_err());
+
+    // A delta of u64::MAX microseconds is the longest varint
+    let mut longest = b"WOWS\x01".to_vec();
+    longest.extend([0xFF; 9]);
+    longest.extend([0x01, 0x04, 0x02]);
+    let (_, read_back) = read(&longest);
+    assert_eq!(read_back, vec![RecordedInteger::new(Duration::from_micros(u64::MAX), 2, 1)]);
+
+    for tenth_byte in [0x81, 0x02] {
+        let mut too_long = b"WOWS\x01".to_vec();
+        too_long.extend([0xFF; 9]);
+        too_long.extend([tenth_byte, 0x01, 0x04, 0x02]);
+        let mut reader = SessionReader::new(&too_long[..]).unwrap();
+        assert!(reader.read().is_err(), "tenth byte {:#x}", tenth_byte);
+    }
+
+    let truncated = write(SessionFormat::Binary, &integers());
+    let mut reader = SessionReader::new(&truncated[..truncated.len() - 1]).unwrap();
+    assert_eq!(reader.by_ref().take(3).count(), 3);
+    assert!(reader.read().is_err());
+}
+
+#[test]
+fn converts_between_formats() {
+    let json = write(SessionFormat::JsonLines, &integers());
+    let mut binary = Vec::new();
+    assert_eq!(convert_session(&json[..], &mut binary, SessionFormat::Binary).unwrap(), 4);
+    assert_eq!(binary, write(SessionFormat::Binary, &integers()));
+    assert!(binary.len() < json.len());
+
+    let mut back = Vec::new();
+    assert_eq!(convert_session(&binary[..], &mut back, SessionFormat::JsonLines).unwrap(), 4);
+    assert_eq!(back, json);
+}
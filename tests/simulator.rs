@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use wowint::utility::simulator::VirtualPlayer;
+
+const NUMPAD1: i32 = 0x61;
+const NUMPAD3: i32 = 0x63;
+const NUMPAD4: i32 = 0x64;
+const NUMPAD6: i32 = 0x66;
+const NUMPAD8: i32 = 0x68;
+
+fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+    let distance = ((actual.0 - expected.0).powi(2) + (actual.1 - expected.1).powi(2)).sqrt();
+    assert!(distance < 0.01, "position {:?}, expected {:?}", actual, expected);
+}
+
+/// Presses the key, holds it for `seconds` then releases it.
+fn hold(player: &mut VirtualPlayer, key: i32, seconds: f32) {
+    player.apply(1000 + key);
+    player.advance(Duration::from_secs_f32(seconds));
+    player.apply(2000 + key);
+}
+
+#[test]
+fn moves_forward_while_the_key_is_held() {
+    let mut player = VirtualPlayer::new();
+    hold(&mut player, NUMPAD8, 3.0);
+    player.advance(Duration::from_secs(1));
+    assert_near(player.position(), (0.0, 3.0));
+    assert_eq!(player.heading(), 0.0);
+    assert!(player.is_idle());
+}
+
+#[test]
+fn numpad4_and_numpad6_turn_without_moving() {
+    let mut player = VirtualPlayer::new();
+    hold(&mut player, NUMPAD6, 0.5);
+    assert_near(player.position(), (0.0, 0.0));
+    assert!((player.heading() - 90.0).abs() < 0.01);
+    hold(&mut player, NUMPAD4, 1.0);
+    assert!((player.heading() - 270.0).abs() < 0.01);
+}
+
+#[test]
+fn numpad1_and_numpad3_strafe() {
+    let mut player = VirtualPlayer::new();
+    hold(&mut player, NUMPAD3, 2.0);
+    assert_near(player.position(), (2.0, 0.0));
+    hold(&mut player, NUMPAD1, 1.0);
+    assert_near(player.position(), (1.0, 0.0));
+    assert_eq!(player.heading(), 0.0);
+}
+
+#[test]
+fn macro_turns_then_moves_forward() {
+    let mut player = VirtualPlayer::new().with_speed(2.0);
+    hold(&mut player, NUMPAD8, 1.0);
+    // Quarter turn to the right, then forward along +x
+    hold(&mut player, NUMPAD6, 0.5);
+    hold(&mut player, NUMPAD8, 1.5);
+    // Strafing right while looking to +x goes to -y
+    hold(&mut player, NUMPAD3, 0.5);
+    assert_near(player.position(), (3.0, 1.0));
+    assert!((player.heading() - 90.0).abs() < 0.01);
+    assert_eq!(player.time(), Duration::from_millis(3500));
+    assert!(player.held_keys().is_empty());
+}
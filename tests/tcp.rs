@@ -0,0 +1,102 @@
+use std::io::{self, Cursor};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use wowint::utility::error::WowIntError;
+use wowint::utility::tcp::{read_frame, write_frame, WowIntegerTcpListener, WowIntegerTcpTarget, MAX_FRAME_SIZE};
+use wowint::utility::{encode_index_integer, IntegerSender};
+
+#[test]
+fn reads_back_written_frames() {
+    let mut stream = Vec::new();
+    write_frame(&mut stream, &encode_index_integer(2, 1032)).unwrap();
+    write_frame(&mut stream, &[]).unwrap();
+    let mut reader = Cursor::new(stream);
+    assert_eq!(read_frame(&mut reader).unwrap(), Some(encode_index_integer(2, 1032).to_vec()));
+    assert_eq!(read_frame(&mut reader).unwrap(), Some(Vec::new()));
+    assert_eq!(read_frame(&mut reader).unwrap(), None);
+}
+
+#[test]
+fn eof_inside_a_frame_is_an_error() {
+    let mut stream = Vec::new();
+    write_frame(&mut stream, &encode_index_integer(2, 1032)).unwrap();
+    for end in [2, 6] {
+        let result = read_frame(&mut Cursor::new(&stream[..end]));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof, "closed after {} bytes", end);
+    }
+    let too_large = ((MAX_FRAME_SIZE + 1) as u32).to_le_bytes();
+    assert!(matches!(read_frame(&mut Cursor::new(too_large)), Err(WowIntError::PayloadTooLarge { .. })));
+}
+
+fn spawn_listener() -> (SocketAddr, mpsc::Receiver<(i32, i32)>) {
+    let listener = WowIntegerTcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    thread::spawn(move || listener.run(move |_, index, value| sender.lock().unwrap().send((index, value)).unwrap()));
+    (address, receiver)
+}
+
+#[test]
+fn listener_receives_integers_in_order() {
+    let (address, received) = spawn_listener();
+    let target = WowIntegerTcpTarget::new("127.0.0.1", address.port(), 4);
+    assert!(!target.is_connected());
+    target.send_integer_to_target(1032).unwrap();
+    target.send_integer_to_target_at_index(5, 2032).unwrap();
+    assert!(target.is_connected());
+    let timeout = Duration::from_secs(2);
+    assert_eq!(received.recv_timeout(timeout).unwrap(), (4, 1032));
+    assert_eq!(received.recv_timeout(timeout).unwrap(), (5, 2032));
+}
+
+#[test]
+fn reconnects_when_the_player_starts_late() {
+    let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let player = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        let listener = TcpListener::bind(address).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        read_frame(&mut stream).unwrap()
+    });
+    let target = WowIntegerTcpTarget::new("127.0.0.1", address.port(), 1).with_reconnect(
+        20,
+        Duration::from_millis(20),
+        Duration::from_millis(50),
+    );
+    target.send_integer_to_target(1032).unwrap();
+    assert_eq!(player.join().unwrap(), Some(encode_index_integer(1, 1032).to_vec()));
+}
+
+#[test]
+fn fails_after_the_last_attempt_without_blocking_other_calls() {
+    let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let target = Arc::new(WowIntegerTcpTarget::new("127.0.0.1", address.port(), 1).with_reconnect(
+        3,
+        Duration::from_millis(200),
+        Duration::from_millis(200),
+    ));
+    let sending = Arc::clone(&target);
+    let send = thread::spawn(move || sending.send_integer_to_target(1032));
+    thread::sleep(Duration::from_millis(50));
+    let start = Instant::now();
+    assert!(!target.is_connected());
+    assert!(start.elapsed() < Duration::from_millis(100));
+    assert!(matches!(send.join().unwrap(), Err(WowIntError::Send { value: 1032, .. })));
+}
+
+#[test]
+fn invalid_frames_close_the_connection_with_an_error() {
+    let mut listener = WowIntegerTcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (errors, received) = mpsc::channel();
+    let errors = Mutex::new(errors);
+    listener.set_error_handler(move |error| errors.lock().unwrap().send(error.to_string()).unwrap());
+    thread::spawn(move || listener.run(|_, _, _| {}));
+    let mut stream = TcpStream::connect(address).unwrap();
+    write_frame(&mut stream, &[1, 2, 3]).unwrap();
+    assert_eq!(received.recv_timeout(Duration::from_secs(2)).unwrap(), "Invalid TCP frame: 3 bytes");
+}
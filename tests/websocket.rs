@@ -0,0 +1,80 @@
+use std::net::UdpSocket;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::client::IntoClientRequest;
+use tungstenite::Message;
+use wowint::utility::websocket::WowIntegerWebSocketBridge;
+use wowint::utility::{decode_index_integer, encode_index_integer, WowIntegerTarget};
+
+fn receiver() -> UdpSocket {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    socket
+}
+
+#[test]
+fn forwards_text_and_binary_frames() {
+    let receiver = receiver();
+    let target = WowIntegerTarget::new("127.0.0.1", receiver.local_addr().unwrap().port(), 7);
+    let bridge = WowIntegerWebSocketBridge::bind("127.0.0.1:0", target).unwrap();
+    let address = bridge.local_addr().unwrap();
+    thread::spawn(move || bridge.run());
+
+    let (mut client, _) = tungstenite::connect(format!("ws://{}", address)).unwrap();
+    client.send(Message::Text("1032".into())).unwrap();
+    client.send(Message::Text("2:2032".into())).unwrap();
+    client.send(Message::Binary(encode_index_integer(3, 1040).to_vec().into())).unwrap();
+
+    let mut buf = [0u8; 16];
+    for expected in [(7, 1032), (2, 2032), (3, 1040)] {
+        let size = receiver.recv(&mut buf).unwrap();
+        assert_eq!(decode_index_integer(&buf[..size]), Some(expected));
+    }
+}
+
+#[test]
+fn rejects_origins_not_allowed() {
+    let receiver = receiver();
+    let target = WowIntegerTarget::new("127.0.0.1", receiver.local_addr().unwrap().port(), 0);
+    let mut bridge = WowIntegerWebSocketBridge::bind("127.0.0.1:0", target).unwrap();
+    bridge.allow_origin("http://allowed.local");
+    let address = bridge.local_addr().unwrap();
+    thread::spawn(move || bridge.run());
+
+    let mut request = format!("ws://{}", address).into_client_request().unwrap();
+    request.headers_mut().insert("Origin", "http://other.local".parse().unwrap());
+    assert!(tungstenite::connect(request).is_err());
+
+    let mut request = format!("ws://{}", address).into_client_request().unwrap();
+    request.headers_mut().insert("Origin", "http://allowed.local".parse().unwrap());
+    let (mut client, _) = tungstenite::connect(request).unwrap();
+    client.send(Message::Text("1032".into())).unwrap();
+    let mut buf = [0u8; 16];
+    let size = receiver.recv(&mut buf).unwrap();
+    assert_eq!(decode_index_integer(&buf[..size]), Some((0, 1032)));
+}
+
+#[test]
+fn reports_messages_that_are_not_integers() {
+    let receiver = receiver();
+    let target = WowIntegerTarget::new("127.0.0.1", receiver.local_addr().unwrap().port(), 0);
+    let mut bridge = WowIntegerWebSocketBridge::bind("127.0.0.1:0", target).unwrap();
+    let (errors, reported) = mpsc::channel();
+    let errors = Mutex::new(errors);
+    bridge.set_error_handler(move |error| errors.lock().unwrap().send(error.to_string()).unwrap());
+    let address = bridge.local_addr().unwrap();
+    thread::spawn(move || bridge.run());
+
+    let (mut client, _) = tungstenite::connect(format!("ws://{}", address)).unwrap();
+    client.send(Message::Text("jump".into())).unwrap();
+    client.send(Message::Text("1032".into())).unwrap();
+    let error = reported.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert!(error.starts_with("Invalid WebSocket message: "), "{}", error);
+    assert!(error.contains("jump"), "{}", error);
+    // The connection stays open
+    let mut buf = [0u8; 16];
+    let size = receiver.recv(&mut buf).unwrap();
+    assert_eq!(decode_index_integer(&buf[..size]), Some((0, 1032)));
+}